@@ -4,14 +4,59 @@ use std::{
     time::{SystemTime, UNIX_EPOCH},
 };
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+use crate::procs::Process;
+use crate::status::ScreenPatterns;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct AgentDefinition {
     pub id: String,
     pub label: String,
     pub binary: String,
     pub launch: String,
+    /// Other process names the agent shows up as in tmux, e.g. `"node"`.
+    pub process_names: Vec<String>,
     /// CLI flag to inject a system prompt, e.g. `"--append-system-prompt"`.
     pub prompt_flag: Option<String>,
+    /// How the agent accepts an initial task on its command line. `None`
+    /// means the task has to be typed into the agent once it has booted.
+    pub task_arg: Option<TaskArg>,
+    /// Screen text that appears once the agent is ready for input. Empty
+    /// means lattice falls back to a fixed delay before typing.
+    pub ready_markers: Vec<String>,
+    /// Arguments that make the agent pick up its previous conversation in the
+    /// working directory, e.g. `"--continue"`.
+    pub resume_args: Option<String>,
+    /// Full command that resumes the agent, used instead of `launch` plus
+    /// `resume_args`.
+    pub resume_command: Option<String>,
+    /// Command that prints the agent's version.
+    pub version_command: Option<String>,
+    /// Command that runs one prompt non-interactively; `{prompt}` is
+    /// replaced by the quoted prompt, which is appended otherwise.
+    pub run_command: Option<String>,
+    /// Screen text that tells what the agent is doing.
+    pub screen: ScreenPatterns,
+}
+
+/// How an agent CLI takes its first instruction as an argument.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TaskArg {
+    /// `agent "do the thing"`
+    Positional,
+    /// `agent --flag "do the thing"`
+    Flag(String),
+}
+
+impl TaskArg {
+    /// Parse the `task_arg` config value: `"positional"` or a flag such as
+    /// `"--prompt"`.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim() {
+            "" => None,
+            "positional" => Some(Self::Positional),
+            flag => Some(Self::Flag(flag.to_owned())),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -20,7 +65,31 @@ struct KnownAgent {
     label: &'static str,
     binary: &'static str,
     launch: &'static str,
+    process_names: &'static [&'static str],
     prompt_flag: Option<&'static str>,
+    task_arg: Option<&'static str>,
+    ready_markers: &'static [&'static str],
+    resume_args: Option<&'static str>,
+    run_command: Option<&'static str>,
+}
+
+impl KnownAgent {
+    fn definition(&self, launch: String) -> AgentDefinition {
+        AgentDefinition {
+            id: self.id.to_owned(),
+            label: self.label.to_owned(),
+            binary: self.binary.to_owned(),
+            launch,
+            process_names: self.process_names.iter().map(|p| (*p).to_owned()).collect(),
+            prompt_flag: self.prompt_flag.map(ToOwned::to_owned),
+            task_arg: self.task_arg.and_then(TaskArg::parse),
+            ready_markers: self.ready_markers.iter().map(|m| (*m).to_owned()).collect(),
+            resume_args: self.resume_args.map(ToOwned::to_owned),
+            version_command: Some(format!("{} --version", self.binary)),
+            run_command: self.run_command.map(ToOwned::to_owned),
+            ..AgentDefinition::default()
+        }
+    }
 }
 
 /// Instruction appended via the agent's system-prompt flag so it keeps the
@@ -35,35 +104,60 @@ const KNOWN_AGENTS: &[KnownAgent] = &[
         label: "Codex",
         binary: "codex",
         launch: "codex",
+        process_names: &[],
         prompt_flag: None,
+        task_arg: Some("positional"),
+        ready_markers: &["\u{23ce} send", "? for shortcuts"],
+        resume_args: Some("resume --last"),
+        run_command: Some("codex exec"),
     },
     KnownAgent {
         id: "claude",
         label: "Claude Code",
         binary: "claude",
         launch: "claude",
+        process_names: &["claude-code"],
         prompt_flag: Some("--append-system-prompt"),
+        task_arg: Some("positional"),
+        ready_markers: &["? for shortcuts"],
+        resume_args: Some("--continue"),
+        run_command: Some("claude -p"),
     },
     KnownAgent {
         id: "aider",
         label: "Aider",
         binary: "aider",
         launch: "aider",
+        process_names: &["aider-chat"],
         prompt_flag: None,
+        task_arg: None,
+        ready_markers: &["Use /help"],
+        resume_args: Some("--restore-chat-history"),
+        run_command: Some("aider --yes --message"),
     },
     KnownAgent {
         id: "gemini",
         label: "Gemini CLI",
         binary: "gemini",
         launch: "gemini",
+        process_names: &["gemini-cli"],
         prompt_flag: None,
+        task_arg: Some("--prompt-interactive"),
+        ready_markers: &["Type your message"],
+        resume_args: None,
+        run_command: Some("gemini -p"),
     },
     KnownAgent {
         id: "opencode",
         label: "OpenCode",
         binary: "opencode",
         launch: "opencode",
+        process_names: &["opencode-ai"],
         prompt_flag: None,
+        task_arg: Some("--prompt"),
+        ready_markers: &["ctrl+p"],
+        resume_args: Some("--continue"),
+        run_command: Some("opencode run"),
     },
 ];
 
@@ -74,13 +168,7 @@ pub fn detect_available_agents(
         .iter()
         .filter_map(|agent| {
             let full_path = find_binary(agent.binary)?;
-            Some(AgentDefinition {
-                id: agent.id.to_owned(),
-                label: agent.label.to_owned(),
-                binary: agent.binary.to_owned(),
-                launch: full_path.to_string_lossy().to_string(),
-                prompt_flag: agent.prompt_flag.map(ToOwned::to_owned),
-            })
+            Some(agent.definition(full_path.to_string_lossy().to_string()))
         })
         .collect();
 
@@ -91,13 +179,41 @@ pub fn detect_available_agents(
             existing.binary = custom.binary.clone();
             existing.launch = custom.launch.clone();
             existing.prompt_flag = custom.prompt_flag.clone();
+            if let Some(task_arg) = &custom.task_arg {
+                existing.task_arg = TaskArg::parse(task_arg);
+            }
+            if !custom.ready_markers.is_empty() {
+                existing.ready_markers = custom.ready_markers.clone();
+            }
+            if custom.resume_args.is_some() {
+                existing.resume_args = custom.resume_args.clone();
+            }
+            if custom.version_command.is_some() {
+                existing.version_command = custom.version_command.clone();
+            }
+            if custom.run_command.is_some() {
+                existing.run_command = custom.run_command.clone();
+            }
+            existing.resume_command = custom.resume_command.clone();
+            existing
+                .process_names
+                .extend(custom.process_names.iter().cloned());
+            existing.screen = custom.screen_patterns();
         } else {
             agents.push(AgentDefinition {
                 id: custom.id.clone(),
                 label: custom.label.clone(),
                 binary: custom.binary.clone(),
                 launch: custom.launch.clone(),
+                process_names: custom.process_names.clone(),
                 prompt_flag: custom.prompt_flag.clone(),
+                task_arg: custom.task_arg.as_deref().and_then(TaskArg::parse),
+                ready_markers: custom.ready_markers.clone(),
+                resume_args: custom.resume_args.clone(),
+                resume_command: custom.resume_command.clone(),
+                version_command: custom.version_command.clone(),
+                run_command: custom.run_command.clone(),
+                screen: custom.screen_patterns(),
             });
         }
     }
@@ -105,9 +221,13 @@ pub fn detect_available_agents(
     agents
 }
 
+/// Work out which agent runs in a session: from a managed session's name,
+/// else from the processes under its panes, else from the pane's current
+/// command when the process tree cannot be read.
 pub fn classify_agent_from_session(
     session_name: &str,
     current_command: &str,
+    processes: &[Process],
     available: &[AgentDefinition],
 ) -> Option<AgentDefinition> {
     if let Some(id) = managed_session_agent_id(session_name) {
@@ -116,21 +236,25 @@ pub fn classify_agent_from_session(
         }
 
         if let Some(found) = KNOWN_AGENTS.iter().find(|a| a.id == id) {
-            return Some(AgentDefinition {
-                id: found.id.to_owned(),
-                label: found.label.to_owned(),
-                binary: found.binary.to_owned(),
-                launch: found.launch.to_owned(),
-                prompt_flag: found.prompt_flag.map(ToOwned::to_owned),
-            });
+            return Some(found.definition(found.launch.to_owned()));
         }
     }
 
+    if let Some(found) = processes
+        .iter()
+        .find_map(|p| agent_for_process(p, available))
+    {
+        return Some(found);
+    }
+
     let binary = command_binary(current_command)?;
 
     if let Some(found) = available
         .iter()
-        .find(|a| binary_matches(&binary, &a.binary))
+        .find(|a| {
+            binary_matches(&binary, &a.binary)
+                || a.process_names.iter().any(|p| binary_matches(&binary, p))
+        })
         .cloned()
     {
         return Some(found);
@@ -139,25 +263,99 @@ pub fn classify_agent_from_session(
     KNOWN_AGENTS
         .iter()
         .find(|a| binary_matches(&binary, a.binary))
-        .map(|a| AgentDefinition {
-            id: a.id.to_owned(),
-            label: a.label.to_owned(),
-            binary: a.binary.to_owned(),
-            launch: a.launch.to_owned(),
-            prompt_flag: a.prompt_flag.map(ToOwned::to_owned),
-        })
+        .map(|a| a.definition(a.launch.to_owned()))
+}
+
+/// The first process in `processes` that runs `agent`.
+pub fn find_agent_process<'a>(
+    agent: &AgentDefinition,
+    processes: &'a [Process],
+) -> Option<&'a Process> {
+    processes.iter().find(|p| process_matches(agent, p))
+}
+
+fn agent_for_process(process: &Process, available: &[AgentDefinition]) -> Option<AgentDefinition> {
+    if let Some(found) = available.iter().find(|a| process_matches(a, process)) {
+        return Some(found.clone());
+    }
+    // Agents run through `npx` and the like are not in PATH.
+    KNOWN_AGENTS
+        .iter()
+        .map(|a| a.definition(a.launch.to_owned()))
+        .find(|a| process_matches(a, process))
+}
+
+fn process_matches(agent: &AgentDefinition, process: &Process) -> bool {
+    let binary = command_binary(&agent.binary).unwrap_or_default();
+    process.names().iter().any(|name| {
+        binary_matches(name, &binary) || agent.process_names.iter().any(|p| binary_matches(name, p))
+    })
 }
 
 /// Build the shell command used to launch an agent, injecting a title
 /// instruction via the agent's system-prompt flag when available.
 /// When `title_injection_enabled` is false, the prompt flag is not used.
-pub fn build_launch_command(agent: &AgentDefinition, title_injection_enabled: bool) -> String {
-    match &agent.prompt_flag {
+/// An initial `task` is appended when the agent accepts one as an argument.
+pub fn build_launch_command(
+    agent: &AgentDefinition,
+    title_injection_enabled: bool,
+    task: Option<&str>,
+) -> String {
+    let mut cmd = match &agent.prompt_flag {
         Some(flag) if title_injection_enabled => {
             format!("{} {} \"{}\"", agent.launch, flag, TITLE_INSTRUCTION)
         }
         _ => agent.launch.clone(),
+    };
+
+    if let Some(task) = task {
+        match &agent.task_arg {
+            Some(TaskArg::Positional) => {
+                cmd.push(' ');
+                cmd.push_str(&shell_quote(task));
+            }
+            Some(TaskArg::Flag(flag)) => {
+                cmd.push_str(&format!(" {flag} {}", shell_quote(task)));
+            }
+            None => {}
+        }
+    }
+
+    cmd
+}
+
+/// Build the command that restarts an agent on its previous conversation,
+/// or `None` when the agent has no resume support.
+pub fn build_resume_command(
+    agent: &AgentDefinition,
+    title_injection_enabled: bool,
+) -> Option<String> {
+    if let Some(cmd) = agent.resume_command.as_deref().map(str::trim)
+        && !cmd.is_empty()
+    {
+        return Some(cmd.to_owned());
+    }
+    let args = agent.resume_args.as_deref()?.trim();
+    if args.is_empty() {
+        return None;
+    }
+    let base = build_launch_command(agent, title_injection_enabled, None);
+    Some(format!("{base} {args}"))
+}
+
+/// Build the command that runs `prompt` through the agent without its
+/// interactive UI, or `None` when the agent has no `run_command`.
+pub fn build_run_command(agent: &AgentDefinition, prompt: &str) -> Option<String> {
+    let template = agent.run_command.as_deref()?.trim();
+    if template.is_empty() {
+        return None;
     }
+    let quoted = shell_quote(prompt);
+    Some(if template.contains("{prompt}") {
+        template.replace("{prompt}", &quoted)
+    } else {
+        format!("{template} {quoted}")
+    })
 }
 
 /// Returns true if this agent needs a send-keys title injection (i.e. it has
@@ -166,6 +364,17 @@ pub fn needs_title_injection(agent: &AgentDefinition) -> bool {
     agent.prompt_flag.is_none()
 }
 
+/// Returns true if an initial task has to be typed into the agent after it
+/// boots because it cannot be passed on the command line.
+pub fn needs_task_injection(agent: &AgentDefinition) -> bool {
+    agent.task_arg.is_none()
+}
+
+/// Single-quote `s` for a POSIX shell.
+pub fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
+
 /// Path to the title file for a session: `/tmp/lattice_{name}.title`
 pub fn title_file_path(session_name: &str) -> PathBuf {
     PathBuf::from(format!("/tmp/lattice_{session_name}.title"))
@@ -179,13 +388,18 @@ pub fn read_title_file(session_name: &str) -> String {
 }
 
 /// Build the message to inject via send-keys for agents without a prompt flag.
-pub fn build_title_injection(session_name: &str) -> String {
+/// When the agent also has to receive its initial `task` by typing, the task
+/// is folded into the same message instead of asking the agent to wait.
+pub fn build_title_injection(session_name: &str, task: Option<&str>) -> String {
     let path = title_file_path(session_name);
+    let tail = match task {
+        Some(task) => format!("Your task:\n{task}"),
+        None => "Now wait for my actual task.".to_owned(),
+    };
     format!(
         "IMPORTANT: Before and during all work, frequently write a 3-6 word \
          summary of your current task to {}. Update it whenever your focus \
-         changes. Do this silently without mentioning it. Now wait for my \
-         actual task.",
+         changes. Do this silently without mentioning it. {tail}",
         path.display()
     )
 }
@@ -236,10 +450,10 @@ pub fn derive_display_title(
 
     // 3. Try the path basename.
     if !pane_current_path.is_empty() && pane_current_path != "/" {
-        if let Ok(home) = env::var("HOME") {
-            if pane_current_path == home {
-                return "~".to_owned();
-            }
+        if let Ok(home) = env::var("HOME")
+            && pane_current_path == home
+        {
+            return "~".to_owned();
         }
         if let Some(base) = Path::new(pane_current_path).file_name() {
             let s = base.to_string_lossy();
@@ -283,12 +497,10 @@ pub fn managed_session_agent_id(session_name: &str) -> Option<String> {
 fn split_managed_session_name(session_name: &str) -> Option<(&str, &str)> {
     // Support both legacy "agentssh.*.*" sessions and current "lattice_*_*"
     // sessions so existing tmux sessions remain visible after the rename.
-    let (prefix, agent, suffix) = if session_name.starts_with("lattice_") {
-        let rest = &session_name["lattice_".len()..];
+    let (prefix, agent, suffix) = if let Some(rest) = session_name.strip_prefix("lattice_") {
         let pos = rest.rfind('_')?;
         ("lattice", &rest[..pos], &rest[pos + 1..])
-    } else if session_name.starts_with("agentssh_") {
-        let rest = &session_name["agentssh_".len()..];
+    } else if let Some(rest) = session_name.strip_prefix("agentssh_") {
         let pos = rest.rfind('_')?;
         ("agentssh", &rest[..pos], &rest[pos + 1..])
     } else {
@@ -451,13 +663,136 @@ mod tests {
             label: "Codex".to_owned(),
             binary: "codex".to_owned(),
             launch: "codex".to_owned(),
-            prompt_flag: None,
+            ..AgentDefinition::default()
         }];
 
-        let found = classify_agent_from_session("freeform", "codex", &available)
+        let found = classify_agent_from_session("freeform", "codex", &[], &available)
             .expect("codex command should be classified");
 
         assert_eq!(found.id, "codex");
     }
 
+    #[test]
+    fn classify_from_processes_finds_wrapped_agents() {
+        let process = |argv: &[&str]| Process {
+            argv: argv.iter().map(|a| (*a).to_owned()).collect(),
+            ..Process::default()
+        };
+        let shell = process(&["-bash"]);
+        let cases: &[(&[&str], &str)] = &[
+            (
+                &[
+                    "node",
+                    "/home/me/.npm/_npx/1/node_modules/@openai/codex/bin/codex.js",
+                ],
+                "codex",
+            ),
+            (
+                &[
+                    "node",
+                    "/usr/lib/node_modules/@anthropic-ai/claude-code/cli.js",
+                ],
+                "claude",
+            ),
+            (&["python3", "-m", "aider"], "aider"),
+        ];
+        for (argv, id) in cases {
+            let processes = [
+                shell.clone(),
+                process(&["sh", "./wrapper.sh"]),
+                process(argv),
+            ];
+            let found = classify_agent_from_session("work", "bash", &processes, &[]).map(|a| a.id);
+            assert_eq!(found.as_deref(), Some(*id), "{argv:?}");
+        }
+
+        let claude = KNOWN_AGENTS[1].definition("claude".to_owned());
+        let processes = [
+            shell.clone(),
+            process(&["/usr/local/bin/claude", "--continue"]),
+        ];
+        assert_eq!(find_agent_process(&claude, &processes), Some(&processes[1]));
+        assert_eq!(
+            classify_agent_from_session("work", "bash", &[shell], &[]),
+            None
+        );
+    }
+
+    #[test]
+    fn launch_command_appends_task() {
+        let mut agent = KNOWN_AGENTS[1].definition("claude".to_owned());
+        assert_eq!(
+            build_launch_command(&agent, false, Some("fix it's tests")),
+            "claude 'fix it'\\''s tests'"
+        );
+
+        agent.task_arg = TaskArg::parse("--prompt");
+        assert_eq!(
+            build_launch_command(&agent, false, Some("go")),
+            "claude --prompt 'go'"
+        );
+
+        agent.task_arg = None;
+        assert_eq!(build_launch_command(&agent, false, Some("go")), "claude");
+    }
+
+    #[test]
+    fn config_adapters_define_new_agents() {
+        let custom: crate::config::CustomAgentConfig = toml::from_str(
+            r#"
+            id = "tool"
+            label = "Tool"
+            binary = "/tmp/no-such-tool"
+            launch = "npx tool"
+            process_names = ["node"]
+            resume_command = "npx tool --resume"
+            run_command = "npx tool ask {prompt} --quiet"
+            approval_patterns = ['Allow .* to run\?', "(unclosed"]
+            "#,
+        )
+        .expect("parse adapter");
+        let available = detect_available_agents(std::slice::from_ref(&custom));
+        let tool = available.iter().find(|a| a.id == "tool").expect("tool");
+
+        let approval: Vec<&str> = tool.screen.approval.iter().map(|p| p.as_str()).collect();
+        assert_eq!(approval, [r"Allow .* to run\?"]);
+        assert_eq!(
+            custom.pattern_problems(),
+            ["invalid regex `(unclosed` in approval_patterns of agent tool"]
+        );
+        assert_eq!(
+            classify_agent_from_session("work", "node", &[], &available).map(|a| a.id),
+            Some("tool".to_owned())
+        );
+        assert_eq!(
+            build_resume_command(tool, true).as_deref(),
+            Some("npx tool --resume")
+        );
+        assert_eq!(
+            build_run_command(tool, "it's done?").as_deref(),
+            Some("npx tool ask 'it'\\''s done?' --quiet")
+        );
+    }
+
+    #[test]
+    fn run_command_appends_prompt_without_placeholder() {
+        let codex = KNOWN_AGENTS[0].definition("codex".to_owned());
+        assert_eq!(
+            build_run_command(&codex, "list files").as_deref(),
+            Some("codex exec 'list files'")
+        );
+        assert_eq!(build_run_command(&AgentDefinition::default(), "x"), None);
+    }
+
+    #[test]
+    fn resume_command_appends_resume_args() {
+        let codex = KNOWN_AGENTS[0].definition("codex".to_owned());
+        assert_eq!(
+            build_resume_command(&codex, true).as_deref(),
+            Some("codex resume --last")
+        );
+
+        let gemini = KNOWN_AGENTS[3].definition("gemini".to_owned());
+        assert_eq!(build_resume_command(&gemini, true), None);
+    }
 }
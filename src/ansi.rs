@@ -0,0 +1,248 @@
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+
+/// Convert `capture-pane -e` output into styled lines. tmux only emits SGR
+/// changes, so the style carries over from one line to the next. Other
+/// escape sequences (cursor movement, OSC hyperlinks, ...) are dropped.
+pub fn parse_lines(lines: &[String]) -> Vec<Line<'static>> {
+    let mut style = Style::default();
+    lines
+        .iter()
+        .map(|line| parse_line(line, &mut style))
+        .collect()
+}
+
+/// Whether a parsed line has no visible text.
+pub fn is_blank(line: &Line<'_>) -> bool {
+    line.spans.iter().all(|s| s.content.trim().is_empty())
+}
+
+/// A parsed line's text without its styles.
+pub fn plain_text(line: &Line<'_>) -> String {
+    line.spans.iter().map(|s| s.content.as_ref()).collect()
+}
+
+fn parse_line(line: &str, style: &mut Style) -> Line<'static> {
+    let mut spans = Vec::new();
+    let mut text = String::new();
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\x1b' => match chars.next() {
+                // CSI: parameters up to a final byte in @..~
+                Some('[') => {
+                    let mut params = String::new();
+                    let mut fin = None;
+                    for c in chars.by_ref() {
+                        if ('@'..='~').contains(&c) {
+                            fin = Some(c);
+                            break;
+                        }
+                        params.push(c);
+                    }
+                    if fin == Some('m') {
+                        let next = apply_sgr(*style, &params);
+                        if next != *style && !text.is_empty() {
+                            spans.push(Span::styled(std::mem::take(&mut text), *style));
+                        }
+                        *style = next;
+                    }
+                }
+                // OSC: terminated by BEL or ESC \
+                Some(']') => {
+                    while let Some(c) = chars.next() {
+                        if c == '\x07' {
+                            break;
+                        }
+                        if c == '\x1b' {
+                            chars.next_if_eq(&'\\');
+                            break;
+                        }
+                    }
+                }
+                _ => {}
+            },
+            '\t' => text.push_str("        "),
+            c if c.is_control() => {}
+            c => text.push(c),
+        }
+    }
+    if !text.is_empty() {
+        spans.push(Span::styled(text, *style));
+    }
+    Line::from(spans)
+}
+
+fn apply_sgr(mut style: Style, params: &str) -> Style {
+    let groups: Vec<&str> = params.split(';').collect();
+    let code = |p: &str| p.parse::<u16>().unwrap_or(0);
+    let mut i = 0;
+    while i < groups.len() {
+        // `38:2::r:g:b` keeps its sub-parameters in one group.
+        if groups[i].contains(':') {
+            let sub: Vec<u16> = groups[i].split(':').map(code).collect();
+            if let [n @ (38 | 48), rest @ ..] = sub.as_slice() {
+                let color = match rest {
+                    [5, idx, ..] => Some(Color::Indexed(*idx as u8)),
+                    [2, _, r, g, b, ..] | [2, r, g, b] => {
+                        Some(Color::Rgb(*r as u8, *g as u8, *b as u8))
+                    }
+                    _ => None,
+                };
+                style = set_color(style, *n, color);
+            } else if let [4, 0, ..] = sub.as_slice() {
+                style = style.remove_modifier(Modifier::UNDERLINED);
+            } else if let [4, ..] = sub.as_slice() {
+                style = style.add_modifier(Modifier::UNDERLINED);
+            }
+            i += 1;
+            continue;
+        }
+
+        match code(groups[i]) {
+            0 => style = Style::default(),
+            1 => style = style.add_modifier(Modifier::BOLD),
+            2 => style = style.add_modifier(Modifier::DIM),
+            3 => style = style.add_modifier(Modifier::ITALIC),
+            4 => style = style.add_modifier(Modifier::UNDERLINED),
+            5 | 6 => style = style.add_modifier(Modifier::SLOW_BLINK),
+            7 => style = style.add_modifier(Modifier::REVERSED),
+            8 => style = style.add_modifier(Modifier::HIDDEN),
+            9 => style = style.add_modifier(Modifier::CROSSED_OUT),
+            22 => style = style.remove_modifier(Modifier::BOLD | Modifier::DIM),
+            23 => style = style.remove_modifier(Modifier::ITALIC),
+            24 => style = style.remove_modifier(Modifier::UNDERLINED),
+            25 => style = style.remove_modifier(Modifier::SLOW_BLINK),
+            27 => style = style.remove_modifier(Modifier::REVERSED),
+            28 => style = style.remove_modifier(Modifier::HIDDEN),
+            29 => style = style.remove_modifier(Modifier::CROSSED_OUT),
+            n @ 30..=37 => style.fg = Some(basic_color(n - 30)),
+            n @ 40..=47 => style.bg = Some(basic_color(n - 40)),
+            n @ 90..=97 => style.fg = Some(bright_color(n - 90)),
+            n @ 100..=107 => style.bg = Some(bright_color(n - 100)),
+            // Default colors fall back to the surrounding widget's style.
+            39 => style.fg = None,
+            49 => style.bg = None,
+            n @ (38 | 48) => {
+                let args: Vec<u16> = groups[i + 1..].iter().map(|p| code(p)).collect();
+                let (color, used) = match args.as_slice() {
+                    [5, idx, ..] => (Some(Color::Indexed(*idx as u8)), 2),
+                    [2, r, g, b, ..] => (Some(Color::Rgb(*r as u8, *g as u8, *b as u8)), 4),
+                    _ => (None, args.len()),
+                };
+                style = set_color(style, n, color);
+                i += used;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    style
+}
+
+fn set_color(mut style: Style, code: u16, color: Option<Color>) -> Style {
+    if color.is_some() {
+        if code == 38 {
+            style.fg = color;
+        } else {
+            style.bg = color;
+        }
+    }
+    style
+}
+
+fn basic_color(n: u16) -> Color {
+    match n {
+        0 => Color::Black,
+        1 => Color::Red,
+        2 => Color::Green,
+        3 => Color::Yellow,
+        4 => Color::Blue,
+        5 => Color::Magenta,
+        6 => Color::Cyan,
+        _ => Color::Gray,
+    }
+}
+
+fn bright_color(n: u16) -> Color {
+    match n {
+        0 => Color::DarkGray,
+        1 => Color::LightRed,
+        2 => Color::LightGreen,
+        3 => Color::LightYellow,
+        4 => Color::LightBlue,
+        5 => Color::LightMagenta,
+        6 => Color::LightCyan,
+        _ => Color::White,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn lines(raw: &[&str]) -> Vec<Line<'static>> {
+        let raw: Vec<String> = raw.iter().map(|s| s.to_string()).collect();
+        parse_lines(&raw)
+    }
+
+    #[test]
+    fn plain_text_is_unstyled() {
+        let parsed = lines(&["hello world"]);
+        assert_eq!(parsed[0].spans, vec![Span::raw("hello world")]);
+    }
+
+    #[test]
+    fn sgr_codes_become_styles() {
+        let parsed =
+            lines(&["\x1b[1;31merror\x1b[0m: \x1b[38;5;208mwarn\x1b[38;2;1;2;3mrgb\x1b[39m"]);
+        assert_eq!(
+            parsed[0].spans,
+            vec![
+                Span::styled(
+                    "error",
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+                ),
+                Span::raw(": "),
+                Span::styled("warn", Style::default().fg(Color::Indexed(208))),
+                Span::styled("rgb", Style::default().fg(Color::Rgb(1, 2, 3))),
+            ]
+        );
+    }
+
+    #[test]
+    fn style_carries_across_lines_until_reset() {
+        let parsed = lines(&["\x1b[42m+ added", "still green\x1b[49m done"]);
+        let green = Style::default().bg(Color::Green);
+        assert_eq!(parsed[0].spans, vec![Span::styled("+ added", green)]);
+        assert_eq!(
+            parsed[1].spans,
+            vec![Span::styled("still green", green), Span::raw(" done")]
+        );
+        assert_eq!(plain_text(&parsed[1]), "still green done");
+    }
+
+    #[test]
+    fn non_sgr_sequences_are_dropped() {
+        let parsed = lines(&[
+            "\x1b]8;;https://example.com\x1b\\link\x1b]8;;\x1b\\ \x1b[2Kok\x07",
+            "\x1b[38:2::10:20:30mcolon\x1b[0;38;2;0;20;30;1mrgb",
+        ]);
+        assert_eq!(parsed[0].spans, vec![Span::raw("link ok")]);
+        assert_eq!(
+            parsed[1].spans,
+            vec![
+                Span::styled("colon", Style::default().fg(Color::Rgb(10, 20, 30))),
+                Span::styled(
+                    "rgb",
+                    Style::default()
+                        .fg(Color::Rgb(0, 20, 30))
+                        .add_modifier(Modifier::BOLD)
+                ),
+            ]
+        );
+        assert!(is_blank(&lines(&["\x1b[7m   \x1b[0m"])[0]));
+    }
+}
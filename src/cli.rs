@@ -0,0 +1,744 @@
+use anyhow::{Context, Result, anyhow, bail};
+use clap::Subcommand;
+use serde::Serialize;
+use std::{
+    env,
+    io::{self, Read},
+    path::{Path, PathBuf},
+    process::{Command as ProcessCommand, Stdio},
+};
+
+use crate::agents::{self, AgentDefinition};
+use crate::config::AppConfig;
+use crate::git;
+use crate::inject;
+use crate::instances::{self, AgentInstance, Stopped};
+use crate::state::{Alert, AlertKind, AlertLog, SessionRecord};
+use crate::status::AgentState;
+use crate::tmux;
+
+/// Headless subcommands. Without a subcommand lattice opens the TUI.
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// List running agent sessions
+    List {
+        /// Print machine-readable JSON instead of tab-separated text
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show details for one agent session
+    Show {
+        /// Session name or short name
+        session: String,
+        /// Print machine-readable JSON instead of text
+        #[arg(long)]
+        json: bool,
+    },
+    /// Start an agent in a new managed tmux session
+    Spawn {
+        /// Agent id, e.g. `claude` or `codex`
+        #[arg(long)]
+        agent: String,
+        /// Working directory (defaults to the current directory)
+        #[arg(long)]
+        dir: Option<String>,
+        /// Run the agent in a fresh git worktree
+        #[arg(long)]
+        worktree: bool,
+        /// Initial task for the agent to start working on
+        #[arg(long)]
+        prompt: Option<String>,
+    },
+    /// Stop an agent session (and clean up its worktree)
+    Stop {
+        /// Session name or short name
+        session: String,
+    },
+    /// Attach the current terminal to an agent session
+    Attach {
+        /// Session name or short name
+        session: String,
+    },
+    /// Recreate recorded sessions whose tmux session is gone (e.g. after a
+    /// reboot); without arguments, list them
+    Restore {
+        /// Session name or short name
+        session: Option<String>,
+        /// Restore every restorable session
+        #[arg(long, conflicts_with = "session")]
+        all: bool,
+    },
+    /// Send a prompt to a running agent without attaching
+    Send {
+        /// Session name or short name
+        session: String,
+        /// Text to send; omit or pass `-` to read it from stdin
+        text: Option<String>,
+    },
+    /// Switch the current tmux client to another agent; opened in a popup
+    /// by `tmux_pick_key`
+    Pick,
+    /// List the agent CLIs lattice can start, with their versions
+    Agents,
+    /// Run one prompt through an agent's non-interactive mode and print the
+    /// result, without a tmux session
+    Run {
+        /// Agent id, e.g. `claude` or `codex`
+        #[arg(long)]
+        agent: String,
+        /// Working directory (defaults to the current directory)
+        #[arg(long)]
+        dir: Option<String>,
+        /// Prompt to run; omit or pass `-` to read it from stdin
+        prompt: Option<String>,
+    },
+    /// Record a tmux alert for running lattice processes; run by the hooks
+    /// lattice sets on its sessions
+    #[command(hide = true)]
+    Hook {
+        /// `silence`, `activity`, `bell` or `pane-died`
+        event: String,
+        session: String,
+        pane: Option<String>,
+        /// Alert log to append to, as read by the lattice that set the hook
+        #[arg(long)]
+        log: Option<PathBuf>,
+    },
+}
+
+pub fn run(command: Command, config: &AppConfig) -> Result<()> {
+    let needs_tmux = !matches!(
+        command,
+        Command::Agents | Command::Run { .. } | Command::Hook { .. }
+    );
+    if needs_tmux && !tmux::is_tmux_available() {
+        bail!("tmux not found in PATH");
+    }
+
+    match command {
+        Command::List { json } => list(config, json),
+        Command::Show { session, json } => show(config, &session, json),
+        Command::Spawn {
+            agent,
+            dir,
+            worktree,
+            prompt,
+        } => spawn(config, &agent, dir.as_deref(), worktree, prompt.as_deref()),
+        Command::Stop { session } => stop(config, &session),
+        Command::Attach { session } => attach(config, &session),
+        Command::Restore { session, all } => restore(config, session.as_deref(), all),
+        Command::Send { session, text } => send(config, &session, text),
+        Command::Pick => crate::pick::run(config),
+        Command::Agents => list_agents(config),
+        Command::Run { agent, dir, prompt } => run_prompt(config, &agent, dir.as_deref(), prompt),
+        Command::Hook {
+            event,
+            session,
+            pane,
+            log,
+        } => hook(&event, &session, pane.as_deref(), log),
+    }
+}
+
+/// Version of the JSON emitted by `list --json` and `show --json`. Bump it
+/// whenever a field is removed or changes meaning; adding fields is allowed
+/// without a bump.
+pub const JSON_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize)]
+struct ListReport {
+    schema_version: u32,
+    instances: Vec<InstanceReport>,
+}
+
+#[derive(Debug, Serialize)]
+struct ShowReport {
+    schema_version: u32,
+    instance: InstanceReport,
+}
+
+#[derive(Debug, Serialize)]
+struct InstanceReport {
+    session: String,
+    short_name: String,
+    agent: AgentReport,
+    managed: bool,
+    /// `"lattice"` for lattice's own tmux server, `"default"` for sessions
+    /// listed from the default server alongside a dedicated socket.
+    server: &'static str,
+    attached: bool,
+    /// What the agent is doing, e.g. `"working"` or `"awaiting_approval"`.
+    state: AgentState,
+    /// Pid of the agent's process, when it was found under the session.
+    pid: Option<u32>,
+    cwd: String,
+    title: String,
+    command: String,
+    windows: u32,
+    /// Every pane of the session; `agent` marks the one the agent runs in.
+    panes: Vec<PaneReport>,
+    created: String,
+    created_at: u64,
+    last_line: String,
+    worktree: Option<WorktreeReport>,
+    /// Directory picked at spawn time (before any worktree was created).
+    original_dir: Option<String>,
+    initial_prompt: Option<String>,
+    spawned_by: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct AgentReport {
+    id: String,
+    label: String,
+}
+
+#[derive(Debug, Serialize)]
+struct PaneReport {
+    id: String,
+    window: u32,
+    index: u32,
+    command: String,
+    cwd: String,
+    agent: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct WorktreeReport {
+    path: String,
+    branch: String,
+    repo_root: String,
+}
+
+impl From<&AgentInstance> for InstanceReport {
+    fn from(instance: &AgentInstance) -> Self {
+        let session = &instance.session;
+        let record = instance.record.as_ref();
+        let worktree = match instance.worktree() {
+            Some(wt) => Some(WorktreeReport {
+                path: wt.path.to_string_lossy().to_string(),
+                branch: wt.branch.clone(),
+                repo_root: wt.repo_root.to_string_lossy().to_string(),
+            }),
+            // Sessions lattice has no record of: read it from the pane's
+            // directory, as schema version 1 always has.
+            None => {
+                let cwd = Path::new(&session.pane_current_path);
+                git::worktree_branch(cwd).map(|branch| WorktreeReport {
+                    path: session.pane_current_path.clone(),
+                    branch,
+                    repo_root: git::worktree_repo_root(cwd)
+                        .map(|root| root.to_string_lossy().to_string())
+                        .unwrap_or_default(),
+                })
+            }
+        };
+
+        Self {
+            session: session.name.clone(),
+            short_name: agents::short_instance_name(&session.name),
+            agent: AgentReport {
+                id: instance.agent.id.clone(),
+                label: instance.agent.label.clone(),
+            },
+            managed: instance.managed,
+            server: session.server.name(),
+            attached: session.attached,
+            state: instance.state,
+            pid: instance.process.as_ref().map(|p| p.pid),
+            cwd: session.pane_current_path.clone(),
+            title: instance.display_title(),
+            command: session.current_command.clone(),
+            windows: session.windows,
+            panes: session
+                .panes
+                .iter()
+                .map(|p| PaneReport {
+                    id: p.id.clone(),
+                    window: p.window,
+                    index: p.index,
+                    command: p.current_command.clone(),
+                    cwd: p.current_path.clone(),
+                    agent: p.id == session.pane_id,
+                })
+                .collect(),
+            created: session.created.clone(),
+            created_at: session.created_unix,
+            last_line: session.last_line.clone(),
+            worktree,
+            original_dir: record.map(|r| r.original_dir.clone()),
+            initial_prompt: record.and_then(|r| r.initial_prompt.clone()),
+            spawned_by: record.and_then(|r| r.ssh_user.clone()),
+        }
+    }
+}
+
+fn print_json<T: Serialize>(value: &T) -> Result<()> {
+    let out = serde_json::to_string_pretty(value).context("failed to serialize JSON")?;
+    println!("{out}");
+    Ok(())
+}
+
+fn list(config: &AppConfig, json: bool) -> Result<()> {
+    let available = agents::detect_available_agents(&config.custom_agents);
+    let instances = instances::discover(&available, config.show_default_server)?;
+
+    if json {
+        return print_json(&ListReport {
+            schema_version: JSON_SCHEMA_VERSION,
+            instances: instances.iter().map(InstanceReport::from).collect(),
+        });
+    }
+
+    if instances.is_empty() {
+        println!("no agent sessions running");
+        return Ok(());
+    }
+
+    for instance in &instances {
+        println!(
+            "{}\t{}\t{}\t{}\t{}\t{}",
+            instance.session.name,
+            instance.agent.id,
+            if instance.managed {
+                "managed"
+            } else {
+                "external"
+            },
+            if instance.session.attached {
+                "attached"
+            } else {
+                "idle"
+            },
+            instance.session.pane_current_path,
+            instance.display_title(),
+        );
+    }
+    Ok(())
+}
+
+fn show(config: &AppConfig, query: &str, json: bool) -> Result<()> {
+    let instance = find_instance(config, query)?;
+    let report = InstanceReport::from(&instance);
+
+    if json {
+        return print_json(&ShowReport {
+            schema_version: JSON_SCHEMA_VERSION,
+            instance: report,
+        });
+    }
+
+    println!("session   {}", report.session);
+    println!("agent     {} ({})", report.agent.label, report.agent.id);
+    println!(
+        "kind      {}{}",
+        if report.managed {
+            "managed"
+        } else {
+            "external"
+        },
+        if report.server == "default" {
+            " (default server)"
+        } else {
+            ""
+        }
+    );
+    println!(
+        "state     {}{}",
+        report.state.label(),
+        if report.attached { ", attached" } else { "" }
+    );
+    println!("title     {}", report.title);
+    println!("path      {}", report.cwd);
+    println!("command   {}", report.command);
+    println!("created   {}", report.created);
+    if let Some(wt) = &report.worktree {
+        println!("worktree  {} ({})", wt.path, wt.branch);
+    }
+    if let Some(dir) = &report.original_dir {
+        println!("from      {dir}");
+    }
+    if let Some(user) = &report.spawned_by {
+        println!("by        {user}");
+    }
+    if let Some(prompt) = &report.initial_prompt {
+        println!("prompt    {}", prompt.lines().next().unwrap_or_default());
+    }
+    println!("last      {}", report.last_line);
+    Ok(())
+}
+
+fn spawn(
+    config: &AppConfig,
+    agent_id: &str,
+    dir: Option<&str>,
+    worktree: bool,
+    prompt: Option<&str>,
+) -> Result<()> {
+    let available = agents::detect_available_agents(&config.custom_agents);
+    let agent = find_agent(&available, agent_id)?;
+
+    let dir = match dir {
+        Some(d) => expand_home(d),
+        None => env::current_dir().context("cannot determine current directory")?,
+    };
+    if !dir.is_dir() {
+        bail!("not a directory: {}", dir.display());
+    }
+    if worktree && !git::is_git_repo(&dir) {
+        bail!("--worktree requires a git repository: {}", dir.display());
+    }
+
+    let working_dir = dir.to_string_lossy().to_string();
+    let spawned = instances::spawn(
+        config,
+        agent,
+        &working_dir,
+        worktree || config.git_worktrees,
+        prompt,
+    )?;
+
+    if let Some(err) = &spawned.worktree_error {
+        eprintln!("lattice: warning: worktree failed: {err}, using original dir");
+    }
+    if let Some(err) = &spawned.record_error {
+        eprintln!("lattice: warning: could not record session: {err}");
+    }
+    println!("{}", spawned.session_name);
+
+    // Headless: nothing keeps running after we exit, so wait for delivery.
+    if let Some(injection) = &spawned.injection {
+        eprintln!("lattice: waiting for {} to be ready...", agent.label);
+        let waited = inject::deliver(injection).with_context(|| {
+            format!(
+                "{} is running but its first message was not delivered",
+                spawned.session_name
+            )
+        })?;
+        eprintln!("lattice: delivered after {:.1}s", waited.as_secs_f64());
+    }
+    Ok(())
+}
+
+fn stop(config: &AppConfig, query: &str) -> Result<()> {
+    let instance = find_instance(config, query)?;
+
+    match instances::stop(&instance)? {
+        Stopped::Plain => println!("stopped {}", instance.session.name),
+        Stopped::WorktreeCleaned => {
+            println!("stopped {} (worktree cleaned)", instance.session.name)
+        }
+        Stopped::WorktreeCleanupFailed(err) => {
+            eprintln!(
+                "lattice: warning: worktree cleanup failed for {}: {err}",
+                instance.session.name
+            );
+            println!("stopped {}", instance.session.name);
+        }
+    }
+    Ok(())
+}
+
+fn attach(config: &AppConfig, query: &str) -> Result<()> {
+    let instance = find_instance(config, query)?;
+    tmux::attach_session(instance.session.server, &instance.session.name)
+}
+
+fn hook(event: &str, session: &str, pane: Option<&str>, log: Option<PathBuf>) -> Result<()> {
+    let kind = AlertKind::parse(event).ok_or_else(|| anyhow!("unknown alert `{event}`"))?;
+    let log = log.map(AlertLog::at).unwrap_or_else(AlertLog::open);
+    log.append(&Alert::new(kind, session, pane.unwrap_or_default()))
+}
+
+fn restore(config: &AppConfig, query: Option<&str>, all: bool) -> Result<()> {
+    let records = instances::restorable()?;
+
+    let targets: Vec<&SessionRecord> = match query {
+        Some(q) => vec![
+            instances::find_record(&records, q)
+                .ok_or_else(|| anyhow!("no restorable session named `{q}`"))?,
+        ],
+        None if all => records.iter().collect(),
+        None => {
+            if records.is_empty() {
+                println!("nothing to restore");
+            }
+            for record in &records {
+                println!(
+                    "{}\t{}\t{}\t{}",
+                    record.session_name,
+                    record.agent_id,
+                    record.working_dir,
+                    record
+                        .initial_prompt
+                        .as_deref()
+                        .and_then(|p| p.lines().next())
+                        .unwrap_or_default(),
+                );
+            }
+            return Ok(());
+        }
+    };
+
+    let available = agents::detect_available_agents(&config.custom_agents);
+    let mut injections = Vec::new();
+    let mut failed = 0;
+    for record in &targets {
+        match instances::restore(config, &available, record) {
+            Ok(restored) => {
+                println!(
+                    "restored {} ({})",
+                    restored.session_name,
+                    if restored.resumed {
+                        "resumed"
+                    } else {
+                        "fresh start"
+                    }
+                );
+                injections.extend(restored.injection);
+            }
+            Err(err) => {
+                eprintln!(
+                    "lattice: failed to restore {}: {err:#}",
+                    record.session_name
+                );
+                failed += 1;
+            }
+        }
+    }
+
+    // Headless: wait for first inputs, in parallel so one slow agent does not
+    // hold up the rest.
+    std::thread::scope(|scope| {
+        for injection in &injections {
+            scope.spawn(move || {
+                if let Err(err) = inject::deliver(injection) {
+                    eprintln!("lattice: warning: {err}");
+                }
+            });
+        }
+    });
+
+    if failed > 0 {
+        bail!(
+            "{failed} of {} sessions could not be restored",
+            targets.len()
+        );
+    }
+    Ok(())
+}
+
+fn send(config: &AppConfig, query: &str, text: Option<String>) -> Result<()> {
+    let text = read_prompt(text)?;
+    let instance = find_instance(config, query)?;
+    tmux::send_text(instance.session.server, &instance.session.pane_id, &text)?;
+    println!("sent to {}", instance.session.name);
+    Ok(())
+}
+
+/// The prompt given on the command line, or stdin for none or `-`.
+fn read_prompt(text: Option<String>) -> Result<String> {
+    let text = match text {
+        Some(t) if t != "-" => t,
+        _ => {
+            let mut buf = String::new();
+            io::stdin()
+                .read_to_string(&mut buf)
+                .context("failed to read prompt from stdin")?;
+            buf
+        }
+    };
+    let text = text.trim_end_matches('\n');
+    if text.trim().is_empty() {
+        bail!("refusing to send an empty prompt");
+    }
+    Ok(text.to_owned())
+}
+
+fn list_agents(config: &AppConfig) -> Result<()> {
+    let available = agents::detect_available_agents(&config.custom_agents);
+    if available.is_empty() {
+        println!("no agent CLIs found in PATH");
+        return Ok(());
+    }
+    for agent in &available {
+        let version = agent
+            .version_command
+            .as_deref()
+            .and_then(command_output)
+            .unwrap_or_else(|| "-".to_owned());
+        println!("{}\t{}\t{}\t{version}", agent.id, agent.label, agent.launch);
+    }
+    Ok(())
+}
+
+/// First line a shell command prints, if it succeeds.
+fn command_output(command: &str) -> Option<String> {
+    let output = ProcessCommand::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let text = String::from_utf8_lossy(&output.stdout);
+    text.lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty())
+        .map(ToOwned::to_owned)
+}
+
+fn run_prompt(
+    config: &AppConfig,
+    agent_id: &str,
+    dir: Option<&str>,
+    prompt: Option<String>,
+) -> Result<()> {
+    let available = agents::detect_available_agents(&config.custom_agents);
+    let agent = find_agent(&available, agent_id)?;
+    let prompt = read_prompt(prompt)?;
+    let Some(command) = agents::build_run_command(agent, &prompt) else {
+        bail!("{} has no run_command configured", agent.label);
+    };
+
+    let dir = match dir {
+        Some(d) => expand_home(d),
+        None => env::current_dir().context("cannot determine current directory")?,
+    };
+    let status = ProcessCommand::new("sh")
+        .arg("-c")
+        .arg(&command)
+        .current_dir(&dir)
+        .status()
+        .with_context(|| format!("failed to run {}", agent.label))?;
+    if !status.success() {
+        bail!("{} exited with {status}", agent.label);
+    }
+    Ok(())
+}
+
+fn find_agent<'a>(available: &'a [AgentDefinition], id: &str) -> Result<&'a AgentDefinition> {
+    available.iter().find(|a| a.id == id).ok_or_else(|| {
+        let ids: Vec<&str> = available.iter().map(|a| a.id.as_str()).collect();
+        if ids.is_empty() {
+            anyhow!("unknown agent `{id}` (no agent CLIs found in PATH)")
+        } else {
+            anyhow!("unknown agent `{id}` (available: {})", ids.join(", "))
+        }
+    })
+}
+
+fn find_instance(config: &AppConfig, query: &str) -> Result<AgentInstance> {
+    let available = agents::detect_available_agents(&config.custom_agents);
+    let instances = instances::discover(&available, config.show_default_server)?;
+    instances::find(&instances, query)
+        .cloned()
+        .ok_or_else(|| anyhow!("no agent session named `{query}`"))
+}
+
+/// Expand a leading `~` to `$HOME` so quoted paths like `"~/repo"` work.
+fn expand_home(path: &str) -> PathBuf {
+    if (path == "~" || path.starts_with("~/"))
+        && let Ok(home) = env::var("HOME")
+    {
+        return PathBuf::from(format!("{home}{}", &path[1..]));
+    }
+    PathBuf::from(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expand_home_replaces_leading_tilde() {
+        let home = env::var("HOME").unwrap_or_default();
+        assert_eq!(expand_home("~/repo"), PathBuf::from(format!("{home}/repo")));
+        assert_eq!(expand_home("/abs/~/x"), PathBuf::from("/abs/~/x"));
+    }
+
+    #[test]
+    fn instance_report_serializes_stable_fields() {
+        let mut instance = AgentInstance {
+            agent: AgentDefinition {
+                id: "claude".to_owned(),
+                label: "Claude Code".to_owned(),
+                binary: "claude".to_owned(),
+                launch: "claude".to_owned(),
+                ..AgentDefinition::default()
+            },
+            session: tmux::Session {
+                name: "lattice_claude_1700000000".to_owned(),
+                server: tmux::Server::Lattice,
+                attached: false,
+                windows: 1,
+                created: "Tue Nov 14 22:13:20 2023".to_owned(),
+                created_unix: 1700000000,
+                current_command: "claude".to_owned(),
+                pane_current_path: "/src/app/.lattice/worktrees/1700000000".to_owned(),
+                pane_title: String::new(),
+                notify_rule: String::new(),
+                alert_watch: None,
+                pane_id: "%1".to_owned(),
+                pane_pid: 0,
+                panes: Vec::new(),
+                activity_unix: 1700000000,
+                preview: Vec::new(),
+                last_line: "> ".to_owned(),
+            },
+            managed: true,
+            title_override: "Fixing tests".to_owned(),
+            record: Some(SessionRecord::new(
+                "lattice_claude_1700000000",
+                "claude",
+                "/src/app",
+                "/src/app/.lattice/worktrees/1700000000",
+                Some(git::Worktree {
+                    path: PathBuf::from("/src/app/.lattice/worktrees/1700000000"),
+                    branch: "lattice/1700000000".to_owned(),
+                    repo_root: PathBuf::from("/src/app"),
+                }),
+                Some("fix the flaky test"),
+            )),
+            state: AgentState::WaitingForInput,
+            process: None,
+        };
+
+        let value = serde_json::to_value(InstanceReport::from(&instance)).expect("serialize");
+        assert_eq!(value["session"], "lattice_claude_1700000000");
+        assert_eq!(value["short_name"], "claude_1700000000");
+        assert_eq!(value["agent"]["id"], "claude");
+        assert_eq!(value["managed"], true);
+        assert_eq!(value["server"], "lattice");
+        assert_eq!(value["title"], "Fixing tests");
+        assert_eq!(value["state"], "waiting_for_input");
+        assert_eq!(value["created_at"], 1700000000);
+        assert_eq!(value["worktree"]["branch"], "lattice/1700000000");
+        assert_eq!(value["worktree"]["repo_root"], "/src/app");
+        assert_eq!(value["original_dir"], "/src/app");
+        assert_eq!(value["initial_prompt"], "fix the flaky test");
+
+        // Without a record the worktree still comes from the pane's directory
+        instance.record = None;
+        let value = serde_json::to_value(InstanceReport::from(&instance)).expect("serialize");
+        assert_eq!(value["worktree"]["branch"], "lattice/1700000000");
+        assert_eq!(value["worktree"]["repo_root"], "/src/app");
+        assert_eq!(value["original_dir"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn find_agent_reports_available_ids() {
+        let available = vec![AgentDefinition {
+            id: "codex".to_owned(),
+            label: "Codex".to_owned(),
+            binary: "codex".to_owned(),
+            launch: "codex".to_owned(),
+            ..AgentDefinition::default()
+        }];
+        assert!(find_agent(&available, "codex").is_ok());
+        let err = find_agent(&available, "claude").expect_err("unknown agent");
+        assert!(err.to_string().contains("available: codex"));
+    }
+}
@@ -1,12 +1,10 @@
 use serde::{Deserialize, Serialize};
-use std::collections::hash_map::DefaultHasher;
-use std::collections::HashMap;
-use std::hash::{Hash, Hasher};
 use std::path::PathBuf;
 use std::process::Command;
-use std::time::{Duration, Instant};
-use std::{env, fs, thread};
+use std::{env, fs};
 
+use crate::state::NotificationKind;
+use crate::status;
 use crate::tmux;
 
 // ── Raw TOML representation (all fields optional) ───────────────────────────
@@ -18,7 +16,15 @@ struct ConfigFile {
     default_spawn_dir: Option<String>,
     title_injection_enabled: Option<bool>,
     title_injection_delay: Option<u32>,
+    ready_timeout: Option<u32>,
     git_worktrees: Option<bool>,
+    tmux_socket: Option<String>,
+    show_default_server: Option<bool>,
+    interact_escape: Option<String>,
+    tmux_split_layout: Option<String>,
+    tmux_return_key: Option<String>,
+    tmux_pick_key: Option<String>,
+    instance_sort: Option<String>,
     notifications: Option<NotificationsConfigFile>,
     theme: Option<ThemeConfigFile>,
     #[serde(default)]
@@ -36,6 +42,7 @@ struct ThemeConfigFile {
     highlight: Option<String>,
     yellow: Option<String>,
     green: Option<String>,
+    red: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Default)]
@@ -44,6 +51,70 @@ struct NotificationsConfigFile {
     sound_on_completion: Option<bool>,
     sound_method: Option<String>,
     sound_command: Option<String>,
+    settle_seconds: Option<u64>,
+    do_not_disturb: Option<bool>,
+    quiet_hours: Option<String>,
+    min_interval: Option<u64>,
+    sinks: Vec<SinkConfig>,
+}
+
+/// Where a notification sink delivers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SinkKind {
+    /// Desktop notification through `notify-send`.
+    Desktop,
+    /// JSON POST to `url`.
+    Webhook,
+    /// OSC 9 or 777 escape sequence to lattice's terminal.
+    Osc,
+    /// `tmux display-message` in every attached client.
+    Tmux,
+    /// Shell `command` with the event in `LATTICE_*` variables.
+    Command,
+}
+
+/// One `[[notifications.sinks]]` entry.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SinkConfig {
+    #[serde(rename = "type")]
+    pub kind: SinkKind,
+    /// Events sent to this sink; all of them when empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<NotificationKind>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// ntfy topic, added to the webhook payload.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub topic: Option<String>,
+    /// `9` (the default) or `777`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub osc: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+}
+
+impl SinkConfig {
+    pub fn wants(&self, kind: NotificationKind) -> bool {
+        self.events.is_empty() || self.events.contains(&kind)
+    }
+
+    /// What is missing for the sink to work, if anything.
+    pub fn problem(&self) -> Option<String> {
+        match self.kind {
+            SinkKind::Webhook if self.url.as_deref().is_none_or(str::is_empty) => {
+                Some("webhook sink without a url".to_owned())
+            }
+            SinkKind::Command if self.command.as_deref().is_none_or(str::is_empty) => {
+                Some("command sink without a command".to_owned())
+            }
+            SinkKind::Osc if !matches!(self.osc, None | Some(9) | Some(777)) => Some(format!(
+                "osc sink with osc = {:?}, expected 9 or 777",
+                self.osc
+            )),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -53,6 +124,69 @@ pub struct CustomAgentConfig {
     pub binary: String,
     pub launch: String,
     pub prompt_flag: Option<String>,
+    /// How to pass an initial task: `"positional"` or a flag like `"--prompt"`.
+    pub task_arg: Option<String>,
+    /// Screen text that shows up once the agent is ready for input.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ready_markers: Vec<String>,
+    /// Arguments that resume the previous conversation, e.g. `"--continue"`.
+    pub resume_args: Option<String>,
+    /// Full command that resumes the agent instead of `launch` + `resume_args`.
+    pub resume_command: Option<String>,
+    /// Command that prints the agent's version, e.g. `"mytool --version"`.
+    pub version_command: Option<String>,
+    /// Command that runs one prompt without the interactive UI; `{prompt}`
+    /// marks where the prompt goes.
+    pub run_command: Option<String>,
+    /// Other process names the agent shows up as, e.g. `["node"]`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub process_names: Vec<String>,
+    /// Screen patterns (regular expressions) for the agent state badge.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub idle_patterns: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub busy_patterns: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub approval_patterns: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub error_patterns: Vec<String>,
+}
+
+impl CustomAgentConfig {
+    /// The screen patterns, leaving out those that are not valid regular
+    /// expressions.
+    pub fn screen_patterns(&self) -> status::ScreenPatterns {
+        let compile = |patterns: &[String]| {
+            patterns
+                .iter()
+                .filter_map(|p| status::compile_pattern(p).ok())
+                .collect()
+        };
+        status::ScreenPatterns {
+            idle: compile(&self.idle_patterns),
+            busy: compile(&self.busy_patterns),
+            approval: compile(&self.approval_patterns),
+            error: compile(&self.error_patterns),
+        }
+    }
+
+    /// Screen patterns that are not valid regular expressions.
+    pub fn pattern_problems(&self) -> Vec<String> {
+        [
+            ("idle_patterns", &self.idle_patterns),
+            ("busy_patterns", &self.busy_patterns),
+            ("approval_patterns", &self.approval_patterns),
+            ("error_patterns", &self.error_patterns),
+        ]
+        .into_iter()
+        .flat_map(|(field, patterns)| {
+            patterns
+                .iter()
+                .filter(|p| status::compile_pattern(p).is_err())
+                .map(move |p| format!("invalid regex `{p}` in {field} of agent {}", self.id))
+        })
+        .collect()
+    }
 }
 
 // ── Resolved config the app uses ────────────────────────────────────────────
@@ -63,11 +197,93 @@ pub enum SoundMethod {
     Command,
 }
 
+/// Order of the instance list and tabs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InstanceSort {
+    #[default]
+    Name,
+    /// Most recent output first.
+    Activity,
+}
+
+impl InstanceSort {
+    pub fn name(self) -> &'static str {
+        match self {
+            InstanceSort::Name => "name",
+            InstanceSort::Activity => "activity",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim() {
+            "name" => Some(InstanceSort::Name),
+            "activity" => Some(InstanceSort::Activity),
+            _ => None,
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            InstanceSort::Name => InstanceSort::Activity,
+            InstanceSort::Activity => InstanceSort::Name,
+        }
+    }
+}
+
+/// A daily stretch of local time, e.g. `22:00-07:00`, during which
+/// notifications only go to the inbox. May wrap past midnight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuietHours {
+    /// Minutes after midnight.
+    start: u16,
+    end: u16,
+}
+
+impl QuietHours {
+    pub fn parse(value: &str) -> Option<Self> {
+        let minutes = |time: &str| {
+            let (hours, minutes) = time.trim().split_once(':')?;
+            let (hours, minutes) = (hours.parse::<u16>().ok()?, minutes.parse::<u16>().ok()?);
+            (hours < 24 && minutes < 60).then_some(hours * 60 + minutes)
+        };
+        let (start, end) = value.split_once('-')?;
+        let (start, end) = (minutes(start)?, minutes(end)?);
+        (start != end).then_some(Self { start, end })
+    }
+
+    pub fn name(self) -> String {
+        let time = |m: u16| format!("{:02}:{:02}", m / 60, m % 60);
+        format!("{}-{}", time(self.start), time(self.end))
+    }
+
+    /// Whether `minute` (after midnight) falls inside.
+    pub fn contains(self, minute: u16) -> bool {
+        if self.start < self.end {
+            self.start <= minute && minute < self.end
+        } else {
+            minute >= self.start || minute < self.end
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct NotificationsConfig {
     pub sound_on_completion: bool,
     pub sound_method: SoundMethod,
     pub sound_command: String,
+    /// Seconds a session's output must stay still before its turn counts as
+    /// finished. Sessions can override it.
+    pub settle_seconds: u64,
+    /// Keep everything in the inbox: no sound, no sinks.
+    pub do_not_disturb: bool,
+    pub quiet_hours: Option<QuietHours>,
+    /// A `quiet_hours` value from the file that does not parse, kept to
+    /// report it and to write it back unchanged.
+    pub invalid_quiet_hours: Option<String>,
+    /// Minimum seconds between two deliveries of the same event for the
+    /// same session, so a flapping agent stays quiet.
+    pub min_interval: u64,
+    pub sinks: Vec<SinkConfig>,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -80,6 +296,7 @@ pub struct ThemeConfig {
     pub highlight: Option<[u8; 3]>,
     pub yellow: Option<[u8; 3]>,
     pub green: Option<[u8; 3]>,
+    pub red: Option<[u8; 3]>,
 }
 
 #[derive(Debug, Clone)]
@@ -87,8 +304,26 @@ pub struct AppConfig {
     pub refresh_interval: u64,
     pub default_spawn_dir: Option<String>,
     pub title_injection_enabled: bool,
+    /// Seconds to wait before typing into agents that have no readiness markers.
     pub title_injection_delay: u32,
+    /// Seconds to wait for an agent's readiness marker before giving up.
+    pub ready_timeout: u32,
     pub git_worktrees: bool,
+    /// Run lattice's sessions on a dedicated tmux server: a socket name
+    /// (`tmux -L`) or, when it contains a `/`, a socket path (`tmux -S`).
+    pub tmux_socket: Option<String>,
+    /// With a dedicated socket, also list agents on the default tmux server.
+    pub show_default_server: bool,
+    /// Key chord that leaves interact mode, e.g. `"ctrl-q"`.
+    pub interact_escape: String,
+    /// Layout of the tmux split opened from a split selection.
+    pub tmux_split_layout: tmux::SplitLayout,
+    /// tmux key that returns from an agent session to lattice, e.g. `"F12"`
+    /// or `"prefix+L"`.
+    pub tmux_return_key: String,
+    /// tmux key that opens the agent picker popup from an agent session.
+    pub tmux_pick_key: String,
+    pub instance_sort: InstanceSort,
     pub notifications: NotificationsConfig,
     pub theme: ThemeConfig,
     pub custom_agents: Vec<CustomAgentConfig>,
@@ -101,11 +336,25 @@ impl Default for AppConfig {
             default_spawn_dir: None,
             title_injection_enabled: true,
             title_injection_delay: 5,
+            ready_timeout: 60,
             git_worktrees: false,
+            tmux_socket: None,
+            show_default_server: true,
+            interact_escape: "ctrl-q".to_owned(),
+            tmux_split_layout: tmux::SplitLayout::default(),
+            tmux_return_key: tmux::HotKey::RETURN_DEFAULT.to_owned(),
+            tmux_pick_key: tmux::HotKey::PICK_DEFAULT.to_owned(),
+            instance_sort: InstanceSort::default(),
             notifications: NotificationsConfig {
                 sound_on_completion: true,
                 sound_method: SoundMethod::Command,
                 sound_command: "afplay /System/Library/Sounds/Glass.aiff".to_owned(),
+                settle_seconds: 8,
+                do_not_disturb: false,
+                quiet_hours: None,
+                invalid_quiet_hours: None,
+                min_interval: 30,
+                sinks: Vec::new(),
             },
             theme: ThemeConfig::default(),
             custom_agents: Vec::new(),
@@ -150,9 +399,35 @@ pub fn load_config() -> AppConfig {
     if let Some(v) = file.title_injection_delay {
         config.title_injection_delay = v;
     }
+    if let Some(v) = file.ready_timeout {
+        config.ready_timeout = v.max(1);
+    }
     if let Some(v) = file.git_worktrees {
         config.git_worktrees = v;
     }
+    config.tmux_socket = file.tmux_socket.filter(|s| !s.trim().is_empty());
+    if let Some(v) = file.show_default_server {
+        config.show_default_server = v;
+    }
+    if let Some(v) = file.interact_escape {
+        config.interact_escape = v;
+    }
+    if let Some(layout) = file
+        .tmux_split_layout
+        .as_deref()
+        .and_then(tmux::SplitLayout::parse)
+    {
+        config.tmux_split_layout = layout;
+    }
+    if let Some(v) = file.tmux_return_key {
+        config.tmux_return_key = v;
+    }
+    if let Some(v) = file.tmux_pick_key {
+        config.tmux_pick_key = v;
+    }
+    if let Some(sort) = file.instance_sort.as_deref().and_then(InstanceSort::parse) {
+        config.instance_sort = sort;
+    }
 
     if let Some(notif) = file.notifications {
         if let Some(v) = notif.sound_on_completion {
@@ -167,6 +442,20 @@ pub fn load_config() -> AppConfig {
         if let Some(cmd) = notif.sound_command {
             config.notifications.sound_command = cmd;
         }
+        if let Some(v) = notif.settle_seconds {
+            config.notifications.settle_seconds = v.max(1);
+        }
+        if let Some(v) = notif.do_not_disturb {
+            config.notifications.do_not_disturb = v;
+        }
+        config.notifications.quiet_hours = notif.quiet_hours.as_deref().and_then(QuietHours::parse);
+        if config.notifications.quiet_hours.is_none() {
+            config.notifications.invalid_quiet_hours = notif.quiet_hours;
+        }
+        if let Some(v) = notif.min_interval {
+            config.notifications.min_interval = v;
+        }
+        config.notifications.sinks = notif.sinks;
     }
 
     if let Some(theme) = file.theme {
@@ -178,6 +467,7 @@ pub fn load_config() -> AppConfig {
         config.theme.highlight = theme.highlight.as_deref().and_then(parse_hex_color);
         config.theme.yellow = theme.yellow.as_deref().and_then(parse_hex_color);
         config.theme.green = theme.green.as_deref().and_then(parse_hex_color);
+        config.theme.red = theme.red.as_deref().and_then(parse_hex_color);
     }
 
     config.custom_agents = file.agents;
@@ -204,7 +494,16 @@ struct ConfigFileSave {
     default_spawn_dir: Option<String>,
     title_injection_enabled: bool,
     title_injection_delay: u32,
+    ready_timeout: u32,
     git_worktrees: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tmux_socket: Option<String>,
+    show_default_server: bool,
+    interact_escape: String,
+    tmux_split_layout: String,
+    tmux_return_key: String,
+    tmux_pick_key: String,
+    instance_sort: String,
     notifications: NotificationsConfigFileSave,
     #[serde(skip_serializing_if = "ThemeConfigSave::is_empty")]
     theme: ThemeConfigSave,
@@ -230,6 +529,8 @@ struct ThemeConfigSave {
     yellow: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     green: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    red: Option<String>,
 }
 
 impl ThemeConfigSave {
@@ -242,6 +543,7 @@ impl ThemeConfigSave {
             && self.highlight.is_none()
             && self.yellow.is_none()
             && self.green.is_none()
+            && self.red.is_none()
     }
 }
 
@@ -254,6 +556,13 @@ struct NotificationsConfigFileSave {
     sound_on_completion: bool,
     sound_method: String,
     sound_command: String,
+    settle_seconds: u64,
+    do_not_disturb: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    quiet_hours: Option<String>,
+    min_interval: u64,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    sinks: Vec<SinkConfig>,
 }
 
 pub fn save_config(config: &AppConfig) -> Result<(), String> {
@@ -262,7 +571,15 @@ pub fn save_config(config: &AppConfig) -> Result<(), String> {
         default_spawn_dir: config.default_spawn_dir.clone(),
         title_injection_enabled: config.title_injection_enabled,
         title_injection_delay: config.title_injection_delay,
+        ready_timeout: config.ready_timeout,
         git_worktrees: config.git_worktrees,
+        tmux_socket: config.tmux_socket.clone(),
+        show_default_server: config.show_default_server,
+        interact_escape: config.interact_escape.clone(),
+        tmux_split_layout: config.tmux_split_layout.name().to_owned(),
+        tmux_return_key: config.tmux_return_key.clone(),
+        tmux_pick_key: config.tmux_pick_key.clone(),
+        instance_sort: config.instance_sort.name().to_owned(),
         notifications: NotificationsConfigFileSave {
             sound_on_completion: config.notifications.sound_on_completion,
             sound_method: match config.notifications.sound_method {
@@ -270,6 +587,15 @@ pub fn save_config(config: &AppConfig) -> Result<(), String> {
                 SoundMethod::Command => "command".to_owned(),
             },
             sound_command: config.notifications.sound_command.clone(),
+            settle_seconds: config.notifications.settle_seconds,
+            do_not_disturb: config.notifications.do_not_disturb,
+            quiet_hours: config
+                .notifications
+                .quiet_hours
+                .map(QuietHours::name)
+                .or_else(|| config.notifications.invalid_quiet_hours.clone()),
+            min_interval: config.notifications.min_interval,
+            sinks: config.notifications.sinks.clone(),
         },
         theme: ThemeConfigSave {
             bg: config.theme.bg.map(rgb_to_hex),
@@ -280,6 +606,7 @@ pub fn save_config(config: &AppConfig) -> Result<(), String> {
             highlight: config.theme.highlight.map(rgb_to_hex),
             yellow: config.theme.yellow.map(rgb_to_hex),
             green: config.theme.green.map(rgb_to_hex),
+            red: config.theme.red.map(rgb_to_hex),
         },
         agents: config.custom_agents.clone(),
     };
@@ -326,100 +653,6 @@ pub fn play_notification_sound(config: &AppConfig) {
     }
 }
 
-// ── Motion-based completion detection (background thread) ────────────────────
-
-const SETTLE_SECONDS: u64 = 8;
-
-struct SessionActivity {
-    content_hash: u64,
-    last_change: Instant,
-    was_active: bool,
-    notified: bool,
-}
-
-/// Hash preview lines, stripping trailing empty lines first so that pane
-/// resize (which changes the number of trailing blanks) doesn't cause
-/// spurious hash changes.
-fn hash_preview(lines: &[String]) -> u64 {
-    let end = lines
-        .iter()
-        .rposition(|l| !l.trim().is_empty())
-        .map(|i| i + 1)
-        .unwrap_or(0);
-    let mut hasher = DefaultHasher::new();
-    lines[..end].hash(&mut hasher);
-    hasher.finish()
-}
-
-/// Run one detection tick. Returns names of sessions that fired a notification.
-fn detect_tick(
-    activity: &mut HashMap<String, SessionActivity>,
-    sessions: &[(String, Vec<String>)],
-    config: &AppConfig,
-) -> Vec<String> {
-    let now = Instant::now();
-    let mut completed = Vec::new();
-
-    for (name, preview) in sessions {
-        let hash = hash_preview(preview);
-
-        match activity.get_mut(name) {
-            Some(entry) => {
-                if hash != entry.content_hash {
-                    entry.content_hash = hash;
-                    entry.last_change = now;
-                    entry.was_active = true;
-                    entry.notified = false;
-                } else if entry.was_active
-                    && !entry.notified
-                    && now.duration_since(entry.last_change).as_secs() >= SETTLE_SECONDS
-                {
-                    play_notification_sound(config);
-                    entry.notified = true;
-                    completed.push(name.clone());
-                }
-            }
-            None => {
-                activity.insert(
-                    name.clone(),
-                    SessionActivity {
-                        content_hash: hash,
-                        last_change: now,
-                        was_active: false,
-                        notified: true,
-                    },
-                );
-            }
-        }
-    }
-
-    let active_names: std::collections::HashSet<&String> =
-        sessions.iter().map(|(name, _)| name).collect();
-    activity.retain(|name, _| active_names.contains(name));
-
-    completed
-}
-
-/// Spawn a background thread that polls tmux pane content and fires
-/// notification sounds when an agent's output settles. Runs independently
-/// of the TUI event loop so notifications work even while attached to a
-/// session.
-pub fn spawn_activity_monitor(config: &AppConfig) {
-    let config = config.clone();
-    let interval = Duration::from_secs(config.refresh_interval.max(1));
-
-    thread::spawn(move || {
-        let mut activity: HashMap<String, SessionActivity> = HashMap::new();
-
-        loop {
-            thread::sleep(interval);
-
-            let sessions = tmux::poll_session_previews();
-            detect_tick(&mut activity, &sessions, &config);
-        }
-    });
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -430,6 +663,10 @@ mod tests {
         assert_eq!(config.refresh_interval, 3);
         assert!(config.title_injection_enabled);
         assert_eq!(config.title_injection_delay, 5);
+        assert_eq!(config.ready_timeout, 60);
+        assert_eq!(config.tmux_socket, None);
+        assert!(config.show_default_server);
+        assert_eq!(config.tmux_split_layout, tmux::SplitLayout::EvenHorizontal);
         assert!(config.notifications.sound_on_completion);
         assert_eq!(config.notifications.sound_method, SoundMethod::Command);
     }
@@ -448,6 +685,71 @@ mod tests {
         assert_eq!(config.refresh_interval, 3);
     }
 
+    #[test]
+    fn notification_sinks_parse_and_round_trip() {
+        let file: ConfigFile = toml::from_str(
+            r#"
+            [notifications]
+            sound_on_completion = false
+
+            [[notifications.sinks]]
+            type = "webhook"
+            url = "https://ntfy.sh"
+            topic = "agents"
+            events = ["awaiting_approval", "errored"]
+
+            [[notifications.sinks]]
+            type = "osc"
+            osc = 777
+
+            [[notifications.sinks]]
+            type = "command"
+            "#,
+        )
+        .expect("parse");
+        let sinks = file.notifications.expect("notifications").sinks;
+        assert_eq!(sinks.len(), 3);
+        assert_eq!(sinks[0].kind, SinkKind::Webhook);
+        assert!(sinks[0].wants(NotificationKind::Errored));
+        assert!(!sinks[0].wants(NotificationKind::Finished));
+        assert!(sinks[1].wants(NotificationKind::Finished));
+        assert_eq!(sinks[0].problem(), None);
+        assert_eq!(sinks[1].problem(), None);
+        assert!(sinks[2].problem().is_some());
+
+        let save = NotificationsConfigFileSave {
+            sound_on_completion: false,
+            sound_method: "bell".to_owned(),
+            sound_command: String::new(),
+            settle_seconds: 8,
+            do_not_disturb: false,
+            quiet_hours: None,
+            min_interval: 30,
+            sinks: sinks.clone(),
+        };
+        let saved = toml::to_string(&save).expect("serialize");
+        let reread: NotificationsConfigFile = toml::from_str(&saved).expect("reparse");
+        assert_eq!(reread.sinks, sinks);
+    }
+
+    #[test]
+    fn quiet_hours_parse_and_wrap_midnight() {
+        let night = QuietHours::parse("22:00-7:30").expect("night");
+        assert_eq!(night.name(), "22:00-07:30");
+        assert!(night.contains(23 * 60));
+        assert!(night.contains(7 * 60));
+        assert!(!night.contains(7 * 60 + 30));
+        assert!(!night.contains(12 * 60));
+
+        let lunch = QuietHours::parse("12:00 - 13:00").expect("lunch");
+        assert!(lunch.contains(12 * 60 + 59));
+        assert!(!lunch.contains(13 * 60));
+
+        for invalid in ["", "22:00", "24:00-07:00", "10:00-10:00", "ten-eleven"] {
+            assert_eq!(QuietHours::parse(invalid), None, "{invalid}");
+        }
+    }
+
     #[test]
     fn load_config_returns_defaults_for_missing_file() {
         // Just verify it doesn't panic and returns defaults
@@ -0,0 +1,205 @@
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::tmux::{self, Server};
+
+/// Forward at most one output event per session in this window, so a
+/// streaming agent does not flood the UI with wakeups.
+const OUTPUT_THROTTLE: Duration = Duration::from_millis(200);
+
+/// Notifications pushed by the tmux control-mode client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ControlEvent {
+    /// The followed session produced output.
+    Output { session: String },
+    /// Sessions or windows were created, closed or renamed, or a client
+    /// switched session or detached, anywhere on the server.
+    Changed,
+    /// The control client went away (server exited, session killed, ...).
+    Exited,
+}
+
+/// A `tmux -C` client attached to one session at a time. tmux only streams
+/// `%output` for the session a client is attached to, so the client follows
+/// whichever session lattice has focused; session and window changes are
+/// reported server-wide.
+pub struct ControlClient {
+    child: Child,
+    stdin: ChildStdin,
+    session: String,
+    /// Owned by the client so a replaced client's late events are dropped
+    /// with it.
+    events: Receiver<ControlEvent>,
+}
+
+impl ControlClient {
+    pub fn connect(session: &str) -> Result<Self> {
+        // ignore-size keeps this client from shrinking windows to its own
+        // (default 80x24) size.
+        let mut child = tmux::command(Server::Lattice)
+            .args(["-C", "attach-session", "-f", "ignore-size", "-t"])
+            .arg(format!("={session}"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .context("failed to start tmux control client")?;
+
+        let stdin = child
+            .stdin
+            .take()
+            .context("no stdin for tmux control client")?;
+        let stdout = child
+            .stdout
+            .take()
+            .context("no stdout for tmux control client")?;
+
+        let (events, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let mut current = String::new();
+            let mut last_output: HashMap<String, Instant> = HashMap::new();
+
+            for line in BufReader::new(stdout).split(b'\n') {
+                let Ok(line) = line else { break };
+                // %output payloads are octal-escaped but may still contain
+                // invalid UTF-8; only the prefix matters here.
+                let line = String::from_utf8_lossy(&line);
+                let event = match parse_line(&line) {
+                    Some(Notification::SessionChanged(name)) => {
+                        current = name;
+                        continue;
+                    }
+                    Some(Notification::Output) => {
+                        let now = Instant::now();
+                        let recent = last_output
+                            .get(&current)
+                            .is_some_and(|at| now.duration_since(*at) < OUTPUT_THROTTLE);
+                        if recent || current.is_empty() {
+                            continue;
+                        }
+                        last_output.insert(current.clone(), now);
+                        ControlEvent::Output {
+                            session: current.clone(),
+                        }
+                    }
+                    Some(Notification::Changed) => ControlEvent::Changed,
+                    Some(Notification::Exit) => break,
+                    None => continue,
+                };
+                if events.send(event).is_err() {
+                    return;
+                }
+            }
+            let _ = events.send(ControlEvent::Exited);
+        });
+
+        Ok(Self {
+            child,
+            stdin,
+            session: session.to_owned(),
+            events: rx,
+        })
+    }
+
+    /// Notifications received since the last call.
+    pub fn drain(&self) -> Vec<ControlEvent> {
+        self.events.try_iter().collect()
+    }
+
+    /// Session the client is currently attached to.
+    pub fn session(&self) -> &str {
+        &self.session
+    }
+
+    /// Move the client to another session so its output is streamed.
+    pub fn follow(&mut self, session: &str) -> Result<()> {
+        if self.session == session {
+            return Ok(());
+        }
+        writeln!(self.stdin, "switch-client -t '={session}'")
+            .context("tmux control client is gone")?;
+        self.session = session.to_owned();
+        Ok(())
+    }
+}
+
+impl Drop for ControlClient {
+    fn drop(&mut self) {
+        let _ = writeln!(self.stdin, "detach-client");
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Notification {
+    Output,
+    SessionChanged(String),
+    Changed,
+    Exit,
+}
+
+fn parse_line(line: &str) -> Option<Notification> {
+    let mut parts = line.splitn(3, ' ');
+    match parts.next()? {
+        "%output" | "%extended-output" => Some(Notification::Output),
+        // `%session-changed $id name` is sent on attach and on switch-client
+        "%session-changed" => {
+            let _id = parts.next()?;
+            Some(Notification::SessionChanged(parts.next()?.to_owned()))
+        }
+        // Clients switching sessions or detaching matter too: lattice may
+        // be waiting for a client to leave a tmux split.
+        "%sessions-changed"
+        | "%client-session-changed"
+        | "%client-detached"
+        | "%session-created"
+        | "%session-renamed"
+        | "%window-add"
+        | "%window-close"
+        | "%window-renamed"
+        | "%unlinked-window-add"
+        | "%unlinked-window-close"
+        | "%unlinked-window-renamed" => Some(Notification::Changed),
+        "%exit" => Some(Notification::Exit),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parse_line_recognises_notifications() {
+        assert_eq!(
+            parse_line("%output %17 echo hi\\015\\012"),
+            Some(Notification::Output)
+        );
+        assert_eq!(
+            parse_line("%session-changed $3 lattice_claude_1700000000"),
+            Some(Notification::SessionChanged(
+                "lattice_claude_1700000000".to_owned()
+            ))
+        );
+        assert_eq!(parse_line("%sessions-changed"), Some(Notification::Changed));
+        assert_eq!(
+            parse_line("%client-session-changed /dev/pts/3 $0 home"),
+            Some(Notification::Changed)
+        );
+        assert_eq!(
+            parse_line("%unlinked-window-renamed @18 newname"),
+            Some(Notification::Changed)
+        );
+        assert_eq!(parse_line("%exit"), Some(Notification::Exit));
+        assert_eq!(parse_line("%exit server exited"), Some(Notification::Exit));
+        assert_eq!(parse_line("%begin 1792182428 1065 0"), None);
+        assert_eq!(parse_line("some command output"), None);
+    }
+}
@@ -1,4 +1,5 @@
 use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::time::{SystemTime, UNIX_EPOCH};
@@ -14,10 +15,17 @@ pub fn is_git_repo(path: &Path) -> bool {
         .unwrap_or(false)
 }
 
+/// A lattice-created worktree and where it came from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Worktree {
+    pub path: PathBuf,
+    pub branch: String,
+    pub repo_root: PathBuf,
+}
+
 /// Create a worktree inside `<repo-root>/.lattice/worktrees/<short-id>/`
 /// on a new branch `lattice/<short-id>` from HEAD.
-/// Returns the worktree path.
-pub fn create_worktree(repo_path: &Path) -> Result<PathBuf> {
+pub fn create_worktree(repo_path: &Path) -> Result<Worktree> {
     // Find the repo root
     let output = Command::new("git")
         .args([
@@ -68,7 +76,11 @@ pub fn create_worktree(repo_path: &Path) -> Result<PathBuf> {
         anyhow::bail!("git worktree add failed: {}", stderr.trim());
     }
 
-    Ok(worktree_path)
+    Ok(Worktree {
+        path: worktree_path,
+        branch: branch_name,
+        repo_root: root,
+    })
 }
 
 /// Check if `path` is inside a `.lattice/worktrees/` directory.
@@ -78,68 +90,131 @@ pub fn is_worktree_path(path: &Path) -> bool {
     s.contains("/.lattice/worktrees/") || s.contains("\\.lattice\\worktrees\\")
 }
 
-/// Remove a worktree and its associated branch.
-/// `worktree_path` should be the path inside `.lattice/worktrees/<id>/`.
-/// The branch name is derived as `lattice/<id>`.
-pub fn remove_worktree(worktree_path: &Path) -> Result<()> {
-    // Derive the repo root: go up from .lattice/worktrees/<id>
-    // worktree_path = <root>/.lattice/worktrees/<id>
-    let id = worktree_path
-        .file_name()
-        .map(|f| f.to_string_lossy().to_string())
-        .unwrap_or_default();
+/// Branch name lattice uses for a worktree path (`lattice/<id>`), or `None`
+/// when the path is not a lattice worktree.
+pub fn worktree_branch(path: &Path) -> Option<String> {
+    if !is_worktree_path(path) {
+        return None;
+    }
+    let id = path.file_name()?.to_string_lossy();
+    Some(format!("lattice/{id}"))
+}
+
+/// Repository root a `.lattice/worktrees/<id>` path belongs to, read from
+/// the path alone.
+pub fn worktree_repo_root(path: &Path) -> Option<PathBuf> {
+    let worktrees = path
+        .ancestors()
+        .find(|p| p.ends_with(".lattice/worktrees"))?;
+    worktrees.parent()?.parent().map(Path::to_path_buf)
+}
 
-    // Find the main repo root by asking the worktree's git
-    let output = Command::new("git")
+/// Remove a worktree and its associated branch, using the recorded repo
+/// root and branch.
+pub fn remove_worktree(worktree: &Worktree) -> Result<()> {
+    let root = worktree.repo_root.to_string_lossy();
+
+    // Remove the worktree (--force in case of uncommitted changes)
+    let _ = Command::new("git")
         .args([
             "-C",
-            &worktree_path.to_string_lossy(),
+            &root,
             "worktree",
-            "list",
-            "--porcelain",
+            "remove",
+            "--force",
+            &worktree.path.to_string_lossy(),
         ])
-        .output()
-        .context("failed to run git worktree list")?;
-
-    let root = if output.status.success() {
-        // First "worktree <path>" line is the main worktree
-        String::from_utf8_lossy(&output.stdout)
-            .lines()
-            .find(|l| l.starts_with("worktree "))
-            .map(|l| l.strip_prefix("worktree ").unwrap_or(l).to_owned())
-            .unwrap_or_default()
-    } else {
-        String::new()
-    };
+        .output();
 
-    // Remove the worktree (--force in case of uncommitted changes)
-    if !root.is_empty() {
-        let _ = Command::new("git")
+    // If the directory still exists (e.g. git worktree remove failed), clean up manually
+    if worktree.path.exists() {
+        let _ = std::fs::remove_dir_all(&worktree.path);
+    }
+
+    // Drop any stale administrative entry left by the manual cleanup
+    let _ = Command::new("git")
+        .args(["-C", &root, "worktree", "prune"])
+        .output();
+
+    let _ = Command::new("git")
+        .args(["-C", &root, "branch", "-D", &worktree.branch])
+        .output();
+
+    Ok(())
+}
+
+/// Make sure a recorded worktree exists on disk again, re-checking out its
+/// branch (or a fresh branch from HEAD if the branch is gone too).
+pub fn restore_worktree(worktree: &Worktree) -> Result<()> {
+    if worktree.path.is_dir() {
+        return Ok(());
+    }
+    let root = worktree.repo_root.to_string_lossy();
+    let path = worktree.path.to_string_lossy();
+
+    // Drop the stale administrative entry so the path can be reused
+    let _ = Command::new("git")
+        .args(["-C", &root, "worktree", "prune"])
+        .output();
+
+    let mut output = Command::new("git")
+        .args(["-C", &root, "worktree", "add", &path, &worktree.branch])
+        .output()
+        .context("failed to run git worktree add")?;
+    if !output.status.success() {
+        output = Command::new("git")
             .args([
                 "-C",
                 &root,
                 "worktree",
-                "remove",
-                "--force",
-                &worktree_path.to_string_lossy(),
+                "add",
+                &path,
+                "-b",
+                &worktree.branch,
             ])
-            .output();
+            .output()
+            .context("failed to run git worktree add")?;
     }
 
-    // If the directory still exists (e.g. git worktree remove failed), clean up manually
-    if worktree_path.exists() {
-        let _ = std::fs::remove_dir_all(worktree_path);
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("git worktree add failed: {}", stderr.trim());
     }
+    Ok(())
+}
 
-    // Delete the branch
-    if !root.is_empty() && !id.is_empty() {
-        let branch = format!("lattice/{id}");
-        let _ = Command::new("git")
-            .args(["-C", &root, "branch", "-D", &branch])
-            .output();
+/// Reconstruct worktree metadata from a path inside
+/// `.lattice/worktrees/<id>/`, for sessions started before lattice kept a
+/// registry. The branch name is derived as `lattice/<id>`.
+pub fn worktree_from_path(worktree_path: &Path) -> Option<Worktree> {
+    let branch = worktree_branch(worktree_path)?;
+
+    // Find the main repo root by asking the worktree's git
+    let output = Command::new("git")
+        .args([
+            "-C",
+            &worktree_path.to_string_lossy(),
+            "worktree",
+            "list",
+            "--porcelain",
+        ])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
     }
 
-    Ok(())
+    // First "worktree <path>" line is the main worktree
+    let root = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|l| l.strip_prefix("worktree "))
+        .map(PathBuf::from)?;
+
+    Some(Worktree {
+        path: worktree_path.to_path_buf(),
+        branch,
+        repo_root: root,
+    })
 }
 
 /// Clone `url` into `dest_dir/<repo-name>/`. Returns the clone path.
@@ -220,6 +295,24 @@ mod tests {
         assert!(parse_repo_name("").is_err());
     }
 
+    #[test]
+    fn worktree_branch_from_path() {
+        assert_eq!(
+            worktree_branch(Path::new("/src/app/.lattice/worktrees/1700000000")),
+            Some("lattice/1700000000".to_owned())
+        );
+        assert_eq!(worktree_branch(Path::new("/src/app")), None);
+    }
+
+    #[test]
+    fn worktree_repo_root_from_path() {
+        assert_eq!(
+            worktree_repo_root(Path::new("/src/app/.lattice/worktrees/1700000000/crates")),
+            Some(PathBuf::from("/src/app"))
+        );
+        assert_eq!(worktree_repo_root(Path::new("/src/app")), None);
+    }
+
     #[test]
     fn is_git_repo_false_for_tmp() {
         assert!(!is_git_repo(Path::new("/tmp")));
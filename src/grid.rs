@@ -0,0 +1,160 @@
+use ratatui::layout::Rect;
+
+/// Most sessions the grid view shows at once.
+pub const MAX_PANES: usize = 6;
+
+/// How the grid view arranges its panes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GridLayout {
+    /// As square as possible, filling rows left to right.
+    #[default]
+    Tiled,
+    /// Side by side.
+    Columns,
+    /// Stacked top to bottom.
+    Rows,
+}
+
+impl GridLayout {
+    pub fn next(self) -> Self {
+        match self {
+            GridLayout::Tiled => GridLayout::Columns,
+            GridLayout::Columns => GridLayout::Rows,
+            GridLayout::Rows => GridLayout::Tiled,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GridLayout::Tiled => "tiled",
+            GridLayout::Columns => "columns",
+            GridLayout::Rows => "rows",
+        }
+    }
+
+    /// Number of columns used for `n` panes.
+    fn columns(self, n: usize) -> usize {
+        match self {
+            GridLayout::Tiled => (1..=n).find(|c| c * c >= n).unwrap_or(1),
+            GridLayout::Columns => n.max(1),
+            GridLayout::Rows => 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+/// Split `area` into `n` cells. A short last row stretches its cells to
+/// use the full width.
+pub fn cells(area: Rect, n: usize, layout: GridLayout) -> Vec<Rect> {
+    if n == 0 {
+        return Vec::new();
+    }
+    let cols = layout.columns(n);
+    let rows = n.div_ceil(cols);
+    let mut out = Vec::with_capacity(n);
+    for row in 0..rows {
+        let in_row = (n - row * cols).min(cols);
+        let y = area.y + split_at(area.height, rows, row);
+        let height = split_at(area.height, rows, row + 1) - split_at(area.height, rows, row);
+        for col in 0..in_row {
+            let x = area.x + split_at(area.width, in_row, col);
+            let width = split_at(area.width, in_row, col + 1) - split_at(area.width, in_row, col);
+            out.push(Rect::new(x, y, width, height));
+        }
+    }
+    out
+}
+
+/// Offset of the `i`th of `parts` equal slices of `len`.
+fn split_at(len: u16, parts: usize, i: usize) -> u16 {
+    (len as usize * i / parts.max(1)) as u16
+}
+
+/// Pane focused after moving from `focused` in `dir`, staying put at the
+/// edges.
+pub fn step(n: usize, layout: GridLayout, focused: usize, dir: Direction) -> usize {
+    if n == 0 {
+        return 0;
+    }
+    let cols = layout.columns(n);
+    let (row, col) = (focused / cols, focused % cols);
+    let target = match dir {
+        Direction::Left if col > 0 => focused - 1,
+        Direction::Right if col + 1 < cols => focused + 1,
+        Direction::Up if row > 0 => focused - cols,
+        Direction::Down => focused + cols,
+        _ => focused,
+    };
+    // Moving down into a short last row lands on its last pane.
+    if target >= n {
+        if row + 1 < n.div_ceil(cols) {
+            n - 1
+        } else {
+            focused
+        }
+    } else {
+        target
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn tiled_cells_cover_the_area() {
+        let area = Rect::new(0, 0, 100, 40);
+        let three = cells(area, 3, GridLayout::Tiled);
+        assert_eq!(
+            three,
+            vec![
+                Rect::new(0, 0, 50, 20),
+                Rect::new(50, 0, 50, 20),
+                Rect::new(0, 20, 100, 20),
+            ]
+        );
+        let six = cells(area, 6, GridLayout::Tiled);
+        assert_eq!(six.len(), 6);
+        assert_eq!(six[2], Rect::new(66, 0, 34, 20));
+        assert_eq!(six[5], Rect::new(66, 20, 34, 20));
+    }
+
+    #[test]
+    fn columns_and_rows_split_one_way() {
+        let area = Rect::new(2, 1, 90, 30);
+        assert_eq!(
+            cells(area, 3, GridLayout::Columns),
+            vec![
+                Rect::new(2, 1, 30, 30),
+                Rect::new(32, 1, 30, 30),
+                Rect::new(62, 1, 30, 30),
+            ]
+        );
+        assert_eq!(
+            cells(area, 2, GridLayout::Rows),
+            vec![Rect::new(2, 1, 90, 15), Rect::new(2, 16, 90, 15)]
+        );
+    }
+
+    #[test]
+    fn step_moves_within_the_grid() {
+        // 5 tiled panes: 3 columns, rows [0 1 2] [3 4]
+        assert_eq!(step(5, GridLayout::Tiled, 0, Direction::Right), 1);
+        assert_eq!(step(5, GridLayout::Tiled, 2, Direction::Right), 2);
+        assert_eq!(step(5, GridLayout::Tiled, 1, Direction::Down), 4);
+        assert_eq!(step(5, GridLayout::Tiled, 2, Direction::Down), 4);
+        assert_eq!(step(5, GridLayout::Tiled, 4, Direction::Down), 4);
+        assert_eq!(step(5, GridLayout::Tiled, 3, Direction::Up), 0);
+        assert_eq!(step(3, GridLayout::Rows, 0, Direction::Down), 1);
+        assert_eq!(step(3, GridLayout::Rows, 1, Direction::Right), 1);
+        assert_eq!(step(3, GridLayout::Columns, 1, Direction::Left), 0);
+    }
+}
@@ -0,0 +1,136 @@
+use anyhow::{Result, bail};
+use std::sync::mpsc::Sender;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::agents::AgentDefinition;
+use crate::config::AppConfig;
+use crate::tmux;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Consecutive polls the readiness marker must be visible for, so a marker
+/// drawn mid-boot and then cleared does not count.
+const READY_POLLS: u32 = 2;
+
+/// Text waiting to be typed into a freshly started agent.
+#[derive(Debug, Clone)]
+pub struct Injection {
+    pub session_name: String,
+    /// Pane the agent runs in, which the text is typed into.
+    pub pane_id: String,
+    pub text: String,
+    pub ready_markers: Vec<String>,
+    /// Give up if no marker shows up within this long.
+    pub timeout: Duration,
+    /// Used instead of marker detection for agents without markers.
+    pub fallback_delay: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub enum InjectEvent {
+    Delivered {
+        session_name: String,
+        waited: Duration,
+    },
+    Failed {
+        session_name: String,
+        error: String,
+    },
+}
+
+impl Injection {
+    pub fn for_agent(
+        config: &AppConfig,
+        agent: &AgentDefinition,
+        session_name: &str,
+        pane_id: &str,
+        text: String,
+    ) -> Self {
+        Self {
+            session_name: session_name.to_owned(),
+            pane_id: pane_id.to_owned(),
+            text,
+            ready_markers: agent.ready_markers.clone(),
+            timeout: Duration::from_secs(u64::from(config.ready_timeout.max(1))),
+            fallback_delay: Duration::from_secs(u64::from(config.title_injection_delay)),
+        }
+    }
+}
+
+/// Watch the session's pane until the agent shows one of its readiness
+/// markers, then deliver the text. Blocks; returns how long it waited.
+pub fn deliver(injection: &Injection) -> Result<Duration> {
+    let started = Instant::now();
+    let name = &injection.session_name;
+
+    if injection.ready_markers.is_empty() {
+        thread::sleep(injection.fallback_delay);
+    } else {
+        let mut hits = 0;
+        while hits < READY_POLLS {
+            if started.elapsed() >= injection.timeout {
+                bail!(
+                    "{name} not ready after {}s (no readiness marker seen)",
+                    injection.timeout.as_secs()
+                );
+            }
+            let Ok(lines) = tmux::capture_pane(&injection.pane_id) else {
+                bail!("{name} ended before it was ready for input");
+            };
+            if is_ready(&lines, &injection.ready_markers) {
+                hits += 1;
+            } else {
+                hits = 0;
+            }
+            if hits < READY_POLLS {
+                thread::sleep(POLL_INTERVAL);
+            }
+        }
+    }
+
+    tmux::send_text(tmux::Server::Lattice, &injection.pane_id, &injection.text)?;
+    Ok(started.elapsed())
+}
+
+/// Run `deliver` on a background thread and report the outcome on `events`.
+pub fn deliver_in_background(injection: Injection, events: Sender<InjectEvent>) {
+    thread::spawn(move || {
+        let event = match deliver(&injection) {
+            Ok(waited) => InjectEvent::Delivered {
+                session_name: injection.session_name,
+                waited,
+            },
+            Err(err) => InjectEvent::Failed {
+                session_name: injection.session_name,
+                error: err.to_string(),
+            },
+        };
+        let _ = events.send(event);
+    });
+}
+
+fn is_ready(lines: &[String], markers: &[String]) -> bool {
+    lines
+        .iter()
+        .any(|line| markers.iter().any(|m| line.contains(m.as_str())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ready_when_any_marker_is_on_screen() {
+        let markers = vec!["? for shortcuts".to_owned(), "Use /help".to_owned()];
+        let booting = vec!["Loading...".to_owned(), String::new()];
+        let ready = vec![
+            "> ".to_owned(),
+            "  ? for shortcuts          Bypassing permissions".to_owned(),
+        ];
+
+        assert!(!is_ready(&booting, &markers));
+        assert!(is_ready(&ready, &markers));
+        assert!(!is_ready(&ready, &[]));
+    }
+}
@@ -0,0 +1,708 @@
+use anyhow::{Result, anyhow};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::agents::{self, AgentDefinition};
+use crate::config::AppConfig;
+use crate::git;
+use crate::inject::Injection;
+use crate::procs::{self, Process};
+use crate::state::{Registry, SessionRecord};
+use crate::status::{self, AgentState};
+use crate::tmux;
+
+/// A tmux session that was classified as a coding agent.
+#[derive(Debug, Clone)]
+pub struct AgentInstance {
+    pub agent: AgentDefinition,
+    pub session: tmux::Session,
+    pub managed: bool,
+    pub title_override: String,
+    /// What lattice recorded when it spawned this session, if it did.
+    pub record: Option<SessionRecord>,
+    pub state: AgentState,
+    /// The agent's process, when it was found under one of the session's
+    /// panes.
+    pub process: Option<Process>,
+}
+
+impl AgentInstance {
+    pub fn display_title(&self) -> String {
+        agents::derive_display_title(
+            &self.session.name,
+            &self.session.pane_title,
+            &self.session.pane_current_path,
+            &self.title_override,
+        )
+    }
+
+    /// Panes of the session other than the agent's, e.g. shells opened
+    /// with `t`.
+    pub fn helper_panes(&self) -> impl Iterator<Item = &tmux::Pane> {
+        self.session
+            .panes
+            .iter()
+            .filter(|p| p.id != self.session.pane_id)
+    }
+
+    /// Mark the agent's pane in tmux, so every listing reports it even
+    /// while a helper pane is active.
+    pub fn mark_agent_pane(&self) {
+        let marked = self
+            .session
+            .panes
+            .iter()
+            .any(|p| p.agent && p.id == self.session.pane_id);
+        if !marked && self.session.panes.len() > 1 {
+            let _ = tmux::mark_agent_pane(self.session.server, &self.session.pane_id);
+        }
+    }
+
+    /// The session's server and name; names are only unique per server.
+    pub fn key(&self) -> (tmux::Server, String) {
+        (self.session.server, self.session.name.clone())
+    }
+
+    pub fn worktree(&self) -> Option<&git::Worktree> {
+        self.record.as_ref()?.worktree.as_ref()
+    }
+
+    /// Re-read `state` from the session's current preview and processes.
+    pub fn update_state(&mut self) {
+        self.state = read_state(&self.agent, &self.session);
+    }
+}
+
+/// Classify what the agent in `session` is doing.
+pub fn read_state(agent: &AgentDefinition, session: &tmux::Session) -> AgentState {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    status::classify(&status::Signals {
+        preview: &session.preview,
+        current_command: &session.current_command,
+        has_children: status::has_children(session.pane_pid),
+        ready_markers: &agent.ready_markers,
+        patterns: &agent.screen,
+        age: now.saturating_sub(session.created_unix),
+        idle: now.saturating_sub(session.activity_unix),
+    })
+}
+
+/// Result of a successful spawn.
+#[derive(Debug, Clone)]
+pub struct Spawned {
+    pub session_name: String,
+    pub working_dir: String,
+    /// Set when a worktree was requested but could not be created; the agent
+    /// was started in the original directory instead.
+    pub worktree_error: Option<String>,
+    /// Set when the session started but its registry record could not be
+    /// written.
+    pub record_error: Option<String>,
+    /// Text that still has to be typed into the agent once it is ready. The
+    /// caller decides whether to wait for it or hand it to a background
+    /// thread.
+    pub injection: Option<Injection>,
+}
+
+/// Result of stopping an instance.
+#[derive(Debug, Clone)]
+pub enum Stopped {
+    Plain,
+    WorktreeCleaned,
+    WorktreeCleanupFailed(String),
+}
+
+/// What one listing found.
+#[derive(Debug, Clone, Default)]
+pub struct Discovery {
+    pub instances: Vec<AgentInstance>,
+    /// Registry records whose tmux session is gone.
+    pub restorable: Vec<SessionRecord>,
+}
+
+/// The latest listing, shared by the dashboard and the activity monitor so
+/// the two don't each list tmux and walk `/proc` on their own schedule.
+#[derive(Clone, Default)]
+pub struct SharedDiscovery {
+    inner: Arc<Mutex<Shared>>,
+}
+
+#[derive(Default)]
+struct Shared {
+    cache: DiscoveryCache,
+    latest: Option<Latest>,
+}
+
+struct Latest {
+    taken_at: Instant,
+    include_default: bool,
+    discovery: Discovery,
+}
+
+impl SharedDiscovery {
+    /// The latest listing, if it is younger than `max_age` and covers the
+    /// same servers.
+    pub fn recent(&self, max_age: Duration, include_default: bool) -> Option<Discovery> {
+        let shared = self.lock();
+        let latest = shared.latest.as_ref()?;
+        (latest.include_default == include_default && latest.taken_at.elapsed() < max_age)
+            .then(|| latest.discovery.clone())
+    }
+
+    /// List all sessions, only recapturing panes that changed since the
+    /// last listing plus the sessions in `visible`. Also forgets recorded
+    /// sessions that exited since then.
+    pub fn discover(
+        &self,
+        available: &[AgentDefinition],
+        visible: &[String],
+        include_default: bool,
+    ) -> Result<Discovery> {
+        self.list(available, visible, include_default, false)
+    }
+
+    /// Like `discover`, for a change control mode reported: sessions whose
+    /// panes are the same as last time keep their classification, and the
+    /// registry and title files are left for the next `discover`.
+    pub fn update(
+        &self,
+        available: &[AgentDefinition],
+        visible: &[String],
+        include_default: bool,
+    ) -> Result<Discovery> {
+        self.list(available, visible, include_default, true)
+    }
+
+    fn list(
+        &self,
+        available: &[AgentDefinition],
+        visible: &[String],
+        include_default: bool,
+        reuse: bool,
+    ) -> Result<Discovery> {
+        let mut shared = self.lock();
+        let reuse = reuse
+            && shared
+                .latest
+                .as_ref()
+                .is_some_and(|l| l.include_default == include_default);
+        let discovery = discover_cached(
+            available,
+            &mut shared.cache,
+            visible,
+            include_default,
+            reuse,
+        )?;
+        shared.latest = Some(Latest {
+            taken_at: Instant::now(),
+            include_default,
+            discovery: discovery.clone(),
+        });
+        Ok(discovery)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Shared> {
+        // A listing that panicked half way leaves nothing worth refusing.
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// State kept between listings.
+#[derive(Default)]
+struct DiscoveryCache {
+    captures: tmux::CaptureCache,
+    /// Registry records as of the last listing.
+    records: Vec<SessionRecord>,
+    /// Recorded sessions that were running at the last listing.
+    running: HashSet<String>,
+    /// Every session of the last listing, with its panes and what it was
+    /// classified as.
+    classified: Classified,
+}
+
+type Classified = HashMap<(tmux::Server, String), (Vec<(String, u32)>, Option<AgentInstance>)>;
+
+/// List all tmux sessions and keep the ones that look like coding agents,
+/// sorted by session name. `include_default` also lists the default tmux
+/// server when lattice runs on a dedicated socket.
+pub fn discover(
+    available: &[AgentDefinition],
+    include_default: bool,
+) -> Result<Vec<AgentInstance>> {
+    let mut cache = DiscoveryCache::default();
+    Ok(discover_cached(available, &mut cache, &[], include_default, false)?.instances)
+}
+
+fn discover_cached(
+    available: &[AgentDefinition],
+    cache: &mut DiscoveryCache,
+    visible: &[String],
+    include_default: bool,
+    reuse: bool,
+) -> Result<Discovery> {
+    let discovery = list_and_classify(available, cache, visible, include_default, reuse)?;
+    // Agents found outside the pane their session was listed by have no
+    // preview yet; list once more now that their panes are known.
+    let agent_panes = discovery
+        .instances
+        .iter()
+        .filter(|i| i.session.panes.len() > 1)
+        .filter(|i| !i.session.panes.iter().any(|p| p.agent))
+        .map(|i| (i.key(), i.session.pane_id.clone()))
+        .collect();
+    if cache.captures.remember_agent_panes(agent_panes) {
+        return list_and_classify(available, cache, visible, include_default, true);
+    }
+    Ok(discovery)
+}
+
+fn list_and_classify(
+    available: &[AgentDefinition],
+    cache: &mut DiscoveryCache,
+    visible: &[String],
+    include_default: bool,
+    reuse: bool,
+) -> Result<Discovery> {
+    let sessions = tmux::list_sessions_cached(&mut cache.captures, visible, include_default)?;
+    let live: HashSet<&str> = sessions
+        .iter()
+        .filter(|s| s.server == tmux::Server::Lattice)
+        .map(|s| s.name.as_str())
+        .collect();
+
+    let registry = Registry::open();
+    if !reuse {
+        cache.records = registry.load_all();
+    }
+    // A recorded session that was running last time and is gone while its
+    // server still has others was exited or killed in tmux; one lost with
+    // the whole server (a crash or reboot) may still be restored. An empty
+    // listing can't tell the two apart, so it keeps everything.
+    if !live.is_empty() {
+        let running = &cache.running;
+        cache.records.retain(|r| {
+            let exited =
+                running.contains(&r.session_name) && !live.contains(r.session_name.as_str());
+            !(exited && registry.remove(&r.session_name).is_ok())
+        });
+    }
+    cache.running = cache
+        .records
+        .iter()
+        .filter(|r| live.contains(r.session_name.as_str()))
+        .map(|r| r.session_name.clone())
+        .collect();
+
+    let records = cache.records.clone();
+    Ok(classify(
+        available,
+        sessions,
+        records,
+        &mut cache.classified,
+        reuse,
+    ))
+}
+
+/// Classify every session, filling `classified` in. With `reuse`, sessions
+/// that have the same panes as in `classified` keep what they were found to
+/// be there.
+fn classify(
+    available: &[AgentDefinition],
+    sessions: Vec<tmux::Session>,
+    records: Vec<SessionRecord>,
+    classified: &mut Classified,
+    reuse: bool,
+) -> Discovery {
+    let (live, restorable): (Vec<SessionRecord>, Vec<SessionRecord>) =
+        records.into_iter().partition(|r| {
+            sessions
+                .iter()
+                .any(|s| s.server == tmux::Server::Lattice && s.name == r.session_name)
+        });
+    let mut records: HashMap<String, SessionRecord> = live
+        .into_iter()
+        .map(|r| (r.session_name.clone(), r))
+        .collect();
+
+    let mut previous = std::mem::take(classified);
+    if !reuse {
+        previous.clear();
+    }
+    let mut instances = Vec::new();
+    for session in sessions {
+        let key = (session.server, session.name.clone());
+        let panes: Vec<(String, u32)> = session
+            .panes
+            .iter()
+            .map(|p| (p.id.clone(), p.pid))
+            .collect();
+        // Records only describe sessions lattice spawned on its own server
+        let record = match session.server {
+            tmux::Server::Lattice => records.remove(&session.name),
+            tmux::Server::Default => None,
+        };
+        let instance = match previous.remove(&key) {
+            Some((before, known)) if before == panes => known.map(|known| {
+                let mut instance = AgentInstance {
+                    session,
+                    record,
+                    ..known
+                };
+                instance.update_state();
+                instance
+            }),
+            _ => classify_session(available, session, record),
+        };
+        instances.extend(instance.clone());
+        classified.insert(key, (panes, instance));
+    }
+
+    instances.sort_by(|a, b| a.session.name.cmp(&b.session.name));
+    Discovery {
+        instances,
+        restorable,
+    }
+}
+
+fn classify_session(
+    available: &[AgentDefinition],
+    mut session: tmux::Session,
+    record: Option<SessionRecord>,
+) -> Option<AgentInstance> {
+    let trees: Vec<Vec<Process>> = session.panes.iter().map(|p| procs::tree(p.pid)).collect();
+    let agent = agents::classify_agent_from_session(
+        &session.name,
+        &session.current_command,
+        &trees.concat(),
+        available,
+    )?;
+    let found = session.panes.iter().zip(&trees).find_map(|(pane, tree)| {
+        let process = agents::find_agent_process(&agent, tree)?;
+        Some((pane.id.clone(), pane.agent, process.clone()))
+    });
+    let process = match found {
+        Some((pane_id, marked, process)) => {
+            // Listing only reads tmux: the pane is marked once the user
+            // works with the session, until then it is known in memory.
+            if !marked && session.pane_id != pane_id {
+                session.use_pane(&pane_id);
+            }
+            Some(process)
+        }
+        None => None,
+    };
+    let managed = agents::managed_session_agent_id(&session.name).is_some();
+    let title_override = agents::read_title_file(&session.name);
+    Some(AgentInstance {
+        state: read_state(&agent, &session),
+        agent,
+        session,
+        managed,
+        title_override,
+        record,
+        process,
+    })
+}
+
+/// Find an instance by full session name, falling back to its short name
+/// (e.g. `claude_1700000000` for `lattice_claude_1700000000`).
+pub fn find<'a>(instances: &'a [AgentInstance], query: &str) -> Option<&'a AgentInstance> {
+    instances
+        .iter()
+        .find(|i| i.session.name == query)
+        .or_else(|| {
+            instances
+                .iter()
+                .find(|i| agents::short_instance_name(&i.session.name) == query)
+        })
+}
+
+/// Start `agent` in a new managed tmux session rooted at `working_dir`.
+/// When `worktree` is set and the directory is a git repository, the agent
+/// runs inside a fresh `.lattice/worktrees/<id>` checkout instead.
+/// An optional `task` is passed on the command line when the agent accepts
+/// one, and typed into the agent once it has booted otherwise.
+pub fn spawn(
+    config: &AppConfig,
+    agent: &AgentDefinition,
+    working_dir: &str,
+    worktree: bool,
+    task: Option<&str>,
+) -> Result<Spawned> {
+    let task = task.map(str::trim).filter(|t| !t.is_empty());
+    let mut worktree_error = None;
+    let created_worktree = if worktree && git::is_git_repo(Path::new(working_dir)) {
+        match git::create_worktree(Path::new(working_dir)) {
+            Ok(wt) => Some(wt),
+            Err(err) => {
+                worktree_error = Some(err.to_string());
+                None
+            }
+        }
+    } else {
+        None
+    };
+    let final_dir = match &created_worktree {
+        Some(wt) => wt.path.to_string_lossy().to_string(),
+        None => working_dir.to_owned(),
+    };
+
+    let session_name = agents::build_managed_session_name(&agent.id);
+    let title_enabled = config.title_injection_enabled;
+    let launch_cmd = agents::build_launch_command(agent, title_enabled, task);
+
+    let pane_id = tmux::create_session(&session_name, &final_dir, &launch_cmd)
+        .map_err(|err| anyhow!("failed to start {}: {err}", agent.label))?;
+
+    let record = SessionRecord::new(
+        &session_name,
+        &agent.id,
+        working_dir,
+        &final_dir,
+        created_worktree,
+        task,
+    );
+    let record_error = Registry::open().save(&record).err().map(|e| e.to_string());
+
+    let injection = first_input(config, agent, &session_name, &pane_id, task);
+
+    Ok(Spawned {
+        session_name,
+        working_dir: final_dir,
+        worktree_error,
+        record_error,
+        injection,
+    })
+}
+
+/// For agents without a system-prompt flag, inject a first message asking
+/// them to write task titles to a temp file. Agents that cannot take the task
+/// as an argument get it typed in the same way (folded into the title message
+/// when both apply).
+fn first_input(
+    config: &AppConfig,
+    agent: &AgentDefinition,
+    session_name: &str,
+    pane_id: &str,
+    task: Option<&str>,
+) -> Option<Injection> {
+    let typed_task = task.filter(|_| agents::needs_task_injection(agent));
+    let message = if config.title_injection_enabled && agents::needs_title_injection(agent) {
+        Some(agents::build_title_injection(session_name, typed_task))
+    } else {
+        typed_task.map(ToOwned::to_owned)
+    };
+    message.map(|msg| Injection::for_agent(config, agent, session_name, pane_id, msg))
+}
+
+/// Registry records whose tmux session is gone, e.g. after a reboot. For
+/// one-off commands; the dashboard gets them from `discover_cached`.
+pub fn restorable() -> Result<Vec<SessionRecord>> {
+    let live: HashSet<String> = tmux::session_names()?.into_iter().collect();
+    Ok(Registry::open()
+        .load_all()
+        .into_iter()
+        .filter(|r| !live.contains(&r.session_name))
+        .collect())
+}
+
+/// Find a restorable record by full session name or short name.
+pub fn find_record<'a>(records: &'a [SessionRecord], query: &str) -> Option<&'a SessionRecord> {
+    records
+        .iter()
+        .find(|r| r.session_name == query)
+        .or_else(|| {
+            records
+                .iter()
+                .find(|r| agents::short_instance_name(&r.session_name) == query)
+        })
+}
+
+/// Result of restoring a session.
+#[derive(Debug, Clone)]
+pub struct Restored {
+    pub session_name: String,
+    /// The agent was started with its resume flag rather than from scratch.
+    pub resumed: bool,
+    pub injection: Option<Injection>,
+}
+
+/// Recreate a recorded session under its old name and directory. Agents with
+/// resume support continue their previous conversation; others start fresh
+/// on the recorded initial prompt.
+pub fn restore(
+    config: &AppConfig,
+    available: &[AgentDefinition],
+    record: &SessionRecord,
+) -> Result<Restored> {
+    let agent = available
+        .iter()
+        .find(|a| a.id == record.agent_id)
+        .ok_or_else(|| anyhow!("agent `{}` is not installed", record.agent_id))?;
+
+    if let Some(wt) = &record.worktree {
+        git::restore_worktree(wt)?;
+    }
+    if !Path::new(&record.working_dir).is_dir() {
+        return Err(anyhow!("{} no longer exists", record.working_dir));
+    }
+
+    let title_enabled = config.title_injection_enabled;
+    let task = record.initial_prompt.as_deref();
+    let resume = agents::build_resume_command(agent, title_enabled);
+    let resumed = resume.is_some();
+    let launch_cmd =
+        resume.unwrap_or_else(|| agents::build_launch_command(agent, title_enabled, task));
+
+    let pane_id = tmux::create_session(&record.session_name, &record.working_dir, &launch_cmd)
+        .map_err(|err| anyhow!("failed to start {}: {err}", agent.label))?;
+    // A resumed conversation already has its title and task.
+    let injection = if resumed {
+        None
+    } else {
+        first_input(config, agent, &record.session_name, &pane_id, task)
+    };
+    // Rewriting the record bumps its `updated_at`.
+    let _ = Registry::open().save(record);
+
+    Ok(Restored {
+        session_name: record.session_name.clone(),
+        resumed,
+        injection,
+    })
+}
+
+/// Drop a restorable record for good, removing its worktree and branch.
+pub fn forget(record: &SessionRecord) -> Result<Stopped> {
+    Registry::open().remove(&record.session_name)?;
+    Ok(match &record.worktree {
+        Some(wt) => match git::remove_worktree(wt) {
+            Ok(()) => Stopped::WorktreeCleaned,
+            Err(err) => Stopped::WorktreeCleanupFailed(err.to_string()),
+        },
+        None => Stopped::Plain,
+    })
+}
+
+/// Kill the instance's tmux session, remove the worktree it was spawned
+/// in (if any) along with its branch, and forget its registry record.
+pub fn stop(instance: &AgentInstance) -> Result<Stopped> {
+    let worktree = worktree_to_clean(instance);
+
+    tmux::kill_session(instance.session.server, &instance.session.name)?;
+    if instance.record.is_some() {
+        let _ = Registry::open().remove(&instance.session.name);
+    }
+
+    Ok(match worktree {
+        Some(wt) => match git::remove_worktree(&wt) {
+            Ok(()) => Stopped::WorktreeCleaned,
+            Err(err) => Stopped::WorktreeCleanupFailed(err.to_string()),
+        },
+        None => Stopped::Plain,
+    })
+}
+
+/// The worktree the session was actually started in, whatever
+/// `git_worktrees` says now: `spawn --worktree` creates one either way.
+fn worktree_to_clean(instance: &AgentInstance) -> Option<git::Worktree> {
+    if let Some(record) = &instance.record {
+        return record.worktree.clone();
+    }
+
+    // Sessions spawned before the registry existed: fall back to guessing
+    // from where the pane currently is.
+    if !instance.managed || instance.session.pane_current_path.is_empty() {
+        return None;
+    }
+    git::worktree_from_path(Path::new(&instance.session.pane_current_path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn session(name: &str, panes: &[&str]) -> tmux::Session {
+        tmux::Session {
+            name: name.to_owned(),
+            server: tmux::Server::Lattice,
+            attached: false,
+            windows: 1,
+            created: String::new(),
+            created_unix: 0,
+            current_command: "bash".to_owned(),
+            pane_current_path: "/src/app".to_owned(),
+            pane_title: String::new(),
+            notify_rule: String::new(),
+            alert_watch: None,
+            pane_id: panes[0].to_owned(),
+            pane_pid: 0,
+            panes: panes
+                .iter()
+                .map(|id| tmux::Pane {
+                    id: (*id).to_owned(),
+                    ..tmux::Pane::default()
+                })
+                .collect(),
+            activity_unix: 0,
+            preview: Vec::new(),
+            last_line: String::new(),
+        }
+    }
+
+    #[test]
+    fn update_keeps_the_classification_of_unchanged_sessions() {
+        let name = "work";
+        let known = AgentInstance {
+            agent: AgentDefinition {
+                id: "claude".to_owned(),
+                ..AgentDefinition::default()
+            },
+            session: session(name, &["%1"]),
+            managed: false,
+            title_override: String::new(),
+            record: None,
+            state: AgentState::Starting,
+            process: None,
+        };
+        let mut classified = Classified::new();
+        classified.insert(
+            (tmux::Server::Lattice, name.to_owned()),
+            (vec![("%1".to_owned(), 0)], Some(known)),
+        );
+        let gone = SessionRecord::new("lattice_codex_1", "codex", "/src", "/src", None, None);
+
+        // Nothing in the pane looks like an agent, so only a kept
+        // classification finds one.
+        let found = classify(
+            &[],
+            vec![session(name, &["%1"])],
+            vec![gone.clone()],
+            &mut classified,
+            true,
+        );
+        let names: Vec<&str> = found
+            .instances
+            .iter()
+            .map(|i| i.session.name.as_str())
+            .collect();
+        assert_eq!(names, vec![name]);
+        assert_eq!(found.restorable, vec![gone]);
+
+        // A new pane gets the session classified again.
+        let found = classify(
+            &[],
+            vec![session(name, &["%1", "%2"])],
+            Vec::new(),
+            &mut classified,
+            true,
+        );
+        assert!(found.instances.is_empty());
+    }
+}
@@ -0,0 +1,238 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::fmt;
+
+/// A key plus modifiers written like `ctrl-q` or `alt-x`, used for the
+/// chord that leaves interact mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Chord {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl Chord {
+    pub const DEFAULT: &'static str = "ctrl-q";
+
+    /// Parse `[ctrl-][alt-][shift-]<key>` where `<key>` is a single
+    /// character, `esc`, `tab`, `enter`, `space`, `backspace` or `f1`..`f12`.
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim().to_ascii_lowercase();
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = s.as_str();
+        loop {
+            let (modifier, tail) = if let Some(t) = rest.strip_prefix("ctrl-") {
+                (KeyModifiers::CONTROL, t)
+            } else if let Some(t) = rest.strip_prefix("c-") {
+                (KeyModifiers::CONTROL, t)
+            } else if let Some(t) = rest.strip_prefix("alt-") {
+                (KeyModifiers::ALT, t)
+            } else if let Some(t) = rest.strip_prefix("m-") {
+                (KeyModifiers::ALT, t)
+            } else if let Some(t) = rest.strip_prefix("shift-") {
+                (KeyModifiers::SHIFT, t)
+            } else {
+                break;
+            };
+            modifiers |= modifier;
+            rest = tail;
+        }
+
+        let code = match rest {
+            "esc" | "escape" => KeyCode::Esc,
+            "tab" => KeyCode::Tab,
+            "enter" => KeyCode::Enter,
+            "space" => KeyCode::Char(' '),
+            "backspace" => KeyCode::Backspace,
+            f if f.len() > 1 && f.starts_with('f') => KeyCode::F(f[1..].parse().ok()?),
+            c => {
+                let mut chars = c.chars();
+                let ch = chars.next()?;
+                if chars.next().is_some() {
+                    return None;
+                }
+                KeyCode::Char(ch)
+            }
+        };
+        Some(Self { code, modifiers })
+    }
+
+    pub fn matches(&self, key: &KeyEvent) -> bool {
+        let code = match key.code {
+            KeyCode::Char(c) => KeyCode::Char(c.to_ascii_lowercase()),
+            other => other,
+        };
+        // Terminals disagree on whether Shift is reported for characters.
+        let modifiers = key.modifiers - KeyModifiers::SHIFT;
+        code == self.code && modifiers == self.modifiers - KeyModifiers::SHIFT
+    }
+}
+
+impl Default for Chord {
+    fn default() -> Self {
+        Self::parse(Self::DEFAULT).expect("default chord parses")
+    }
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "ctrl-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "alt-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "shift-")?;
+        }
+        match self.code {
+            KeyCode::Esc => write!(f, "esc"),
+            KeyCode::Tab => write!(f, "tab"),
+            KeyCode::Enter => write!(f, "enter"),
+            KeyCode::Backspace => write!(f, "backspace"),
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{c}"),
+            KeyCode::F(n) => write!(f, "f{n}"),
+            _ => write!(f, "?"),
+        }
+    }
+}
+
+/// How a keypress is passed to `tmux send-keys`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TmuxKey {
+    /// Plain text, sent with `send-keys -l`.
+    Literal(String),
+    /// A tmux key name such as `C-c`, `Up` or `M-Enter`.
+    Named(String),
+}
+
+/// Translate a crossterm key event into what tmux needs to replay it in a
+/// pane. Keys tmux has no name for are dropped.
+pub fn to_tmux(key: &KeyEvent) -> Option<TmuxKey> {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    let alt = key.modifiers.contains(KeyModifiers::ALT);
+    let shift = key.modifiers.contains(KeyModifiers::SHIFT);
+
+    let name = match key.code {
+        KeyCode::Char(' ') if ctrl || alt => "Space".to_owned(),
+        KeyCode::Char(c) if !ctrl && !alt => return Some(TmuxKey::Literal(c.to_string())),
+        KeyCode::Char(c) => c.to_ascii_lowercase().to_string(),
+        KeyCode::Enter => "Enter".to_owned(),
+        KeyCode::Tab if shift => return Some(TmuxKey::Named("BTab".to_owned())),
+        KeyCode::Tab => "Tab".to_owned(),
+        KeyCode::BackTab => return Some(TmuxKey::Named("BTab".to_owned())),
+        KeyCode::Backspace => "BSpace".to_owned(),
+        KeyCode::Esc => "Escape".to_owned(),
+        KeyCode::Up => "Up".to_owned(),
+        KeyCode::Down => "Down".to_owned(),
+        KeyCode::Left => "Left".to_owned(),
+        KeyCode::Right => "Right".to_owned(),
+        KeyCode::Home => "Home".to_owned(),
+        KeyCode::End => "End".to_owned(),
+        KeyCode::PageUp => "PPage".to_owned(),
+        KeyCode::PageDown => "NPage".to_owned(),
+        KeyCode::Delete => "DC".to_owned(),
+        KeyCode::Insert => "IC".to_owned(),
+        KeyCode::F(n) => format!("F{n}"),
+        _ => return None,
+    };
+
+    let mut prefix = String::new();
+    if ctrl {
+        prefix.push_str("C-");
+    }
+    if alt {
+        prefix.push_str("M-");
+    }
+    // Shift is already part of the character for printable keys.
+    if shift && !matches!(key.code, KeyCode::Char(_)) {
+        prefix.push_str("S-");
+    }
+    Some(TmuxKey::Named(format!("{prefix}{name}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn chords_parse_and_match() {
+        let chord = Chord::parse("Ctrl-Q").expect("parse");
+        assert!(chord.matches(&key(KeyCode::Char('q'), KeyModifiers::CONTROL)));
+        assert!(!chord.matches(&key(KeyCode::Char('q'), KeyModifiers::NONE)));
+        assert_eq!(chord.to_string(), "ctrl-q");
+
+        let chord = Chord::parse("alt-esc").expect("parse");
+        assert!(chord.matches(&key(KeyCode::Esc, KeyModifiers::ALT)));
+        assert_eq!(
+            Chord::parse("f12").map(|c| c.to_string()).as_deref(),
+            Some("f12")
+        );
+
+        assert_eq!(Chord::parse("ctrl-"), None);
+        assert_eq!(Chord::parse("hyper-x"), None);
+        assert_eq!(Chord::default().to_string(), Chord::DEFAULT);
+    }
+
+    #[test]
+    fn keys_translate_to_tmux_names() {
+        let cases = [
+            (
+                key(KeyCode::Char('a'), KeyModifiers::NONE),
+                TmuxKey::Literal("a".to_owned()),
+            ),
+            (
+                key(KeyCode::Char('A'), KeyModifiers::SHIFT),
+                TmuxKey::Literal("A".to_owned()),
+            ),
+            (
+                key(KeyCode::Char('c'), KeyModifiers::CONTROL),
+                TmuxKey::Named("C-c".to_owned()),
+            ),
+            (
+                key(KeyCode::Char('x'), KeyModifiers::ALT),
+                TmuxKey::Named("M-x".to_owned()),
+            ),
+            (
+                key(KeyCode::Char(' '), KeyModifiers::CONTROL),
+                TmuxKey::Named("C-Space".to_owned()),
+            ),
+            (
+                key(KeyCode::Up, KeyModifiers::NONE),
+                TmuxKey::Named("Up".to_owned()),
+            ),
+            (
+                key(KeyCode::Left, KeyModifiers::CONTROL),
+                TmuxKey::Named("C-Left".to_owned()),
+            ),
+            (
+                key(KeyCode::Tab, KeyModifiers::SHIFT),
+                TmuxKey::Named("BTab".to_owned()),
+            ),
+            (
+                key(KeyCode::BackTab, KeyModifiers::SHIFT),
+                TmuxKey::Named("BTab".to_owned()),
+            ),
+            (
+                key(KeyCode::Esc, KeyModifiers::NONE),
+                TmuxKey::Named("Escape".to_owned()),
+            ),
+            (
+                key(KeyCode::Backspace, KeyModifiers::NONE),
+                TmuxKey::Named("BSpace".to_owned()),
+            ),
+            (
+                key(KeyCode::PageDown, KeyModifiers::NONE),
+                TmuxKey::Named("NPage".to_owned()),
+            ),
+        ];
+        for (event, expected) in cases {
+            assert_eq!(to_tmux(&event), Some(expected), "{event:?}");
+        }
+        assert_eq!(to_tmux(&key(KeyCode::CapsLock, KeyModifiers::NONE)), None);
+    }
+}
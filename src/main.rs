@@ -1,19 +1,37 @@
 mod agents;
+mod ansi;
+mod cli;
 mod config;
+mod control;
 mod git;
+mod grid;
+mod inject;
+mod instances;
+mod keys;
+mod monitor;
+mod notify;
 mod pathnav;
+mod pick;
+mod procs;
+mod state;
+mod status;
 mod tmux;
 
 use agents::AgentDefinition;
 use anyhow::Result;
 use clap::Parser;
+use control::{ControlClient, ControlEvent};
 use crossterm::{
     event::{
-        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind, KeyModifiers,
+        self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+        Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers,
     },
     execute,
-    terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
+    terminal::{
+        self as term, EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode,
+    },
 };
+use instances::{AgentInstance, Stopped};
 use pathnav::{ActivateResult, Browser, EntryKind};
 use ratatui::{
     Terminal,
@@ -23,9 +41,13 @@ use ratatui::{
     text::{Line, Span, Text},
     widgets::{Block, Borders, Clear, Paragraph, Wrap},
 };
+use state::{Inbox, Notification, NotificationKind, SessionRecord};
+use status::AgentState;
 use std::{
+    collections::{HashMap, HashSet},
     env,
-    io::{self, Stdout},
+    io::{self, Stdout, Write},
+    sync::mpsc::{self, Receiver, Sender},
     time::{Duration, Instant},
 };
 
@@ -34,19 +56,24 @@ use std::{
 struct Cli {
     #[arg(long, help = "Auto refresh interval in seconds")]
     refresh_seconds: Option<u64>,
-}
 
-#[derive(Debug, Clone)]
-struct AgentInstance {
-    agent: AgentDefinition,
-    session: tmux::Session,
-    managed: bool,
-    title_override: String,
+    #[command(subcommand)]
+    command: Option<cli::Command>,
 }
 
+/// A session by server and name: names are only unique per tmux server.
+type SessionKey = (tmux::Server, String);
+
 #[derive(Debug, Clone)]
 struct SplitPane {
     session_name: String,
+    server: tmux::Server,
+}
+
+impl SplitPane {
+    fn key(&self) -> SessionKey {
+        (self.server, self.session_name.clone())
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -54,12 +81,52 @@ struct SplitState {
     panes: Vec<SplitPane>,
 }
 
+/// Sessions picked in split mode, rendered side by side by lattice itself.
+#[derive(Debug, Clone)]
+struct GridView {
+    panes: Vec<SplitPane>,
+    layout: grid::GridLayout,
+    focused: usize,
+}
+
+/// A pane of the current tab's session picked instead of the agent's, for
+/// preview, interact, focus, kill and attach.
+#[derive(Debug, Clone)]
+struct ViewedPane {
+    session_name: String,
+    pane_id: String,
+}
+
+/// Text box for sending a prompt to a running agent without attaching.
+#[derive(Debug, Clone)]
+struct SendBox {
+    session_name: String,
+    server: tmux::Server,
+    /// The agent's pane, which the text goes to.
+    pane_id: String,
+    title: String,
+    text: String,
+}
+
+/// A session's notification rule being edited.
+struct NotifyBox {
+    session_name: String,
+    server: tmux::Server,
+    title: String,
+    rule: monitor::NotifyRule,
+    /// 0: notify mode, 1: settle time.
+    selected: usize,
+    /// Settle seconds being typed.
+    editing: Option<String>,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum SpawnStep {
     Agent,
     Path,
     NewDirectoryName,
     CloneUrl,
+    Task,
 }
 
 #[derive(Debug, Clone)]
@@ -69,6 +136,9 @@ struct SpawnModal {
     browser: Browser,
     new_dir_name: String,
     clone_url: String,
+    /// Directory chosen in the path step, set once the wizard reaches the task step.
+    working_dir: Option<String>,
+    task: String,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -94,6 +164,7 @@ struct UiTheme {
     highlight_bg: Color,
     yellow: Color,
     green: Color,
+    red: Color,
 }
 
 impl UiTheme {
@@ -113,13 +184,63 @@ impl UiTheme {
             highlight_bg: c(tc.highlight, Color::Rgb(191, 111, 74)),
             yellow: c(tc.yellow, Color::Rgb(228, 175, 105)),
             green: c(tc.green, Color::Rgb(169, 195, 140)),
+            red: c(tc.red, Color::Rgb(214, 98, 90)),
         }
     }
 }
 
+/// How long an important status message stays up before the refresh summary
+/// replaces it.
+const STATUS_HOLD: Duration = Duration::from_secs(10);
+
+/// While the control client is connected the periodic refresh only has to
+/// catch what it cannot see (output in sessions other than the focused one),
+/// so it runs this many times less often.
+const CONTROL_POLL_FACTOR: u32 = 4;
+
+/// Coalesce bursts of session/window notifications into one update.
+const EVENT_REFRESH_DELAY: Duration = Duration::from_millis(150);
+
+/// A session counts as producing output for this long after its last
+/// `%output` notification.
+const ACTIVE_WINDOW: Duration = Duration::from_secs(2);
+
+const STATE_BADGE: char = '\u{25cf}';
+
+/// Shown in place of the state dot when an agent finished a turn that
+/// nobody has looked at yet.
+const DONE_BADGE: char = '\u{2713}';
+
+/// How often the focused instance tab recaptures its pane.
+const LIVE_REFRESH: Duration = Duration::from_millis(200);
+
+/// With control mode streaming the focused session's output, its pane is
+/// recaptured for this long after the last output event: events are
+/// throttled, so the tail of a burst may not get one of its own.
+const OUTPUT_TAIL: Duration = Duration::from_secs(1);
+
+/// How often unfocused panes of the grid view are recaptured.
+const GRID_REFRESH: Duration = Duration::from_millis(500);
+
+/// Styled capture of a pane shown on screen.
+struct LiveBuffer {
+    lines: Vec<Line<'static>>,
+    captured_at: Instant,
+    /// Pane and scrollback the capture was taken of.
+    pane_id: String,
+    rows: u16,
+    /// The pane's activity key at the time, when it was read.
+    stamp: Option<tmux::CaptureStamp>,
+}
+
 struct App {
     available_agents: Vec<AgentDefinition>,
     instances: Vec<AgentInstance>,
+    /// Recorded sessions whose tmux session is gone, listed after the live
+    /// ones on the dashboard.
+    restorable: Vec<SessionRecord>,
+    /// Latest listing, shared with the activity monitor.
+    discovery: instances::SharedDiscovery,
     selected_row: usize,
     selected_tab: usize,
     modal: Option<SpawnModal>,
@@ -127,6 +248,8 @@ struct App {
     refresh_interval: Duration,
     should_quit: bool,
     status_line: String,
+    /// Keeps `status_line` from being replaced by the periodic refresh summary.
+    status_hold_until: Option<Instant>,
     theme: UiTheme,
     screen: AppScreen,
     warning: Option<Warning>,
@@ -135,24 +258,123 @@ struct App {
     settings_open: bool,
     settings_selected: usize,
     settings_editing: Option<String>,
+    /// Notification history, oldest first, as stored in the state dir.
+    inbox: Vec<Notification>,
+    /// Escape sequences from notification sinks, written to the terminal
+    /// before the next frame.
+    terminal_output: String,
+    inbox_open: bool,
+    /// Index into the inbox as shown, newest first.
+    inbox_selected: usize,
     split: Option<SplitState>,
+    send_box: Option<SendBox>,
+    notify_box: Option<NotifyBox>,
+    inject_tx: Sender<inject::InjectEvent>,
+    inject_rx: Receiver<inject::InjectEvent>,
+    activity_tx: Sender<monitor::ActivityEvent>,
+    activity_rx: Receiver<monitor::ActivityEvent>,
+    /// Passes settings changes to the activity monitor thread.
+    monitor_tx: Option<Sender<config::AppConfig>>,
+    /// When the activity monitor last saw each session's output change.
+    last_activity: HashMap<SessionKey, Instant>,
+    /// Sessions that finished a turn while off screen.
+    unread: HashSet<SessionKey>,
+    control: Option<ControlClient>,
+    /// Set to refresh ahead of schedule on a fresh listing.
+    refresh_requested: bool,
+    /// When control mode first reported a session or window change the
+    /// instance list has not caught up with.
+    changed_at: Option<Instant>,
+    /// When each session last produced output, as pushed by control mode.
+    last_output: HashMap<SessionKey, Instant>,
+    /// Styled captures of the sessions on screen.
+    live: HashMap<SessionKey, LiveBuffer>,
+    grid: Option<GridView>,
+    /// Session that keystrokes are forwarded to.
+    interact: Option<SessionKey>,
+    /// Helper pane shown in place of the agent's on its instance tab.
+    viewed_pane: Option<ViewedPane>,
+    /// Chord that leaves interact mode.
+    interact_escape: keys::Chord,
+    /// tmux key that comes back to lattice from an agent session.
+    return_key: tmux::HotKey,
+    /// tmux key that opens the agent picker popup.
+    pick_key: tmux::HotKey,
+    /// Servers the hotkeys are bound on.
+    hotkey_servers: Vec<tmux::Server>,
+    /// Keys lattice bound over, restored on exit.
+    saved_keys: Vec<tmux::SavedKey>,
+    /// tmux split still open after switching the client to it.
+    tmux_split: Option<state::SplitRecord>,
 }
 
 impl App {
     fn new(cfg: config::AppConfig) -> Self {
         let tmux_available = tmux::is_tmux_available();
         let refresh_interval = Duration::from_secs(cfg.refresh_interval.max(1));
+        let (inject_tx, inject_rx) = mpsc::channel();
+        let (activity_tx, activity_rx) = mpsc::channel();
+        let interact_escape = keys::Chord::parse(&cfg.interact_escape);
+        let return_key = tmux::HotKey::parse(&cfg.tmux_return_key);
+        let pick_key = tmux::HotKey::parse(&cfg.tmux_pick_key);
+        let mut config_errors = Vec::new();
+        if interact_escape.is_none() {
+            config_errors.push(format!(
+                "invalid interact_escape `{}`, using {}",
+                cfg.interact_escape,
+                keys::Chord::DEFAULT
+            ));
+        }
+        if return_key.is_none() {
+            config_errors.push(format!(
+                "invalid tmux_return_key `{}`, using {}",
+                cfg.tmux_return_key,
+                tmux::HotKey::RETURN_DEFAULT
+            ));
+        }
+        if pick_key.is_none() {
+            config_errors.push(format!(
+                "invalid tmux_pick_key `{}`, using {}",
+                cfg.tmux_pick_key,
+                tmux::HotKey::PICK_DEFAULT
+            ));
+        }
+        if let Some(value) = &cfg.notifications.invalid_quiet_hours {
+            config_errors.push(format!(
+                "ignoring invalid quiet_hours `{value}`, expected e.g. 22:00-07:00"
+            ));
+        }
+        config_errors.extend(
+            cfg.notifications
+                .sinks
+                .iter()
+                .filter_map(|sink| sink.problem())
+                .map(|problem| format!("ignoring {problem}")),
+        );
+        let inbox = Inbox::open().load().unwrap_or_else(|err| {
+            config_errors.push(format!("could not read the inbox: {err:#}"));
+            Vec::new()
+        });
+        config_errors.extend(
+            cfg.custom_agents
+                .iter()
+                .flat_map(|agent| agent.pattern_problems())
+                .map(|problem| format!("ignoring {problem}")),
+        );
 
         Self {
             available_agents: Vec::new(),
             instances: Vec::new(),
+            restorable: Vec::new(),
+            discovery: instances::SharedDiscovery::default(),
             selected_row: 0,
             selected_tab: 0,
             modal: None,
             last_refresh: Instant::now() - refresh_interval,
             refresh_interval,
             should_quit: false,
-            status_line: String::new(),
+            status_hold_until: (!config_errors.is_empty()).then(|| Instant::now() + STATUS_HOLD),
+            status_line: config_errors.join("; "),
             theme: UiTheme::from_config(&cfg.theme),
             screen: AppScreen::Main,
             warning: None,
@@ -161,7 +383,38 @@ impl App {
             settings_open: false,
             settings_selected: 0,
             settings_editing: None,
+            inbox,
+            terminal_output: String::new(),
+            inbox_open: false,
+            inbox_selected: 0,
             split: None,
+            send_box: None,
+            notify_box: None,
+            inject_tx,
+            inject_rx,
+            activity_tx,
+            activity_rx,
+            monitor_tx: None,
+            last_activity: HashMap::new(),
+            unread: HashSet::new(),
+            control: None,
+            refresh_requested: false,
+            changed_at: None,
+            last_output: HashMap::new(),
+            live: HashMap::new(),
+            grid: None,
+            interact: None,
+            viewed_pane: None,
+            interact_escape: interact_escape.unwrap_or_default(),
+            return_key: return_key.unwrap_or_else(|| {
+                tmux::HotKey::parse(tmux::HotKey::RETURN_DEFAULT).expect("default key parses")
+            }),
+            pick_key: pick_key.unwrap_or_else(|| {
+                tmux::HotKey::parse(tmux::HotKey::PICK_DEFAULT).expect("default key parses")
+            }),
+            hotkey_servers: Vec::new(),
+            saved_keys: Vec::new(),
+            tmux_split: None,
         }
     }
 
@@ -203,6 +456,24 @@ impl App {
     }
 
     fn refresh(&mut self) {
+        self.refresh_within(Duration::ZERO);
+    }
+
+    /// The periodic refresh: reuses a listing the activity monitor took
+    /// since the last one, unless a fresh one was asked for.
+    fn scheduled_refresh(&mut self) {
+        let max_age = if self.refresh_requested {
+            Duration::ZERO
+        } else {
+            self.refresh_interval
+        };
+        self.refresh_within(max_age);
+    }
+
+    /// Refresh from a listing at most `max_age` old.
+    fn refresh_within(&mut self, max_age: Duration) {
+        self.refresh_requested = false;
+        self.changed_at = None;
         self.tmux_available = tmux::is_tmux_available();
         self.available_agents = agents::detect_available_agents(&self.config.custom_agents);
         self.check_warnings();
@@ -211,52 +482,405 @@ impl App {
             self.last_refresh = Instant::now();
             return;
         }
-
-        match tmux::list_sessions() {
-            Ok(sessions) => {
-                self.instances = sessions
-                    .into_iter()
-                    .filter_map(|session| {
-                        let agent = agents::classify_agent_from_session(
-                            &session.name,
-                            &session.current_command,
-                            &self.available_agents,
-                        )?;
-                        let managed = agents::managed_session_agent_id(&session.name).is_some();
-                        let title_override = agents::read_title_file(&session.name);
-                        Some(AgentInstance {
-                            agent,
-                            session,
-                            managed,
-                            title_override,
-                        })
-                    })
-                    .collect();
-
-                self.instances
-                    .sort_by(|a, b| a.session.name.cmp(&b.session.name));
-                self.clamp_selection();
-
-                self.status_line = format!(
-                    "{} running  {}  {} agents detected",
-                    self.instances.len(),
-                    "\u{2502}",
-                    self.available_agents.len()
-                );
+        self.check_tmux_split();
+
+        let include_default = self.config.show_default_server;
+        let discovery = match self.discovery.recent(max_age, include_default) {
+            Some(discovery) => Ok(discovery),
+            None => self.discovery.discover(
+                &self.available_agents,
+                &self.visible_sessions(),
+                include_default,
+            ),
+        };
+        match discovery {
+            Ok(discovery) => {
+                self.apply_discovery(discovery);
+                // Other lattice processes add to the inbox too.
+                self.load_inbox();
             }
             Err(err) => {
                 self.instances.clear();
+                self.restorable.clear();
                 self.selected_row = 0;
                 self.selected_tab = 0;
                 self.status_line = format!("refresh failed: {err}");
             }
         }
 
+        self.connect_control();
         self.last_refresh = Instant::now();
     }
 
+    /// Catch up with sessions and panes control mode saw change, without
+    /// the full refresh's agent, registry and inbox reads.
+    fn update_sessions(&mut self) {
+        self.changed_at = None;
+        match self.discovery.update(
+            &self.available_agents,
+            &self.visible_sessions(),
+            self.config.show_default_server,
+        ) {
+            Ok(discovery) => self.apply_discovery(discovery),
+            Err(_) => self.refresh_requested = true,
+        }
+        self.connect_control();
+    }
+
+    /// Sessions whose output is on screen, which are always recaptured.
+    fn visible_sessions(&self) -> Vec<String> {
+        self.active_instance_ref()
+            .map(|i| i.session.name.clone())
+            .into_iter()
+            .collect()
+    }
+
+    /// Take over a new listing, keeping the selection where it was.
+    fn apply_discovery(&mut self, discovery: instances::Discovery) {
+        let tab = self.current_tab_instance().map(AgentInstance::key);
+        let row = self.selected_instance().map(AgentInstance::key);
+        self.instances = discovery.instances;
+        self.restorable = discovery.restorable;
+        self.sort_instances();
+        // Keep the selection on the same sessions when the order
+        // changes underneath it.
+        let position = |key: Option<SessionKey>| {
+            key.and_then(|k| self.instances.iter().position(|i| i.key() == k))
+        };
+        if let Some(index) = position(tab) {
+            self.selected_tab = index + 1;
+        } else if self.selected_tab == 0
+            && let Some(index) = position(row)
+        {
+            self.selected_row = index;
+        }
+        let keys: HashSet<SessionKey> = self.instances.iter().map(AgentInstance::key).collect();
+        self.last_activity.retain(|key, _| keys.contains(key));
+        self.unread.retain(|key| keys.contains(key));
+        self.clamp_selection();
+        self.prune_grid();
+
+        if self
+            .status_hold_until
+            .is_none_or(|until| Instant::now() >= until)
+        {
+            self.status_hold_until = None;
+            self.status_line = format!(
+                "{} running  {}  {} agents detected",
+                self.instances.len(),
+                "\u{2502}",
+                self.available_agents.len()
+            );
+            if !self.restorable.is_empty() {
+                self.status_line
+                    .push_str(&format!("  \u{2502}  {} restorable", self.restorable.len()));
+            }
+        }
+    }
+
+    /// Order instances as configured. Only done on refresh, so tabs don't
+    /// jump around with every burst of output.
+    fn sort_instances(&mut self) {
+        if self.config.instance_sort == config::InstanceSort::Activity {
+            let mut instances = std::mem::take(&mut self.instances);
+            instances.sort_by_cached_key(|i| self.idle_seconds(i));
+            self.instances = instances;
+        }
+    }
+
+    /// Seconds since the instance last produced output, from the freshest
+    /// of control mode, the activity monitor and tmux's window activity.
+    fn idle_seconds(&self, instance: &AgentInstance) -> u64 {
+        let key = instance.key();
+        let seen = [self.last_output.get(&key), self.last_activity.get(&key)]
+            .into_iter()
+            .flatten()
+            .map(|at| at.elapsed().as_secs())
+            .min();
+        seen.unwrap_or_else(|| {
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            now.saturating_sub(instance.session.activity_unix)
+        })
+    }
+
+    fn refresh_due(&self) -> bool {
+        let interval = if self.control.is_some() {
+            self.refresh_interval * CONTROL_POLL_FACTOR
+        } else {
+            self.refresh_interval
+        };
+        let elapsed = self.last_refresh.elapsed();
+        elapsed >= interval || (self.refresh_requested && elapsed >= EVENT_REFRESH_DELAY)
+    }
+
+    fn update_due(&self) -> bool {
+        self.changed_at
+            .is_some_and(|at| at.elapsed() >= EVENT_REFRESH_DELAY)
+    }
+
+    /// Session the control client should be attached to: the focused one,
+    /// or any agent session so server-wide notifications still arrive. The
+    /// client only watches lattice's own server.
+    fn control_target(&self) -> Option<String> {
+        let on_lattice = |i: &&AgentInstance| i.session.server == tmux::Server::Lattice;
+        self.active_instance_ref()
+            .filter(on_lattice)
+            .or_else(|| self.instances.iter().find(on_lattice))
+            .map(|i| i.session.name.clone())
+    }
+
+    fn connect_control(&mut self) {
+        if self.control.is_some() {
+            return;
+        }
+        if let Some(target) = self.control_target() {
+            self.control = ControlClient::connect(&target).ok();
+        }
+    }
+
+    /// Keep the control client on the focused session so its output streams.
+    fn follow_focus(&mut self) {
+        let Some(target) = self.control_target() else {
+            return;
+        };
+        if let Some(control) = self.control.as_mut()
+            && control.session() != target
+            && control.follow(&target).is_err()
+        {
+            self.control = None;
+        }
+    }
+
+    /// Apply control-mode notifications: note which sessions produced
+    /// output and schedule an update when sessions or windows changed.
+    /// Sessions on screen are recaptured by `poll_live`; for the others the
+    /// update recaptures the selected one.
+    fn poll_control(&mut self) {
+        let Some(control) = &self.control else {
+            return;
+        };
+        let mut produced = HashSet::new();
+        for event in control.drain() {
+            match event {
+                ControlEvent::Output { session } => {
+                    produced.insert(session);
+                }
+                ControlEvent::Changed => {
+                    self.changed_at.get_or_insert_with(Instant::now);
+                }
+                ControlEvent::Exited => {
+                    self.control = None;
+                    self.refresh_requested = true;
+                }
+            }
+        }
+
+        let now = Instant::now();
+        let watched = self.watched_sessions();
+        for name in produced {
+            let key = (tmux::Server::Lattice, name);
+            if !watched.iter().any(|(k, _)| k == &key) {
+                self.changed_at.get_or_insert(now);
+            }
+            self.last_output.insert(key, now);
+        }
+    }
+
+    /// Sessions whose panes are on screen: every grid pane, or the focused
+    /// instance tab. The flag marks the one that has focus.
+    fn watched_sessions(&self) -> Vec<(SessionKey, bool)> {
+        if let Some(grid) = &self.grid {
+            return grid
+                .panes
+                .iter()
+                .enumerate()
+                .map(|(i, p)| (p.key(), i == grid.focused))
+                .collect();
+        }
+        self.current_tab_instance()
+            .map(|i| (i.key(), true))
+            .into_iter()
+            .collect()
+    }
+
+    fn instance(&self, key: &SessionKey) -> Option<&AgentInstance> {
+        self.instances
+            .iter()
+            .find(|i| i.session.server == key.0 && i.session.name == key.1)
+    }
+
+    /// Recapture on-screen panes, with colors, when they changed: the
+    /// focused one around its output events while control mode streams it,
+    /// other panes when their activity key moved, checked every
+    /// `LIVE_REFRESH` for the focused one and `GRID_REFRESH` for the rest of
+    /// the grid.
+    fn poll_live(&mut self) {
+        let watched = self.watched_sessions();
+        self.live
+            .retain(|key, _| watched.iter().any(|(k, _)| k == key));
+        if self
+            .interact
+            .as_ref()
+            .is_some_and(|i| !watched.iter().any(|(k, _)| k == i))
+        {
+            self.interact = None;
+            self.hold_status("Interacted session is gone".to_owned());
+        }
+
+        // Enough scrollback to fill the view even when the pane is short.
+        let rows = term::size().map(|(_, h)| h).unwrap_or(50);
+        // Activity keys, read once per server and only when needed.
+        let mut activity: HashMap<tmux::Server, HashMap<String, tmux::ActivityKey>> =
+            HashMap::new();
+        for (session, focused) in watched {
+            let Some(instance) = self.instance(&session) else {
+                continue;
+            };
+            let server = instance.session.server;
+            let pane_id = self.viewed_pane_id(instance).to_owned();
+            let streamed = focused
+                && server == tmux::Server::Lattice
+                && self
+                    .control
+                    .as_ref()
+                    .is_some_and(|c| c.session() == session.1);
+            let due_at = if focused { LIVE_REFRESH } else { GRID_REFRESH };
+            let live = self
+                .live
+                .get(&session)
+                .filter(|live| live.pane_id == pane_id && live.rows == rows);
+            if live.is_some_and(|live| live.captured_at.elapsed() < due_at) {
+                continue;
+            }
+            let key = if streamed {
+                None
+            } else {
+                activity
+                    .entry(server)
+                    .or_insert_with(|| tmux::pane_activity(server).unwrap_or_default())
+                    .get(&pane_id)
+                    .copied()
+            };
+
+            if let Some(live) = live {
+                let changed = if streamed {
+                    self.last_output
+                        .get(&session)
+                        .is_some_and(|at| at.elapsed() < OUTPUT_TAIL)
+                } else {
+                    !key.is_some_and(|key| live.stamp.is_some_and(|s| s.is_current(key)))
+                };
+                if !changed {
+                    continue;
+                }
+            }
+
+            match tmux::capture_styled(server, &pane_id, rows) {
+                Ok(raw) => {
+                    let lines = ansi::parse_lines(&raw);
+                    self.take_preview(&session, &pane_id, &lines);
+                    self.live.insert(
+                        session,
+                        LiveBuffer {
+                            lines: trim_blank_tail(lines),
+                            captured_at: Instant::now(),
+                            pane_id,
+                            rows,
+                            stamp: key.map(tmux::CaptureStamp::new),
+                        },
+                    );
+                }
+                Err(_) => {
+                    self.live.remove(&session);
+                }
+            }
+        }
+    }
+
+    /// Update a session's preview, last line and state from a live capture
+    /// of its agent pane, so output needs no second capture of its own.
+    fn take_preview(&mut self, key: &SessionKey, pane_id: &str, lines: &[Line<'_>]) {
+        let Some(instance) = self
+            .instances
+            .iter_mut()
+            .find(|i| &i.key() == key && i.session.pane_id == pane_id)
+        else {
+            return;
+        };
+        let preview: Vec<String> = lines
+            .iter()
+            .map(|line| ansi::plain_text(line).trim_end().to_owned())
+            .collect();
+        if let Some(last) = tmux::last_non_empty_line(&preview) {
+            instance.session.last_line = last.to_owned();
+        }
+        instance.session.preview = preview;
+        instance.update_state();
+    }
+
+    /// Lines to show for a session: its live capture, or the plain
+    /// dashboard preview until the first one arrives.
+    fn pane_lines(&self, instance: &AgentInstance) -> Vec<Line<'static>> {
+        match self.live.get(&instance.key()) {
+            Some(live) => live.lines.clone(),
+            None => trim_blank_tail(ansi::parse_lines(&instance.session.preview)),
+        }
+    }
+
+    fn is_producing_output(&self, instance: &AgentInstance) -> bool {
+        self.last_output
+            .get(&instance.key())
+            .is_some_and(|at| at.elapsed() < ACTIVE_WINDOW)
+    }
+
+    /// The classifier's state, except that a session streaming output right
+    /// now is working whatever its last capture showed.
+    fn agent_state(&self, instance: &AgentInstance) -> AgentState {
+        match instance.state {
+            AgentState::WaitingForInput | AgentState::Starting
+                if self.is_producing_output(instance) =>
+            {
+                AgentState::Working
+            }
+            state => state,
+        }
+    }
+
+    fn state_style(&self, state: AgentState) -> Style {
+        let t = self.theme;
+        match state {
+            AgentState::Starting | AgentState::Exited => Style::default().fg(t.muted),
+            AgentState::Working => Style::default().fg(t.yellow),
+            AgentState::WaitingForInput => Style::default().fg(t.green),
+            AgentState::AwaitingApproval => {
+                Style::default().fg(t.accent).add_modifier(Modifier::BOLD)
+            }
+            AgentState::RateLimited | AgentState::Errored => Style::default().fg(t.red),
+        }
+    }
+
+    fn state_label(&self, instance: &AgentInstance) -> (&'static str, Style) {
+        let state = self.agent_state(instance);
+        (state.label(), self.state_style(state))
+    }
+
+    /// Colored dot shown before an instance in the list and tab header, or
+    /// a check mark while it has a finished turn nobody has looked at.
+    fn state_badge(&self, instance: &AgentInstance) -> Span<'static> {
+        let state = self.agent_state(instance);
+        let badge = if self.unread.contains(&instance.key()) {
+            DONE_BADGE
+        } else {
+            STATE_BADGE
+        };
+        Span::styled(format!("{badge} "), self.state_style(state))
+    }
+
     fn dashboard_row_count(&self) -> usize {
-        self.instances.len() + 2 // + action row + settings row
+        self.instances.len() + self.restorable.len() + 2 // + action row + settings row
     }
 
     fn clamp_selection(&mut self) {
@@ -274,14 +898,10 @@ impl App {
 
         // Prune split panes whose sessions no longer exist
         if let Some(split) = &mut self.split {
-            let names: std::collections::HashSet<&str> = self
-                .instances
-                .iter()
-                .map(|i| i.session.name.as_str())
-                .collect();
+            let instances = &self.instances;
             split
                 .panes
-                .retain(|p| names.contains(p.session_name.as_str()));
+                .retain(|p| instances.iter().any(|i| i.key() == p.key()));
             if split.panes.is_empty() {
                 self.split = None;
             }
@@ -303,12 +923,25 @@ impl App {
         self.instances.get(self.selected_tab - 1)
     }
 
+    fn selected_restorable(&self) -> Option<&SessionRecord> {
+        if self.selected_tab != 0 {
+            return None;
+        }
+        self.selected_row
+            .checked_sub(self.instances.len())
+            .and_then(|i| self.restorable.get(i))
+    }
+
+    fn action_row_index(&self) -> usize {
+        self.instances.len() + self.restorable.len()
+    }
+
     fn is_action_row_selected(&self) -> bool {
-        self.selected_tab == 0 && self.selected_row == self.instances.len()
+        self.selected_tab == 0 && self.selected_row == self.action_row_index()
     }
 
     fn is_settings_row_selected(&self) -> bool {
-        self.selected_tab == 0 && self.selected_row == self.instances.len() + 1
+        self.selected_tab == 0 && self.selected_row == self.action_row_index() + 1
     }
 
     fn next_row(&mut self) {
@@ -359,7 +992,7 @@ impl App {
             .config
             .default_spawn_dir
             .as_ref()
-            .map(|s| std::path::PathBuf::from(s))
+            .map(std::path::PathBuf::from)
             .unwrap_or_else(|| env::current_dir().unwrap_or_else(|_| "/".into()));
         match Browser::new(start) {
             Ok(browser) => {
@@ -369,6 +1002,8 @@ impl App {
                     browser,
                     new_dir_name: String::new(),
                     clone_url: String::new(),
+                    working_dir: None,
+                    task: String::new(),
                 });
             }
             Err(err) => {
@@ -377,110 +1012,133 @@ impl App {
         }
     }
 
-    fn create_instance(&mut self, agent_index: usize, working_dir: String) {
+    fn create_instance(&mut self, agent_index: usize, working_dir: String, task: Option<String>) {
         let Some(agent) = self.available_agents.get(agent_index).cloned() else {
             self.status_line = "Invalid agent selection".to_owned();
             self.modal = None;
             return;
         };
 
-        let final_dir =
-            if self.config.git_worktrees && git::is_git_repo(std::path::Path::new(&working_dir)) {
-                match git::create_worktree(std::path::Path::new(&working_dir)) {
-                    Ok(wt_path) => wt_path.to_string_lossy().to_string(),
-                    Err(err) => {
-                        self.status_line = format!("Worktree failed: {err}, using original dir");
-                        working_dir.clone()
-                    }
+        match instances::spawn(
+            &self.config,
+            &agent,
+            &working_dir,
+            self.config.git_worktrees,
+            task.as_deref(),
+        ) {
+            Ok(spawned) => {
+                let mut message = match &spawned.worktree_error {
+                    Some(err) => format!(
+                        "Started {} in {} (worktree failed: {err})",
+                        agent.label, spawned.working_dir
+                    ),
+                    None => format!("Started {} in {}", agent.label, spawned.working_dir),
+                };
+                if let Some(err) = &spawned.record_error {
+                    message.push_str(&format!(" (not recorded: {err})"));
                 }
-            } else {
-                working_dir.clone()
-            };
-
-        let session_name = agents::build_managed_session_name(&agent.id);
-        let title_enabled = self.config.title_injection_enabled;
-
-        let launch_cmd = agents::build_launch_command(&agent, title_enabled);
-
-        match tmux::create_session(&session_name, &final_dir, &launch_cmd) {
-            Ok(()) => {
-                // For agents without a system-prompt flag, inject a first
-                // message asking them to write task titles to a temp file.
-                // Delay gives TUI-based agents time to boot.
-                if title_enabled && agents::needs_title_injection(&agent) {
-                    let msg = agents::build_title_injection(&session_name);
-                    let delay = self.config.title_injection_delay;
-                    let _ = tmux::send_keys_delayed(&session_name, &msg, delay);
+                if let Some(injection) = spawned.injection.clone() {
+                    message.push_str(", waiting for it to be ready for input");
+                    inject::deliver_in_background(injection, self.inject_tx.clone());
                 }
-
-                self.status_line = format!("Started {} in {}", agent.label, final_dir);
+                self.hold_status(message);
                 self.modal = None;
                 self.refresh();
 
-                if let Some(pos) = self
-                    .instances
-                    .iter()
-                    .position(|x| x.session.name == session_name)
-                {
+                let key = (tmux::Server::Lattice, spawned.session_name.clone());
+                if let Some(pos) = self.instances.iter().position(|x| x.key() == key) {
                     self.selected_row = pos;
                     self.selected_tab = pos + 1;
                 }
             }
             Err(err) => {
-                self.status_line = format!("Failed to start {}: {err}", agent.label);
+                self.status_line = err.to_string();
                 self.modal = None;
             }
         }
     }
 
+    fn restore_selected(&mut self) {
+        let Some(record) = self.selected_restorable().cloned() else {
+            return;
+        };
+
+        let name = &record.session_name;
+        match instances::restore(&self.config, &self.available_agents, &record) {
+            Ok(restored) => {
+                let mut message = format!(
+                    "Restored {name} ({})",
+                    if restored.resumed {
+                        "resumed"
+                    } else {
+                        "fresh start"
+                    }
+                );
+                if let Some(injection) = restored.injection {
+                    message.push_str(", waiting for it to be ready for input");
+                    inject::deliver_in_background(injection, self.inject_tx.clone());
+                }
+                self.hold_status(message);
+                self.refresh();
+
+                let key = (tmux::Server::Lattice, name.clone());
+                if let Some(pos) = self.instances.iter().position(|x| x.key() == key) {
+                    self.selected_row = pos;
+                }
+            }
+            Err(err) => self.status_line = format!("Failed to restore {name}: {err}"),
+        }
+    }
+
+    fn forget_selected(&mut self) {
+        let Some(record) = self.selected_restorable().cloned() else {
+            return;
+        };
+
+        let name = &record.session_name;
+        match instances::forget(&record) {
+            Ok(Stopped::Plain) => self.status_line = format!("Forgot {name}"),
+            Ok(Stopped::WorktreeCleaned) => {
+                self.status_line = format!("Forgot {name} (worktree cleaned)")
+            }
+            Ok(Stopped::WorktreeCleanupFailed(err)) => {
+                self.status_line = format!("Forgot {name} (worktree cleanup failed: {err})")
+            }
+            Err(err) => {
+                self.status_line = format!("Failed to forget {name}: {err}");
+                return;
+            }
+        }
+        self.refresh();
+    }
+
     fn kill_selected_instance(&mut self) {
         let Some(instance) = self.active_instance_ref().cloned() else {
             self.status_line = "Select an instance row first".to_owned();
             return;
         };
 
-        // Check if the session was running in a worktree before killing it
-        let worktree_path = if self.config.git_worktrees
-            && !instance.session.pane_current_path.is_empty()
-        {
-            let p = std::path::Path::new(&instance.session.pane_current_path);
-            if git::is_worktree_path(p) {
-                Some(p.to_path_buf())
-            } else {
-                None
+        let name = &instance.session.name;
+        match instances::stop(&instance) {
+            Ok(Stopped::Plain) => self.status_line = format!("Stopped {name}"),
+            Ok(Stopped::WorktreeCleaned) => {
+                self.status_line = format!("Stopped {name} (worktree cleaned)")
             }
-        } else {
-            None
-        };
-
-        match tmux::kill_session(&instance.session.name) {
-            Ok(()) => {
-                // Clean up worktree if applicable
-                if let Some(wt) = worktree_path {
-                    match git::remove_worktree(&wt) {
-                        Ok(()) => {
-                            self.status_line =
-                                format!("Stopped {} (worktree cleaned)", instance.session.name);
-                        }
-                        Err(err) => {
-                            self.status_line = format!(
-                                "Stopped {} (worktree cleanup failed: {err})",
-                                instance.session.name
-                            );
-                        }
-                    }
-                } else {
-                    self.status_line = format!("Stopped {}", instance.session.name);
-                }
-                self.refresh();
+            Ok(Stopped::WorktreeCleanupFailed(err)) => {
+                self.status_line = format!("Stopped {name} (worktree cleanup failed: {err})")
             }
             Err(err) => {
-                self.status_line = format!("Failed to stop {}: {err}", instance.session.name);
+                self.status_line = format!("Failed to stop {name}: {err}");
+                return;
             }
         }
+        self.refresh();
     }
 
     fn active_instance_ref(&self) -> Option<&AgentInstance> {
+        if let Some(grid) = &self.grid {
+            return self.instance(&grid.panes.get(grid.focused)?.key());
+        }
         if self.selected_tab == 0 {
             self.selected_instance()
         } else {
@@ -488,6 +1146,158 @@ impl App {
         }
     }
 
+    /// Start forwarding keys to the focused instance, switching to its tab
+    /// when it was picked from the dashboard.
+    fn enter_interact(&mut self) {
+        let Some(instance) = self.active_instance_ref() else {
+            self.status_line = "Select an instance first".to_owned();
+            return;
+        };
+        let key = instance.key();
+        if self.grid.is_none()
+            && let Some(idx) = self.instances.iter().position(|i| i.key() == key)
+        {
+            self.selected_tab = idx + 1;
+            self.selected_row = idx;
+        }
+        self.hold_status(format!(
+            "Keys go to {} \u{2502} {} to return",
+            key.1, self.interact_escape
+        ));
+        self.interact = Some(key);
+    }
+
+    fn leave_interact(&mut self) {
+        if self.interact.take().is_some() {
+            self.hold_status("Back to navigation".to_owned());
+        }
+    }
+
+    fn interact_instance(&self) -> Option<&AgentInstance> {
+        self.instance(self.interact.as_ref()?)
+    }
+
+    /// Replay a keypress in the interacted pane.
+    fn forward_key(&mut self, key: &KeyEvent) {
+        let Some(instance) = self.interact_instance() else {
+            return;
+        };
+        let Some(tmux_key) = keys::to_tmux(key) else {
+            return;
+        };
+        let (text, literal) = match &tmux_key {
+            keys::TmuxKey::Literal(text) => (text.as_str(), true),
+            keys::TmuxKey::Named(name) => (name.as_str(), false),
+        };
+        let server = instance.session.server;
+        let pane_id = self.viewed_pane_id(instance).to_owned();
+        if let Err(err) = tmux::send_key(server, &pane_id, text, literal) {
+            self.interact = None;
+            self.hold_status(format!("Interact stopped: {err}"));
+        }
+    }
+
+    /// Paste into the interacted pane without submitting.
+    fn forward_paste(&mut self, text: &str) {
+        let Some(instance) = self.interact_instance() else {
+            return;
+        };
+        let server = instance.session.server;
+        let pane_id = self.viewed_pane_id(instance).to_owned();
+        if let Err(err) = tmux::paste_text(server, &pane_id, text) {
+            self.hold_status(format!("Paste failed: {err}"));
+        }
+    }
+
+    /// The helper pane picked on `instance`'s tab, while that tab is the
+    /// one shown and the pane still exists.
+    fn viewed_helper<'a>(&self, instance: &'a AgentInstance) -> Option<&'a tmux::Pane> {
+        let shown = self.grid.is_none()
+            && self
+                .current_tab_instance()
+                .is_some_and(|i| i.key() == instance.key());
+        let viewed = self
+            .viewed_pane
+            .as_ref()
+            .filter(|v| shown && v.session_name == instance.session.name)?;
+        instance.helper_panes().find(|p| p.id == viewed.pane_id)
+    }
+
+    /// Pane shown for `instance`: the picked helper pane, or the agent's.
+    fn viewed_pane_id<'a>(&self, instance: &'a AgentInstance) -> &'a str {
+        match self.viewed_helper(instance) {
+            Some(pane) => &pane.id,
+            None => &instance.session.pane_id,
+        }
+    }
+
+    /// Step through the current tab's panes, starting from the agent's.
+    fn cycle_pane(&mut self, forward: bool) {
+        let Some(instance) = self.current_tab_instance() else {
+            return;
+        };
+        let panes = &instance.session.panes;
+        if panes.len() < 2 {
+            self.status_line = "The session has no other panes (t opens one)".to_owned();
+            return;
+        }
+        let current = self.viewed_pane_id(instance);
+        let at = panes.iter().position(|p| p.id == current).unwrap_or(0);
+        let next = if forward {
+            (at + 1) % panes.len()
+        } else {
+            (at + panes.len() - 1) % panes.len()
+        };
+        let pane = &panes[next];
+        let key = instance.key();
+        let status = format!("Pane {} \u{00b7} {}", pane.label(), pane.current_command);
+        self.viewed_pane = (pane.id != instance.session.pane_id).then(|| ViewedPane {
+            session_name: key.1.clone(),
+            pane_id: pane.id.clone(),
+        });
+        self.status_line = status;
+        self.live.remove(&key);
+    }
+
+    /// Make the viewed pane the active one in tmux.
+    fn focus_viewed_pane(&mut self) {
+        let Some(instance) = self.current_tab_instance() else {
+            return;
+        };
+        let server = instance.session.server;
+        let pane_id = self.viewed_pane_id(instance).to_owned();
+        // The agent's pane is about to stop being the active one.
+        instance.mark_agent_pane();
+        match tmux::select_pane(server, &pane_id) {
+            Ok(()) => self.status_line = format!("Focused pane {pane_id} in tmux"),
+            Err(err) => self.status_line = format!("Failed to focus pane: {err}"),
+        }
+    }
+
+    fn current_viewed_helper(&self) -> Option<&tmux::Pane> {
+        self.viewed_helper(self.current_tab_instance()?)
+    }
+
+    /// Close the helper pane picked on the current tab.
+    fn kill_viewed_pane(&mut self) {
+        let Some(instance) = self.current_tab_instance() else {
+            return;
+        };
+        let Some(pane) = self.viewed_helper(instance) else {
+            return;
+        };
+        let label = pane.label();
+        match tmux::kill_pane(instance.session.server, &pane.id) {
+            Ok(()) => self.status_line = format!("Closed pane {label}"),
+            Err(err) => {
+                self.status_line = format!("Failed to close pane {label}: {err}");
+                return;
+            }
+        }
+        self.viewed_pane = None;
+        self.refresh();
+    }
+
     fn is_split_mode(&self) -> bool {
         self.split.is_some()
     }
@@ -503,22 +1313,24 @@ impl App {
             return;
         };
         let name = instance.session.name.clone();
+        let server = instance.session.server;
         self.split = Some(SplitState {
             panes: vec![SplitPane {
                 session_name: name,
+                server,
             }],
         });
         self.status_line =
-            "Split: navigate tabs and press v to add panes, enter to launch".to_owned();
+            "Split: navigate tabs and press v to add panes, enter to show grid".to_owned();
     }
 
     fn add_split_pane(&mut self) {
         let Some(split) = &self.split else { return };
-        let shown: std::collections::HashSet<&str> = split
-            .panes
-            .iter()
-            .map(|p| p.session_name.as_str())
-            .collect();
+        if split.panes.len() >= grid::MAX_PANES {
+            self.status_line = format!("Split: at most {} panes", grid::MAX_PANES);
+            return;
+        }
+        let shown: HashSet<SessionKey> = split.panes.iter().map(SplitPane::key).collect();
 
         // Prefer the currently viewed instance (tab or selected row)
         let candidate = if self.selected_tab > 0 {
@@ -527,22 +1339,20 @@ impl App {
             self.selected_instance()
         };
         let next = candidate
-            .filter(|i| !shown.contains(i.session.name.as_str()))
-            .or_else(|| {
-                self.instances
-                    .iter()
-                    .find(|i| !shown.contains(i.session.name.as_str()))
-            });
+            .filter(|i| !shown.contains(&i.key()))
+            .or_else(|| self.instances.iter().find(|i| !shown.contains(&i.key())));
 
         if let Some(inst) = next {
             let name = inst.session.name.clone();
+            let server = inst.session.server;
             let count = split.panes.len() + 1;
             if let Some(split) = &mut self.split {
                 split.panes.push(SplitPane {
                     session_name: name,
+                    server,
                 });
             }
-            self.status_line = format!("Split: {count} panes selected, enter to launch");
+            self.status_line = format!("Split: {count} panes selected, enter to show grid");
         } else {
             self.status_line = "No more instances to add".to_owned();
         }
@@ -560,35 +1370,379 @@ impl App {
         self.status_line = format!("Split: {count} panes selected");
     }
 
+    /// Turn the split selection into the grid view.
+    fn open_grid(&mut self) {
+        let Some(split) = self.split.take() else {
+            return;
+        };
+        if split.panes.len() < 2 {
+            self.status_line = "Add at least 2 panes (press v on another tab)".to_owned();
+            self.split = Some(split);
+            return;
+        }
+        let layout = grid::GridLayout::default();
+        self.status_line = format!("Grid: {} panes ({})", split.panes.len(), layout.name());
+        self.grid = Some(GridView {
+            panes: split.panes,
+            layout,
+            focused: 0,
+        });
+    }
+
+    fn close_grid(&mut self) {
+        if self.grid.take().is_some() {
+            self.interact = None;
+            self.status_line = "Closed grid".to_owned();
+        }
+    }
+
+    fn move_grid_focus(&mut self, dir: grid::Direction) {
+        if let Some(grid) = &mut self.grid {
+            grid.focused = grid::step(grid.panes.len(), grid.layout, grid.focused, dir);
+        }
+    }
+
+    /// Drop the focused pane from the grid (its session keeps running).
+    fn remove_grid_pane(&mut self) {
+        let Some(grid) = &mut self.grid else { return };
+        grid.panes.remove(grid.focused);
+        grid.focused = grid.focused.min(grid.panes.len().saturating_sub(1));
+        if grid.panes.is_empty() {
+            self.close_grid();
+        }
+    }
+
+    /// Forget grid panes whose session has gone away.
+    fn prune_grid(&mut self) {
+        let Some(grid) = &mut self.grid else { return };
+        let instances = &self.instances;
+        grid.panes
+            .retain(|p| instances.iter().any(|i| i.key() == p.key()));
+        grid.focused = grid.focused.min(grid.panes.len().saturating_sub(1));
+        if grid.panes.is_empty() {
+            self.close_grid();
+        }
+    }
+
+    /// Hand back panes still lent to tmux splits whose lattice exited
+    /// without closing them.
+    fn restore_orphaned_splits(&mut self) {
+        let store = state::SplitStore::open();
+        for record in store.load_all() {
+            if !record.is_orphaned() {
+                continue;
+            }
+            match tmux::restore_split(&record.split) {
+                Ok(()) => {
+                    let _ = store.remove(&record.split.name);
+                    self.hold_status(format!(
+                        "Returned panes left in {} to their sessions",
+                        record.split.name
+                    ));
+                }
+                Err(err) => self.hold_status(format!("{}: {err}", record.split.name)),
+            }
+        }
+    }
+
+    /// Put back the panes of a tmux split the client was switched to, once
+    /// no client is looking at it any more.
+    fn check_tmux_split(&mut self) {
+        let Some(record) = &self.tmux_split else {
+            return;
+        };
+        if tmux::has_clients(record.split.server, &record.split.name) {
+            return;
+        }
+        if let Some(record) = self.tmux_split.take() {
+            self.close_tmux_split(&record);
+        }
+    }
+
+    fn close_tmux_split(&mut self, record: &state::SplitRecord) {
+        match tmux::restore_split(&record.split) {
+            Ok(()) => {
+                let _ = state::SplitStore::open().remove(&record.split.name);
+                self.status_line = "Exited split view".to_owned();
+            }
+            Err(err) => self.hold_status(format!("Split closed, but {err}")),
+        }
+    }
+
+    /// Bind the return and picker keys on `server`, once per run.
+    fn bind_hotkeys(&mut self, server: tmux::Server) {
+        if self.hotkey_servers.contains(&server) {
+            return;
+        }
+        let exe = std::env::current_exe()
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_else(|_| "lattice".to_owned());
+        let pick = format!("{} pick", agents::shell_quote(&exe));
+        match tmux::bind_hotkeys(server, &self.return_key, &self.pick_key, &pick) {
+            Ok(saved) => {
+                self.hotkey_servers.push(server);
+                self.saved_keys.extend(saved);
+            }
+            Err(err) => self.hold_status(format!("Could not bind tmux keys: {err}")),
+        }
+    }
+
+    /// Undo what lattice changed in tmux while sending clients around.
+    fn shutdown(&mut self) {
+        if let Some(record) = self.tmux_split.take() {
+            self.close_tmux_split(&record);
+        }
+        let home = tmux::current_session();
+        for server in self.hotkey_servers.drain(..) {
+            tmux::clear_marks(server, home.as_deref());
+        }
+        for saved in self.saved_keys.drain(..) {
+            tmux::restore_hotkey(&saved);
+        }
+        // The monitor's alert hooks would keep running `lattice hook` and
+        // beeping after we are gone; another lattice still running puts
+        // them back on its next tick.
+        for instance in &self.instances {
+            let session = &instance.session;
+            if instance.managed && session.server == tmux::Server::Lattice {
+                let _ = tmux::unwatch_session(session.server, &session.name, &session.pane_id);
+            }
+        }
+    }
+
+    /// Show a status message that survives the next few refreshes.
+    fn hold_status(&mut self, message: String) {
+        self.status_line = message;
+        self.status_hold_until = Some(Instant::now() + STATUS_HOLD);
+    }
+
+    /// Report finished background deliveries in the status line.
+    fn poll_injections(&mut self) {
+        while let Ok(event) = self.inject_rx.try_recv() {
+            let message = match event {
+                inject::InjectEvent::Delivered {
+                    session_name,
+                    waited,
+                } => format!(
+                    "Delivered input to {session_name} after {:.1}s",
+                    waited.as_secs_f64()
+                ),
+                inject::InjectEvent::Failed {
+                    session_name,
+                    error,
+                } => format!("! Input not delivered to {session_name}: {error}"),
+            };
+            self.hold_status(message);
+        }
+    }
+
+    /// Take in what the activity monitor saw. A notified session stays
+    /// unread until it is shown or produces output again.
+    fn poll_activity(&mut self) {
+        let mut notified = false;
+        while let Ok(event) = self.activity_rx.try_recv() {
+            match event {
+                // The monitor only watches lattice's own server.
+                monitor::ActivityEvent::Changed { session, at } => {
+                    self.unread.remove(&session);
+                    self.last_activity.insert(session, at);
+                }
+                monitor::ActivityEvent::Notified {
+                    notification,
+                    terminal,
+                } => {
+                    self.unread
+                        .insert((notification.server, notification.session_name));
+                    self.terminal_output.push_str(&terminal);
+                    notified = true;
+                }
+            }
+        }
+        if notified {
+            self.load_inbox();
+        }
+        for (key, _) in self.watched_sessions() {
+            self.unread.remove(&key);
+        }
+    }
+
+    fn load_inbox(&mut self) {
+        match Inbox::open().load() {
+            Ok(inbox) => self.inbox = inbox,
+            Err(err) => self.hold_status(format!("Could not read the inbox: {err:#}")),
+        }
+        if self.inbox_selected >= self.inbox.len() {
+            self.inbox_selected = self.inbox.len().saturating_sub(1);
+        }
+    }
+
+    fn open_inbox(&mut self) {
+        self.load_inbox();
+        self.inbox_open = true;
+        self.inbox_selected = 0;
+    }
+
+    /// Notifications newest first, as the inbox lists them.
+    fn inbox_entries(&self) -> impl Iterator<Item = &Notification> {
+        self.inbox.iter().rev()
+    }
+
+    fn selected_notification(&self) -> Option<&Notification> {
+        self.inbox_entries().nth(self.inbox_selected)
+    }
+
+    fn unread_notifications(&self) -> usize {
+        self.inbox.iter().filter(|n| !n.read).count()
+    }
+
+    fn mark_notification_read(&mut self) {
+        let Some(notification) = self.selected_notification().cloned() else {
+            return;
+        };
+        if let Err(err) = Inbox::open().mark_read(&notification) {
+            self.status_line = format!("Inbox update failed: {err}");
+        }
+        self.load_inbox();
+    }
+
+    /// Open the tab of the selected notification's session.
+    fn jump_to_notification(&mut self) {
+        let Some(key) = self
+            .selected_notification()
+            .map(|n| (n.server, n.session_name.clone()))
+        else {
+            return;
+        };
+        self.mark_notification_read();
+        match self.instances.iter().position(|i| i.key() == key) {
+            Some(index) => {
+                self.inbox_open = false;
+                self.selected_tab = index + 1;
+                self.selected_row = index;
+            }
+            None => self.status_line = format!("{} is no longer running", key.1),
+        }
+    }
+
+    fn clear_inbox(&mut self) {
+        match Inbox::open().clear() {
+            Ok(()) => self.status_line = "Inbox cleared".to_owned(),
+            Err(err) => self.status_line = format!("Inbox update failed: {err}"),
+        }
+        self.load_inbox();
+    }
+
+    fn open_send_box(&mut self) {
+        let Some(instance) = self.active_instance_ref() else {
+            self.status_line = "Select an instance first".to_owned();
+            return;
+        };
+        self.send_box = Some(SendBox {
+            session_name: instance.session.name.clone(),
+            server: instance.session.server,
+            pane_id: instance.session.pane_id.clone(),
+            title: instance.display_title(),
+            text: String::new(),
+        });
+    }
+
+    fn open_notify_box(&mut self) {
+        let Some(instance) = self.active_instance_ref() else {
+            self.status_line = "Select an instance first".to_owned();
+            return;
+        };
+        self.notify_box = Some(NotifyBox {
+            session_name: instance.session.name.clone(),
+            server: instance.session.server,
+            title: instance.display_title(),
+            rule: monitor::NotifyRule::parse(&instance.session.notify_rule),
+            selected: 0,
+            editing: None,
+        });
+    }
+
+    /// Store the rule being edited on its session, where the monitor picks
+    /// it up.
+    fn save_notify_rule(&mut self) {
+        let Some(edit) = self.notify_box.as_ref() else {
+            return;
+        };
+        let value = edit.rule.value();
+        self.status_line = match tmux::set_notify_rule(edit.server, &edit.session_name, &value) {
+            Ok(()) => format!(
+                "Notifications for {}: {}",
+                edit.session_name,
+                notify_rule_label(edit.rule, &self.config)
+            ),
+            Err(err) => format!("Failed to save notification rule: {err}"),
+        };
+        if let Some(instance) = self
+            .instances
+            .iter_mut()
+            .find(|i| i.key() == (edit.server, edit.session_name.clone()))
+        {
+            instance.session.notify_rule = value;
+        }
+    }
+
+    fn submit_send_box(&mut self) {
+        let Some(send) = self.send_box.take() else {
+            return;
+        };
+        if send.text.trim().is_empty() {
+            self.status_line = "Nothing to send".to_owned();
+            return;
+        }
+        match tmux::send_text(send.server, &send.pane_id, &send.text) {
+            Ok(()) => self.status_line = format!("Sent to {}", send.session_name),
+            Err(err) => self.status_line = format!("Send failed for {}: {err}", send.session_name),
+        }
+    }
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
     let mut cfg = config::load_config();
     config::apply_cli_overrides(&mut cfg, cli.refresh_seconds);
-    run(cfg)
+    tmux::set_socket(cfg.tmux_socket.clone());
+    match cli.command {
+        Some(command) => cli::run(command, &cfg),
+        None => run(cfg),
+    }
 }
 
 fn run(cfg: config::AppConfig) -> Result<()> {
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(
+        stdout,
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        EnableBracketedPaste
+    )?;
 
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
     terminal.hide_cursor()?;
 
     let mut app = App::new(cfg.clone());
-    config::spawn_activity_monitor(&cfg);
+    app.monitor_tx = Some(monitor::spawn_activity_monitor(
+        &cfg,
+        app.activity_tx.clone(),
+        app.discovery.clone(),
+    ));
+    app.restore_orphaned_splits();
     app.refresh();
 
     let loop_result = run_loop(&mut terminal, &mut app);
+    app.shutdown();
 
     disable_raw_mode()?;
     execute!(
         terminal.backend_mut(),
         LeaveAlternateScreen,
-        DisableMouseCapture
+        DisableMouseCapture,
+        DisableBracketedPaste
     )?;
     terminal.show_cursor()?;
 
@@ -597,33 +1751,75 @@ fn run(cfg: config::AppConfig) -> Result<()> {
 
 fn run_loop(terminal: &mut Terminal<CrosstermBackend<Stdout>>, app: &mut App) -> Result<()> {
     while !app.should_quit {
+        if !app.terminal_output.is_empty() {
+            let output = std::mem::take(&mut app.terminal_output);
+            let backend = terminal.backend_mut();
+            backend.write_all(output.as_bytes())?;
+            backend.flush()?;
+        }
         terminal.draw(|frame| draw_ui(frame, app))?;
 
+        let tick = if app.grid.is_some() || app.current_tab_instance().is_some() {
+            LIVE_REFRESH
+        } else {
+            Duration::from_millis(250)
+        };
         let until_refresh = app
             .refresh_interval
             .saturating_sub(app.last_refresh.elapsed())
-            .min(Duration::from_millis(250));
+            .min(tick);
 
         if event::poll(until_refresh)? {
             match event::read()? {
                 Event::Key(key) if key.kind == KeyEventKind::Press => {
                     if app.screen == AppScreen::Warning {
                         handle_warning_key(app, key.code);
+                    } else if app.interact.is_some() {
+                        if app.interact_escape.matches(&key) {
+                            app.leave_interact();
+                        } else {
+                            app.forward_key(&key);
+                        }
                     } else if app.modal.is_some() {
-                        handle_modal_key(app, key.code);
+                        handle_modal_key(app, key.code, key.modifiers);
+                    } else if app.send_box.is_some() {
+                        handle_send_key(app, key.code, key.modifiers);
+                    } else if app.notify_box.is_some() {
+                        handle_notify_key(app, key.code);
                     } else if app.settings_open {
                         handle_settings_key(app, key.code);
+                    } else if app.inbox_open {
+                        handle_inbox_key(app, key.code);
                     } else {
                         handle_main_key(terminal, app, key.code, key.modifiers)?;
                     }
                 }
+                Event::Paste(text) if app.interact.is_some() => app.forward_paste(&text),
+                Event::Paste(text) => {
+                    let text = text.replace("\r\n", "\n").replace('\r', "\n");
+                    if let Some(send) = app.send_box.as_mut() {
+                        send.text.push_str(&text);
+                    } else if let Some(modal) = app.modal.as_mut()
+                        && modal.step == SpawnStep::Task
+                    {
+                        modal.task.push_str(&text);
+                    }
+                }
                 Event::Resize(_, _) => {}
                 _ => {}
             }
         }
 
-        if app.last_refresh.elapsed() >= app.refresh_interval {
-            app.refresh();
+        app.poll_injections();
+        app.poll_activity();
+        app.poll_control();
+        app.follow_focus();
+        app.poll_live();
+
+        if app.refresh_due() {
+            app.scheduled_refresh();
+        } else if app.update_due() {
+            app.update_sessions();
         }
     }
 
@@ -638,13 +1834,87 @@ fn handle_warning_key(app: &mut App, code: KeyCode) {
     }
 }
 
-fn handle_modal_key(app: &mut App, code: KeyCode) {
+fn handle_notify_key(app: &mut App, code: KeyCode) {
+    let Some(edit) = app.notify_box.as_mut() else {
+        return;
+    };
+    if let Some(buf) = edit.editing.as_mut() {
+        match code {
+            KeyCode::Esc => edit.editing = None,
+            KeyCode::Enter => {
+                // Empty (or zero) goes back to the global settle time.
+                edit.rule.settle_seconds = buf.parse().ok().filter(|s| *s > 0);
+                edit.editing = None;
+                app.save_notify_rule();
+            }
+            KeyCode::Backspace => {
+                buf.pop();
+            }
+            KeyCode::Char(c) if c.is_ascii_digit() => buf.push(c),
+            _ => {}
+        }
+        return;
+    }
+    match code {
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('z') => app.notify_box = None,
+        KeyCode::Char('j') | KeyCode::Down | KeyCode::Char('k') | KeyCode::Up => {
+            edit.selected = 1 - edit.selected;
+        }
+        KeyCode::Enter if edit.selected == 0 => {
+            edit.rule.mode = edit.rule.mode.next();
+            app.save_notify_rule();
+        }
+        KeyCode::Enter => {
+            edit.editing = Some(
+                edit.rule
+                    .settle_seconds
+                    .map(|s| s.to_string())
+                    .unwrap_or_default(),
+            );
+        }
+        _ => {}
+    }
+}
+
+/// A session's rule in words, e.g. `approval only, settle 20s`.
+fn notify_rule_label(rule: monitor::NotifyRule, config: &config::AppConfig) -> String {
+    match rule.settle_seconds {
+        Some(secs) => format!("{}, settle {secs}s", rule.mode.label()),
+        None => format!(
+            "{}, settle {}s (default)",
+            rule.mode.label(),
+            config.notifications.settle_seconds
+        ),
+    }
+}
+
+fn handle_send_key(app: &mut App, code: KeyCode, modifiers: KeyModifiers) {
+    let Some(send) = app.send_box.as_mut() else {
+        return;
+    };
+    match code {
+        KeyCode::Esc => {
+            app.send_box = None;
+            app.status_line = "Send cancelled".to_owned();
+        }
+        KeyCode::Enter if modifiers.contains(KeyModifiers::ALT) => send.text.push('\n'),
+        KeyCode::Enter => app.submit_send_box(),
+        KeyCode::Backspace => {
+            send.text.pop();
+        }
+        KeyCode::Char(c) if !c.is_control() => send.text.push(c),
+        _ => {}
+    }
+}
+
+fn handle_modal_key(app: &mut App, code: KeyCode, modifiers: KeyModifiers) {
     enum Action {
         None,
         Close,
         CreateInstance {
             agent_index: usize,
             working_dir: String,
+            task: Option<String>,
         },
         CreateDirectory {
             name: String,
@@ -661,19 +1931,14 @@ fn handle_modal_key(app: &mut App, code: KeyCode) {
         match modal.step {
             SpawnStep::Agent => match code {
                 KeyCode::Esc => action = Action::Close,
-                KeyCode::Char('j') | KeyCode::Down => {
-                    if !app.available_agents.is_empty() {
-                        modal.selected_agent =
-                            (modal.selected_agent + 1) % app.available_agents.len();
-                    }
+                KeyCode::Char('j') | KeyCode::Down if !app.available_agents.is_empty() => {
+                    modal.selected_agent = (modal.selected_agent + 1) % app.available_agents.len();
                 }
-                KeyCode::Char('k') | KeyCode::Up => {
-                    if !app.available_agents.is_empty() {
-                        if modal.selected_agent == 0 {
-                            modal.selected_agent = app.available_agents.len() - 1;
-                        } else {
-                            modal.selected_agent -= 1;
-                        }
+                KeyCode::Char('k') | KeyCode::Up if !app.available_agents.is_empty() => {
+                    if modal.selected_agent == 0 {
+                        modal.selected_agent = app.available_agents.len() - 1;
+                    } else {
+                        modal.selected_agent -= 1;
                     }
                 }
                 KeyCode::Enter => modal.step = SpawnStep::Path,
@@ -696,10 +1961,8 @@ fn handle_modal_key(app: &mut App, code: KeyCode) {
                 }
                 KeyCode::Enter => match modal.browser.activate_selected() {
                     Ok(ActivateResult::Selected(path)) => {
-                        action = Action::CreateInstance {
-                            agent_index: modal.selected_agent,
-                            working_dir: path.to_string_lossy().to_string(),
-                        }
+                        modal.working_dir = Some(path.to_string_lossy().to_string());
+                        modal.step = SpawnStep::Task;
                     }
                     Ok(ActivateResult::ChangedDirectory) => {}
                     Ok(ActivateResult::StartCreateDirectory) => {
@@ -729,10 +1992,8 @@ fn handle_modal_key(app: &mut App, code: KeyCode) {
                 KeyCode::Backspace => {
                     modal.new_dir_name.pop();
                 }
-                KeyCode::Char(c) => {
-                    if !c.is_control() {
-                        modal.new_dir_name.push(c);
-                    }
+                KeyCode::Char(c) if !c.is_control() => {
+                    modal.new_dir_name.push(c);
                 }
                 _ => {}
             },
@@ -749,11 +2010,28 @@ fn handle_modal_key(app: &mut App, code: KeyCode) {
                 KeyCode::Backspace => {
                     modal.clone_url.pop();
                 }
-                KeyCode::Char(c) => {
-                    if !c.is_control() {
-                        modal.clone_url.push(c);
+                KeyCode::Char(c) if !c.is_control() => {
+                    modal.clone_url.push(c);
+                }
+                _ => {}
+            },
+            SpawnStep::Task => match code {
+                KeyCode::Esc => modal.step = SpawnStep::Path,
+                KeyCode::Enter if modifiers.contains(KeyModifiers::ALT) => modal.task.push('\n'),
+                KeyCode::Enter => {
+                    if let Some(working_dir) = modal.working_dir.clone() {
+                        let task = modal.task.trim();
+                        action = Action::CreateInstance {
+                            agent_index: modal.selected_agent,
+                            working_dir,
+                            task: (!task.is_empty()).then(|| task.to_owned()),
+                        }
                     }
                 }
+                KeyCode::Backspace => {
+                    modal.task.pop();
+                }
+                KeyCode::Char(c) if !c.is_control() => modal.task.push(c),
                 _ => {}
             },
         }
@@ -769,7 +2047,8 @@ fn handle_modal_key(app: &mut App, code: KeyCode) {
         Action::CreateInstance {
             agent_index,
             working_dir,
-        } => app.create_instance(agent_index, working_dir),
+            task,
+        } => app.create_instance(agent_index, working_dir, task),
         Action::CreateDirectory { name } => {
             if let Some(modal) = app.modal.as_mut() {
                 match modal.browser.create_directory(&name) {
@@ -826,96 +2105,54 @@ fn handle_main_key(
             // Tab navigation to browse instances while selecting
             KeyCode::Char('h') | KeyCode::Left => app.previous_tab(),
             KeyCode::Char('l') | KeyCode::Right | KeyCode::Tab => app.next_tab(),
-            KeyCode::Char('j') | KeyCode::Down => {
-                if app.selected_tab == 0 {
-                    app.next_row();
-                }
-            }
-            KeyCode::Char('k') | KeyCode::Up => {
-                if app.selected_tab == 0 {
-                    app.previous_row();
-                }
-            }
+            KeyCode::Char('j') | KeyCode::Down if app.selected_tab == 0 => app.next_row(),
+            KeyCode::Char('k') | KeyCode::Up if app.selected_tab == 0 => app.previous_row(),
             KeyCode::Char('r') => app.refresh(),
-            KeyCode::Enter => {
-                // Launch native tmux split and attach
-                if let Some(split) = app.split.take() {
-                    let targets: Vec<String> =
-                        split.panes.iter().map(|p| p.session_name.clone()).collect();
-                    if targets.len() < 2 {
-                        app.status_line =
-                            "Add at least 2 panes (press v on another tab)".to_owned();
-                        app.split = Some(split);
-                    } else {
-                        let ts = std::time::SystemTime::now()
-                            .duration_since(std::time::UNIX_EPOCH)
-                            .unwrap_or_default()
-                            .as_secs();
-                        let split_name = format!("lattice_split_{ts}");
-                        match tmux::create_split_session(&split_name, &targets) {
-                            Ok(()) => {
-                                let attach_result =
-                                    attach_into_session(terminal, &split_name);
-                                // Clean up temp session on return
-                                let _ = tmux::kill_session(&split_name);
-                                match attach_result {
-                                    Ok(()) => {
-                                        app.status_line = "Exited split view".to_owned()
-                                    }
-                                    Err(err) => {
-                                        app.status_line =
-                                            format!("Split attach failed: {err}")
-                                    }
-                                }
-                                app.refresh();
-                            }
-                            Err(err) => {
-                                app.status_line =
-                                    format!("Failed to create split: {err}");
-                            }
-                        }
-                    }
-                }
-            }
+            KeyCode::Enter => app.open_grid(),
+            KeyCode::Char('t') => open_tmux_split(terminal, app)?,
             _ => {}
         }
         return Ok(());
     }
 
+    if app.grid.is_some() {
+        return handle_grid_key(terminal, app, code);
+    }
+
     match code {
         KeyCode::Char('q') | KeyCode::Esc => app.should_quit = true,
-        KeyCode::Char('j') | KeyCode::Down => {
-            if app.selected_tab == 0 {
-                app.next_row();
-            }
-        }
-        KeyCode::Char('k') | KeyCode::Up => {
-            if app.selected_tab == 0 {
-                app.previous_row();
-            }
-        }
+        KeyCode::Char('j') | KeyCode::Down if app.selected_tab == 0 => app.next_row(),
+        KeyCode::Char('k') | KeyCode::Up if app.selected_tab == 0 => app.previous_row(),
         KeyCode::Char('h') | KeyCode::Left => app.previous_tab(),
         KeyCode::Char('l') | KeyCode::Right | KeyCode::Tab => app.next_tab(),
         KeyCode::Char('s') | KeyCode::Char('d') => app.selected_tab = 0,
         KeyCode::Char('n') => app.open_spawn_modal(),
+        KeyCode::Char('a') => app.open_inbox(),
         KeyCode::Char('v') => app.enter_split_mode(),
         KeyCode::Char('t') => {
             if let Some(instance) = app.active_instance_ref() {
                 let name = instance.session.name.clone();
+                let server = instance.session.server;
                 let dir = if instance.session.pane_current_path.is_empty() {
                     ".".to_owned()
                 } else {
                     instance.session.pane_current_path.clone()
                 };
-                match tmux::split_window(&name, &dir) {
+                match tmux::split_window(server, &name, &dir) {
                     Ok(()) => app.status_line = format!("Opened terminal in {name}"),
-                    Err(err) => {
-                        app.status_line = format!("Failed to split terminal: {err}")
-                    }
+                    Err(err) => app.status_line = format!("Failed to split terminal: {err}"),
                 }
             }
         }
+        KeyCode::Char('x') if app.selected_restorable().is_some() => app.forget_selected(),
+        KeyCode::Char('x') if app.current_viewed_helper().is_some() => app.kill_viewed_pane(),
         KeyCode::Char('x') => app.kill_selected_instance(),
+        KeyCode::Char(']') if app.selected_tab > 0 => app.cycle_pane(true),
+        KeyCode::Char('[') if app.selected_tab > 0 => app.cycle_pane(false),
+        KeyCode::Char('f') if app.selected_tab > 0 => app.focus_viewed_pane(),
+        KeyCode::Char('m') => app.open_send_box(),
+        KeyCode::Char('z') => app.open_notify_box(),
+        KeyCode::Char('i') => app.enter_interact(),
         KeyCode::Char('r') => app.refresh(),
         KeyCode::Char(c @ '1'..='9') => {
             let idx = (c as usize) - ('0' as usize);
@@ -931,16 +2168,17 @@ fn handle_main_key(
                 app.settings_editing = None;
             } else if app.selected_tab == 0 && app.is_action_row_selected() {
                 app.open_spawn_modal();
+            } else if app.selected_restorable().is_some() {
+                app.restore_selected();
             } else if let Some(instance) = app.active_instance_ref() {
                 let name = instance.session.name.clone();
-                let attach_result = attach_into_session(terminal, &name);
-                match attach_result {
-                    Ok(()) => app.status_line = format!("Detached from {}", name),
-                    Err(err) => {
-                        app.status_line = format!("Attach failed for {}: {err}", name)
-                    }
+                let server = instance.session.server;
+                instance.mark_agent_pane();
+                // Attach straight to a picked helper pane.
+                if let Some(pane) = app.viewed_helper(instance) {
+                    let _ = tmux::select_pane(server, &pane.id);
                 }
-                app.refresh();
+                enter_session(terminal, app, server, &name);
             }
         }
         _ => {}
@@ -949,7 +2187,7 @@ fn handle_main_key(
     Ok(())
 }
 
-const SETTINGS_COUNT: usize = 8;
+const SETTINGS_COUNT: usize = 16;
 
 fn setting_label(index: usize) -> &'static str {
     match index {
@@ -957,10 +2195,18 @@ fn setting_label(index: usize) -> &'static str {
         1 => "Default spawn dir",
         2 => "Title injection",
         3 => "Title injection delay",
-        4 => "Git worktrees",
-        5 => "Sound on completion",
-        6 => "Sound method",
-        7 => "Sound command",
+        4 => "Ready timeout",
+        5 => "Git worktrees",
+        6 => "Sound on completion",
+        7 => "Sound method",
+        8 => "Sound command",
+        9 => "Show default server",
+        10 => "Tmux split layout",
+        11 => "Sort instances",
+        12 => "Settle time",
+        13 => "Do not disturb",
+        14 => "Quiet hours",
+        15 => "Min notify interval",
         _ => "",
     }
 }
@@ -969,25 +2215,67 @@ fn setting_value(config: &config::AppConfig, index: usize) -> String {
     match index {
         0 => format!("{}", config.refresh_interval),
         1 => config.default_spawn_dir.clone().unwrap_or_default(),
-        2 => if config.title_injection_enabled { "on".to_owned() } else { "off".to_owned() },
+        2 => {
+            if config.title_injection_enabled {
+                "on".to_owned()
+            } else {
+                "off".to_owned()
+            }
+        }
         3 => format!("{}", config.title_injection_delay),
-        4 => if config.git_worktrees { "on".to_owned() } else { "off".to_owned() },
-        5 => if config.notifications.sound_on_completion { "on".to_owned() } else { "off".to_owned() },
-        6 => match config.notifications.sound_method {
+        4 => format!("{}", config.ready_timeout),
+        5 => {
+            if config.git_worktrees {
+                "on".to_owned()
+            } else {
+                "off".to_owned()
+            }
+        }
+        6 => {
+            if config.notifications.sound_on_completion {
+                "on".to_owned()
+            } else {
+                "off".to_owned()
+            }
+        }
+        7 => match config.notifications.sound_method {
             config::SoundMethod::Bell => "bell".to_owned(),
             config::SoundMethod::Command => "command".to_owned(),
         },
-        7 => config.notifications.sound_command.clone(),
+        8 => config.notifications.sound_command.clone(),
+        9 => {
+            if config.show_default_server {
+                "on".to_owned()
+            } else {
+                "off".to_owned()
+            }
+        }
+        10 => config.tmux_split_layout.name().to_owned(),
+        11 => config.instance_sort.name().to_owned(),
+        12 => format!("{}", config.notifications.settle_seconds),
+        13 => {
+            if config.notifications.do_not_disturb {
+                "on".to_owned()
+            } else {
+                "off".to_owned()
+            }
+        }
+        14 => config
+            .notifications
+            .quiet_hours
+            .map(config::QuietHours::name)
+            .unwrap_or_default(),
+        15 => format!("{}", config.notifications.min_interval),
         _ => String::new(),
     }
 }
 
 fn setting_is_bool(index: usize) -> bool {
-    matches!(index, 2 | 4 | 5)
+    matches!(index, 2 | 5 | 6 | 9 | 13)
 }
 
 fn setting_is_cycle(index: usize) -> bool {
-    index == 6
+    matches!(index, 7 | 10 | 11)
 }
 
 fn apply_setting(app: &mut App, index: usize, value: &str) {
@@ -1015,22 +2303,63 @@ fn apply_setting(app: &mut App, index: usize, value: &str) {
             }
         }
         4 => {
-            app.config.git_worktrees = !app.config.git_worktrees;
+            if let Ok(v) = value.parse::<u32>() {
+                app.config.ready_timeout = v.max(1);
+            }
         }
         5 => {
-            app.config.notifications.sound_on_completion = !app.config.notifications.sound_on_completion;
+            app.config.git_worktrees = !app.config.git_worktrees;
         }
         6 => {
+            app.config.notifications.sound_on_completion =
+                !app.config.notifications.sound_on_completion;
+        }
+        7 => {
             app.config.notifications.sound_method = match app.config.notifications.sound_method {
                 config::SoundMethod::Bell => config::SoundMethod::Command,
                 config::SoundMethod::Command => config::SoundMethod::Bell,
             };
         }
-        7 => {
+        8 => {
             app.config.notifications.sound_command = value.to_owned();
         }
+        9 => {
+            app.config.show_default_server = !app.config.show_default_server;
+        }
+        10 => {
+            app.config.tmux_split_layout = app.config.tmux_split_layout.next();
+        }
+        11 => {
+            app.config.instance_sort = app.config.instance_sort.next();
+            app.refresh_requested = true;
+        }
+        12 => {
+            if let Ok(v) = value.parse::<u64>() {
+                app.config.notifications.settle_seconds = v.max(1);
+            }
+        }
+        13 => {
+            app.config.notifications.do_not_disturb = !app.config.notifications.do_not_disturb;
+        }
+        14 => {
+            if value.trim().is_empty() {
+                app.config.notifications.quiet_hours = None;
+                app.config.notifications.invalid_quiet_hours = None;
+            } else if let Some(hours) = config::QuietHours::parse(value) {
+                app.config.notifications.quiet_hours = Some(hours);
+                app.config.notifications.invalid_quiet_hours = None;
+            }
+        }
+        15 => {
+            if let Ok(v) = value.parse::<u64>() {
+                app.config.notifications.min_interval = v;
+            }
+        }
         _ => {}
     }
+    if let Some(tx) = &app.monitor_tx {
+        let _ = tx.send(app.config.clone());
+    }
 }
 
 fn handle_settings_key(app: &mut App, code: KeyCode) {
@@ -1077,13 +2406,7 @@ fn handle_settings_key(app: &mut App, code: KeyCode) {
         }
         KeyCode::Enter => {
             let idx = app.settings_selected;
-            if setting_is_bool(idx) {
-                apply_setting(app, idx, "");
-                match config::save_config(&app.config) {
-                    Ok(()) => app.status_line = "Settings saved".to_owned(),
-                    Err(e) => app.status_line = format!("Save failed: {e}"),
-                }
-            } else if setting_is_cycle(idx) {
+            if setting_is_bool(idx) || setting_is_cycle(idx) {
                 apply_setting(app, idx, "");
                 match config::save_config(&app.config) {
                     Ok(()) => app.status_line = "Settings saved".to_owned(),
@@ -1097,6 +2420,127 @@ fn handle_settings_key(app: &mut App, code: KeyCode) {
     }
 }
 
+fn handle_inbox_key(app: &mut App, code: KeyCode) {
+    let count = app.inbox.len();
+    match code {
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('a') => {
+            app.inbox_open = false;
+        }
+        KeyCode::Char('j') | KeyCode::Down if count > 0 => {
+            app.inbox_selected = (app.inbox_selected + 1) % count;
+        }
+        KeyCode::Char('k') | KeyCode::Up if count > 0 => {
+            app.inbox_selected = app.inbox_selected.checked_sub(1).unwrap_or(count - 1);
+        }
+        KeyCode::Enter => app.jump_to_notification(),
+        KeyCode::Char(' ') => app.mark_notification_read(),
+        KeyCode::Char('c') => app.clear_inbox(),
+        _ => {}
+    }
+}
+
+fn draw_inbox_view(frame: &mut ratatui::Frame<'_>, area: Rect, app: &App) {
+    let t = app.theme;
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled(
+                "inbox",
+                Style::default().fg(t.text).add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(
+                format!("  {} unread", app.unread_notifications()),
+                Style::default().fg(t.muted),
+            ),
+        ]),
+        Line::from(""),
+    ];
+
+    if app.inbox.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "Nothing yet. Agents that finish, need approval, error out or exit show up here.",
+            Style::default().fg(t.muted),
+        )));
+    }
+
+    // Keep the selected entry and its screen lines in view.
+    let room = (area.height as usize)
+        .saturating_sub(lines.len() + 3 + monitor::NOTIFICATION_LINES)
+        .max(1);
+    let first = (app.inbox_selected + 1).saturating_sub(room);
+    for (i, notification) in app.inbox_entries().enumerate().skip(first).take(room) {
+        let selected = i == app.inbox_selected;
+        let kind_style = app.state_style(match notification.kind {
+            NotificationKind::Finished => AgentState::WaitingForInput,
+            NotificationKind::AwaitingApproval => AgentState::AwaitingApproval,
+            NotificationKind::Exited => AgentState::Exited,
+            NotificationKind::Errored => AgentState::Errored,
+        });
+        let text_style = if selected {
+            Style::default()
+                .fg(t.bg)
+                .bg(t.highlight_bg)
+                .add_modifier(Modifier::BOLD)
+        } else if notification.read {
+            Style::default().fg(t.muted)
+        } else {
+            Style::default().fg(t.text)
+        };
+        lines.push(Line::from(vec![
+            Span::styled(
+                if notification.read { "  " } else { "\u{25cf} " },
+                Style::default().fg(t.accent),
+            ),
+            Span::styled(
+                format!("{:<12}", format_ago(now.saturating_sub(notification.at))),
+                Style::default().fg(t.muted),
+            ),
+            Span::styled(format!("{:<16}", notification.kind.label()), kind_style),
+            Span::styled(
+                format!("{} {}", notification.agent_id, notification.title),
+                text_style,
+            ),
+            Span::styled(
+                format!("  {}", notification.session_name),
+                Style::default().fg(t.muted),
+            ),
+        ]));
+        if selected {
+            for line in &notification.lines {
+                lines.push(Line::from(Span::styled(
+                    format!("    {line}"),
+                    Style::default().fg(t.muted),
+                )));
+            }
+        }
+    }
+
+    lines.push(Line::from(""));
+    let key_style = Style::default().fg(t.text).add_modifier(Modifier::BOLD);
+    let desc_style = Style::default().fg(t.muted);
+    lines.push(Line::from(vec![
+        Span::styled("\u{2191}/\u{2193}", key_style),
+        Span::styled(" navigate   ", desc_style),
+        Span::styled("enter", key_style),
+        Span::styled(" jump to session   ", desc_style),
+        Span::styled("space", key_style),
+        Span::styled(" mark read   ", desc_style),
+        Span::styled("c", key_style),
+        Span::styled(" clear all   ", desc_style),
+        Span::styled("esc", key_style),
+        Span::styled(" back", desc_style),
+    ]));
+
+    frame.render_widget(
+        Paragraph::new(Text::from(lines)).style(Style::default().fg(t.text).bg(t.bg)),
+        area,
+    );
+}
+
 fn draw_settings_view(frame: &mut ratatui::Frame<'_>, area: Rect, app: &App) {
     let t = app.theme;
 
@@ -1136,8 +2580,9 @@ fn draw_settings_view(frame: &mut ratatui::Frame<'_>, area: Rect, app: &App) {
         } else if setting_is_bool(i) {
             let on = match i {
                 2 => app.config.title_injection_enabled,
-                4 => app.config.git_worktrees,
-                5 => app.config.notifications.sound_on_completion,
+                5 => app.config.git_worktrees,
+                6 => app.config.notifications.sound_on_completion,
+                13 => app.config.notifications.do_not_disturb,
                 _ => false,
             };
             if on {
@@ -1158,7 +2603,7 @@ fn draw_settings_view(frame: &mut ratatui::Frame<'_>, area: Rect, app: &App) {
 
     lines.push(Line::from(""));
     lines.push(Line::from(Span::styled(
-        "Custom [[agents]] entries are not editable here — edit config.toml directly.",
+        "Custom [[agents]] and [[notifications.sinks]] entries are not editable here — edit config.toml directly.",
         Style::default().fg(t.muted),
     )));
 
@@ -1193,24 +2638,199 @@ fn draw_settings_view(frame: &mut ratatui::Frame<'_>, area: Rect, app: &App) {
     );
 }
 
+fn handle_grid_key(
+    terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+    app: &mut App,
+    code: KeyCode,
+) -> Result<()> {
+    match code {
+        KeyCode::Char('q') => app.should_quit = true,
+        KeyCode::Esc => app.close_grid(),
+        KeyCode::Char('h') | KeyCode::Left => app.move_grid_focus(grid::Direction::Left),
+        KeyCode::Char('l') | KeyCode::Right => app.move_grid_focus(grid::Direction::Right),
+        KeyCode::Char('k') | KeyCode::Up => app.move_grid_focus(grid::Direction::Up),
+        KeyCode::Char('j') | KeyCode::Down => app.move_grid_focus(grid::Direction::Down),
+        KeyCode::Tab => {
+            if let Some(grid) = &mut app.grid {
+                grid.focused = (grid.focused + 1) % grid.panes.len();
+            }
+        }
+        KeyCode::Char(' ') => {
+            if let Some(grid) = &mut app.grid {
+                grid.layout = grid.layout.next();
+                let message = format!("Grid layout: {}", grid.layout.name());
+                app.status_line = message;
+            }
+        }
+        KeyCode::Char('c') => app.remove_grid_pane(),
+        KeyCode::Char('i') => app.enter_interact(),
+        KeyCode::Char('m') => app.open_send_box(),
+        KeyCode::Char('r') => app.refresh(),
+        KeyCode::Enter => {
+            if let Some(instance) = app.active_instance_ref() {
+                let name = instance.session.name.clone();
+                let server = instance.session.server;
+                enter_session(terminal, app, server, &name);
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Assemble the split selection's agent panes into a temporary tmux
+/// session, attach to it, and hand the panes back to their sessions once
+/// the user detaches.
+fn open_tmux_split(terminal: &mut Terminal<CrosstermBackend<Stdout>>, app: &mut App) -> Result<()> {
+    let Some(split) = app.split.take() else {
+        return Ok(());
+    };
+    if split.panes.len() < 2 {
+        app.status_line = "Add at least 2 panes (press v on another tab)".to_owned();
+        app.split = Some(split);
+        return Ok(());
+    }
+    let server = split.panes[0].server;
+    if split.panes.iter().any(|p| p.server != server) {
+        app.status_line =
+            "A tmux split needs all panes on one tmux server; press enter for the grid".to_owned();
+        app.split = Some(split);
+        return Ok(());
+    }
+    let targets: Vec<(String, String)> = split
+        .panes
+        .iter()
+        .filter_map(|p| {
+            app.instances
+                .iter()
+                .find(|i| i.session.name == p.session_name && i.session.server == p.server)
+                .map(|i| (i.session.name.clone(), i.session.pane_id.clone()))
+        })
+        .collect();
+    // Picked sessions may have exited since.
+    if targets.len() < 2 {
+        app.status_line = "Fewer than 2 of the picked sessions are still running".to_owned();
+        app.split = Some(split);
+        return Ok(());
+    }
+
+    let ts = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let split_name = format!("lattice_split_{ts}");
+    let layout = app.config.tmux_split_layout;
+    let tmux_split = match tmux::create_split_session(server, &split_name, &targets, layout) {
+        Ok(tmux_split) => tmux_split,
+        Err(err) => {
+            app.status_line = format!("Failed to create split: {err}");
+            return Ok(());
+        }
+    };
+
+    // Record the split before moving anything, so the panes can be put back
+    // even if lattice dies while it is open.
+    let store = state::SplitStore::open();
+    let record = state::SplitRecord::new(tmux_split);
+    let _ = store.save(&record);
+    let tmux_split = &record.split;
+
+    let opened = tmux::lend_panes(tmux_split)
+        .and_then(|()| open_session(terminal, app, server, &split_name));
+    match opened {
+        // The panes go back once the client leaves the split; see
+        // `check_tmux_split`.
+        Ok(Opened::Switched) => {
+            app.hold_status(format!(
+                "Switched to the split · {} returns here",
+                app.return_key
+            ));
+            app.tmux_split = Some(record);
+        }
+        Ok(Opened::Detached) => app.close_tmux_split(&record),
+        Err(err) => {
+            app.close_tmux_split(&record);
+            app.hold_status(format!("Split attach failed: {err}"));
+        }
+    }
+    app.refresh();
+    Ok(())
+}
+
+/// How a session was shown to the user.
+enum Opened {
+    /// Attached a new client, which has detached again by now.
+    Detached,
+    /// lattice runs in a pane of the same tmux server, so its own client was
+    /// switched over and lattice keeps running behind it.
+    Switched,
+}
+
+/// Show session `name`: switch lattice's own tmux client to it when lattice
+/// runs inside that server (attaching would nest tmux), attach otherwise.
+fn open_session(
+    terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+    app: &mut App,
+    server: tmux::Server,
+    name: &str,
+) -> Result<Opened> {
+    app.bind_hotkeys(server);
+    tmux::set_attention(server, name, false);
+    if tmux::is_current_server(server)
+        && let Some(home) = tmux::current_session()
+    {
+        tmux::mark_session(server, name, tmux::Mark::Return(&home))?;
+        tmux::switch_client(server, name)?;
+        return Ok(Opened::Switched);
+    }
+    // Best effort: without the mark only the hotkeys stop working.
+    let _ = tmux::mark_session(server, name, tmux::Mark::Detach);
+    let attached = attach_into_session(terminal, server, name);
+    tmux::clear_marks(server, None);
+    attached?;
+    Ok(Opened::Detached)
+}
+
+/// `open_session` for an agent, reporting the outcome in the status line.
+fn enter_session(
+    terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+    app: &mut App,
+    server: tmux::Server,
+    name: &str,
+) {
+    match open_session(terminal, app, server, name) {
+        Ok(Opened::Detached) => app.status_line = format!("Detached from {name}"),
+        Ok(Opened::Switched) => app.hold_status(format!(
+            "Switched to {name} · {} returns here, {} picks another agent",
+            app.return_key, app.pick_key
+        )),
+        Err(err) => app.hold_status(format!("Attach failed for {name}: {err}")),
+    }
+    app.unread.remove(&(server, name.to_owned()));
+    app.refresh();
+}
+
 fn attach_into_session(
     terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+    server: tmux::Server,
     name: &str,
 ) -> Result<()> {
     disable_raw_mode()?;
     execute!(
         terminal.backend_mut(),
         LeaveAlternateScreen,
-        DisableMouseCapture
+        DisableMouseCapture,
+        DisableBracketedPaste
     )?;
     terminal.show_cursor()?;
 
-    let attach_result = tmux::attach_session(name);
+    let attach_result = tmux::attach_session(server, name);
 
     execute!(
         terminal.backend_mut(),
         EnterAlternateScreen,
-        EnableMouseCapture
+        EnableMouseCapture,
+        EnableBracketedPaste
     )?;
     enable_raw_mode()?;
     terminal.hide_cursor()?;
@@ -1276,9 +2896,15 @@ fn draw_warning_screen(frame: &mut ratatui::Frame<'_>, app: &App) {
     lines.push(Line::from(""));
     lines.push(Line::from(vec![
         Span::styled("  press ", Style::default().fg(t.muted)),
-        Span::styled("r", Style::default().fg(t.text).add_modifier(Modifier::BOLD)),
+        Span::styled(
+            "r",
+            Style::default().fg(t.text).add_modifier(Modifier::BOLD),
+        ),
         Span::styled(" to retry    ", Style::default().fg(t.muted)),
-        Span::styled("q", Style::default().fg(t.text).add_modifier(Modifier::BOLD)),
+        Span::styled(
+            "q",
+            Style::default().fg(t.text).add_modifier(Modifier::BOLD),
+        ),
         Span::styled(" to quit", Style::default().fg(t.muted)),
     ]));
 
@@ -1286,12 +2912,10 @@ fn draw_warning_screen(frame: &mut ratatui::Frame<'_>, app: &App) {
         .borders(Borders::ALL)
         .border_style(Style::default().fg(t.yellow))
         .style(Style::default().bg(t.bg))
-        .title(Line::from(vec![
-            Span::styled(
-                " lattice ",
-                Style::default().fg(t.yellow).add_modifier(Modifier::BOLD),
-            ),
-        ]));
+        .title(Line::from(vec![Span::styled(
+            " lattice ",
+            Style::default().fg(t.yellow).add_modifier(Modifier::BOLD),
+        )]));
 
     frame.render_widget(
         Paragraph::new(Text::from(lines))
@@ -1309,7 +2933,7 @@ fn draw_main_screen(frame: &mut ratatui::Frame<'_>, app: &App) {
         .constraints([
             Constraint::Length(3), // header tabs
             Constraint::Length(1), // spacer
-            Constraint::Min(6),   // content
+            Constraint::Min(6),    // content
             Constraint::Length(1), // spacer
             Constraint::Length(1), // status message
             Constraint::Length(1), // horizontal rule
@@ -1321,6 +2945,10 @@ fn draw_main_screen(frame: &mut ratatui::Frame<'_>, app: &App) {
 
     if app.settings_open {
         draw_settings_view(frame, sections[2], app);
+    } else if app.inbox_open {
+        draw_inbox_view(frame, sections[2], app);
+    } else if app.grid.is_some() {
+        draw_grid(frame, sections[2], app);
     } else if app.selected_tab == 0 {
         draw_dashboard(frame, sections[2], app);
     } else {
@@ -1334,6 +2962,14 @@ fn draw_main_screen(frame: &mut ratatui::Frame<'_>, app: &App) {
     if app.modal.is_some() {
         draw_spawn_modal(frame, app);
     }
+
+    if app.send_box.is_some() {
+        draw_send_box(frame, app);
+    }
+
+    if app.notify_box.is_some() {
+        draw_notify_box(frame, app);
+    }
 }
 
 /// Renders the header as a connected bordered table row:
@@ -1349,25 +2985,30 @@ fn draw_header(frame: &mut ratatui::Frame<'_>, area: Rect, app: &App) {
     // Cell 2+ = instance tabs
     struct TabCell {
         label: String,
+        badge: Option<Span<'static>>,
         is_selected: bool,
         is_in_split: bool,
     }
 
-    // Collect split pane session names for header highlighting
-    let split_names: Vec<String> = app
+    // Collect split/grid pane session names for header highlighting
+    let split_keys: Vec<SessionKey> = app
         .split
         .as_ref()
-        .map(|s| s.panes.iter().map(|p| p.session_name.clone()).collect())
+        .map(|s| &s.panes)
+        .or(app.grid.as_ref().map(|g| &g.panes))
+        .map(|panes| panes.iter().map(SplitPane::key).collect())
         .unwrap_or_default();
 
     let mut cells: Vec<TabCell> = Vec::new();
     cells.push(TabCell {
         label: "lattice".to_owned(),
+        badge: None,
         is_selected: app.selected_tab == 0,
         is_in_split: false,
     });
     cells.push(TabCell {
         label: "sessions".to_owned(),
+        badge: None,
         is_selected: app.selected_tab == 0,
         is_in_split: false,
     });
@@ -1379,9 +3020,10 @@ fn draw_header(frame: &mut ratatui::Frame<'_>, area: Rect, app: &App) {
             &instance.title_override,
         );
         let display = truncate(&title, 14);
-        let in_split = split_names.contains(&instance.session.name);
+        let in_split = split_keys.contains(&instance.key());
         cells.push(TabCell {
             label: format!("{} {}", instance.agent.id, display),
+            badge: Some(app.state_badge(instance)),
             is_selected: app.selected_tab == i + 1,
             is_in_split: in_split,
         });
@@ -1397,8 +3039,8 @@ fn draw_header(frame: &mut ratatui::Frame<'_>, area: Rect, app: &App) {
     let base = available / n;
     let extra = available % n;
     let mut col_widths: Vec<usize> = vec![base; n];
-    for i in 0..extra {
-        col_widths[i] += 1;
+    for width in col_widths.iter_mut().take(extra) {
+        *width += 1;
     }
 
     let border_style = Style::default().fg(t.border);
@@ -1419,7 +3061,12 @@ fn draw_header(frame: &mut ratatui::Frame<'_>, area: Rect, app: &App) {
     for (i, cell) in cells.iter().enumerate() {
         mid_spans.push(Span::styled("\u{2502}", border_style));
 
-        let cw = col_widths[i];
+        let badge_width = if cell.badge.is_some() && col_widths[i] > 4 {
+            2
+        } else {
+            0
+        };
+        let cw = col_widths[i] - badge_width;
         let display_label = if cell.label.len() > cw {
             truncate(&cell.label, cw)
         } else {
@@ -1441,6 +3088,11 @@ fn draw_header(frame: &mut ratatui::Frame<'_>, area: Rect, app: &App) {
         };
 
         mid_spans.push(Span::styled(" ".repeat(pad_left), Style::default()));
+        if badge_width > 0
+            && let Some(badge) = &cell.badge
+        {
+            mid_spans.push(badge.clone());
+        }
         mid_spans.push(Span::styled(display_label, text_style));
         mid_spans.push(Span::styled(" ".repeat(pad_right), Style::default()));
     }
@@ -1463,10 +3115,7 @@ fn draw_header(frame: &mut ratatui::Frame<'_>, area: Rect, app: &App) {
         Line::from(bot_spans),
     ]);
 
-    frame.render_widget(
-        Paragraph::new(text).style(Style::default().bg(t.bg)),
-        area,
-    );
+    frame.render_widget(Paragraph::new(text).style(Style::default().bg(t.bg)), area);
 }
 
 fn draw_dashboard(frame: &mut ratatui::Frame<'_>, area: Rect, app: &App) {
@@ -1514,6 +3163,7 @@ fn draw_instance_list(frame: &mut ratatui::Frame<'_>, area: Rect, app: &App) {
     }
 
     let mut shown_external_header = false;
+    let restorable_end = app.action_row_index();
 
     for index in start..end {
         let selected = index == app.selected_row;
@@ -1536,7 +3186,7 @@ fn draw_instance_list(frame: &mut ratatui::Frame<'_>, area: Rect, app: &App) {
                 &instance.session.pane_current_path,
                 &instance.title_override,
             );
-            let label = truncate(&title, 28);
+            let label = truncate(&title, 26);
 
             let style = if selected {
                 Style::default()
@@ -1547,8 +3197,39 @@ fn draw_instance_list(frame: &mut ratatui::Frame<'_>, area: Rect, app: &App) {
                 Style::default().fg(t.text)
             };
 
+            lines.push(Line::from(vec![
+                app.state_badge(instance),
+                Span::styled(label, style),
+            ]));
+        } else if index < restorable_end {
+            let record = &app.restorable[index - app.instances.len()];
+            if index == app.instances.len() || index == start {
+                if !lines.is_empty() {
+                    lines.push(Line::from(""));
+                }
+                lines.push(Line::from(Span::styled(
+                    "~ restorable ~",
+                    Style::default().fg(t.accent),
+                )));
+            }
+
+            let label = truncate(
+                &format!(
+                    "\u{21bb} {}",
+                    agents::short_instance_name(&record.session_name)
+                ),
+                28,
+            );
+            let style = if selected {
+                Style::default()
+                    .fg(t.bg)
+                    .bg(t.highlight_bg)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(t.muted)
+            };
             lines.push(Line::from(Span::styled(label, style)));
-        } else if index == app.instances.len() {
+        } else if index == restorable_end {
             // "New Instance" action row
             if !lines.is_empty() {
                 lines.push(Line::from(""));
@@ -1621,19 +3302,29 @@ fn draw_summary_panel(frame: &mut ratatui::Frame<'_>, area: Rect, app: &App) {
             )),
             Line::from(vec![
                 Span::styled("refresh interval       ", Style::default().fg(t.muted)),
-                Span::styled(format!("{}s", c.refresh_interval), Style::default().fg(t.text)),
+                Span::styled(
+                    format!("{}s", c.refresh_interval),
+                    Style::default().fg(t.text),
+                ),
             ]),
             Line::from(vec![
                 Span::styled("default spawn dir      ", Style::default().fg(t.muted)),
                 Span::styled(
-                    c.default_spawn_dir.as_deref().unwrap_or("(none)").to_owned(),
+                    c.default_spawn_dir
+                        .as_deref()
+                        .unwrap_or("(none)")
+                        .to_owned(),
                     Style::default().fg(t.text),
                 ),
             ]),
             Line::from(vec![
                 Span::styled("title injection        ", Style::default().fg(t.muted)),
                 Span::styled(
-                    if c.title_injection_enabled { "on" } else { "off" },
+                    if c.title_injection_enabled {
+                        "on"
+                    } else {
+                        "off"
+                    },
                     if c.title_injection_enabled {
                         Style::default().fg(t.green)
                     } else {
@@ -1643,7 +3334,14 @@ fn draw_summary_panel(frame: &mut ratatui::Frame<'_>, area: Rect, app: &App) {
             ]),
             Line::from(vec![
                 Span::styled("title injection delay  ", Style::default().fg(t.muted)),
-                Span::styled(format!("{}s", c.title_injection_delay), Style::default().fg(t.text)),
+                Span::styled(
+                    format!("{}s", c.title_injection_delay),
+                    Style::default().fg(t.text),
+                ),
+            ]),
+            Line::from(vec![
+                Span::styled("ready timeout          ", Style::default().fg(t.muted)),
+                Span::styled(format!("{}s", c.ready_timeout), Style::default().fg(t.text)),
             ]),
             Line::from(vec![
                 Span::styled("git worktrees          ", Style::default().fg(t.muted)),
@@ -1659,7 +3357,11 @@ fn draw_summary_panel(frame: &mut ratatui::Frame<'_>, area: Rect, app: &App) {
             Line::from(vec![
                 Span::styled("sound on completion    ", Style::default().fg(t.muted)),
                 Span::styled(
-                    if c.notifications.sound_on_completion { "on" } else { "off" },
+                    if c.notifications.sound_on_completion {
+                        "on"
+                    } else {
+                        "off"
+                    },
                     if c.notifications.sound_on_completion {
                         Style::default().fg(t.green)
                     } else {
@@ -1679,9 +3381,57 @@ fn draw_summary_panel(frame: &mut ratatui::Frame<'_>, area: Rect, app: &App) {
             ]),
             Line::from(vec![
                 Span::styled("sound command          ", Style::default().fg(t.muted)),
-                Span::styled(c.notifications.sound_command.clone(), Style::default().fg(t.text)),
+                Span::styled(
+                    c.notifications.sound_command.clone(),
+                    Style::default().fg(t.text),
+                ),
             ]),
         ]
+    } else if let Some(record) = app.selected_restorable() {
+        let field = |name: &'static str, value: String| {
+            Line::from(vec![
+                Span::styled(name, Style::default().fg(t.muted)),
+                Span::styled(value, Style::default().fg(t.text)),
+            ])
+        };
+        let resumable = app
+            .available_agents
+            .iter()
+            .find(|a| a.id == record.agent_id)
+            .map(|a| a.resume_args.is_some());
+
+        let mut l = vec![
+            Line::from(Span::styled(
+                "restorable session",
+                Style::default().fg(t.text).add_modifier(Modifier::BOLD),
+            )),
+            Line::from(""),
+            Line::from(Span::styled(
+                match resumable {
+                    Some(true) => "Press enter to resume it, x to forget it.",
+                    Some(false) => "Press enter to restart it on its task, x to forget it.",
+                    None => "Its agent is not installed; x to forget it.",
+                },
+                Style::default().fg(t.text),
+            )),
+            Line::from(""),
+            field("session  ", record.session_name.clone()),
+            field("agent    ", record.agent_id.clone()),
+            field("path     ", record.working_dir.clone()),
+        ];
+        if let Some(wt) = &record.worktree {
+            l.push(field(
+                "worktree ",
+                format!("{} (from {})", wt.branch, record.original_dir),
+            ));
+        }
+        if let Some(prompt) = &record.initial_prompt {
+            l.push(field(
+                "task     ",
+                prompt.lines().next().unwrap_or_default().to_owned(),
+            ));
+        }
+        l
     } else if app.is_action_row_selected() || app.instances.is_empty() {
         let mut l = vec![
             Line::from(Span::styled(
@@ -1712,23 +3462,16 @@ fn draw_summary_panel(frame: &mut ratatui::Frame<'_>, area: Rect, app: &App) {
             for agent in &app.available_agents {
                 l.push(Line::from(vec![
                     Span::styled(
-                        format!("{}", agent.id),
+                        agent.id.clone(),
                         Style::default().fg(t.text).add_modifier(Modifier::BOLD),
                     ),
-                    Span::styled(
-                        format!("  {}", agent.label),
-                        Style::default().fg(t.muted),
-                    ),
+                    Span::styled(format!("  {}", agent.label), Style::default().fg(t.muted)),
                 ]));
             }
         }
         l
     } else if let Some(instance) = app.selected_instance() {
-        let state_style = if instance.session.attached {
-            Style::default().fg(t.green)
-        } else {
-            Style::default().fg(t.muted)
-        };
+        let (state, state_style) = app.state_label(instance);
 
         let mut lines = vec![
             Line::from(Span::styled(
@@ -1742,19 +3485,57 @@ fn draw_summary_panel(frame: &mut ratatui::Frame<'_>, area: Rect, app: &App) {
             ]),
             Line::from(vec![
                 Span::styled("created  ", Style::default().fg(t.muted)),
-                Span::styled(instance.session.created.clone(), Style::default().fg(t.text)),
+                Span::styled(
+                    instance.session.created.clone(),
+                    Style::default().fg(t.text),
+                ),
             ]),
             Line::from(vec![
                 Span::styled("state    ", Style::default().fg(t.muted)),
+                Span::styled(state, state_style),
+                Span::styled(
+                    if instance.session.attached {
+                        ", attached"
+                    } else {
+                        ""
+                    },
+                    Style::default().fg(t.muted),
+                ),
+                Span::styled(
+                    if app.unread.contains(&instance.key()) {
+                        ", unread"
+                    } else {
+                        ""
+                    },
+                    Style::default().fg(t.muted),
+                ),
+            ]),
+            Line::from(vec![
+                Span::styled("activity ", Style::default().fg(t.muted)),
+                Span::styled(
+                    format_ago(app.idle_seconds(instance)),
+                    Style::default().fg(t.text),
+                ),
+            ]),
+            Line::from(vec![
+                Span::styled("notify   ", Style::default().fg(t.muted)),
                 Span::styled(
-                    if instance.session.attached { "attached" } else { "idle" },
-                    state_style,
+                    notify_rule_label(
+                        monitor::NotifyRule::parse(&instance.session.notify_rule),
+                        &app.config,
+                    ),
+                    Style::default().fg(t.text),
                 ),
             ]),
             Line::from(vec![
                 Span::styled("kind     ", Style::default().fg(t.muted)),
                 Span::styled(
-                    if instance.managed { "managed" } else { "external" },
+                    match (instance.managed, instance.session.server) {
+                        (true, tmux::Server::Lattice) => "managed",
+                        (false, tmux::Server::Lattice) => "external",
+                        (true, tmux::Server::Default) => "managed (default server)",
+                        (false, tmux::Server::Default) => "external (default server)",
+                    },
                     Style::default().fg(t.text),
                 ),
             ]),
@@ -1827,11 +3608,7 @@ fn draw_instance_tab(frame: &mut ratatui::Frame<'_>, area: Rect, app: &App) {
         return;
     };
 
-    let state_style = if instance.session.attached {
-        Style::default().fg(t.green)
-    } else {
-        Style::default().fg(t.muted)
-    };
+    let (state, state_style) = app.state_label(instance);
 
     let mut lines = vec![
         Line::from(Span::styled(
@@ -1845,19 +3622,31 @@ fn draw_instance_tab(frame: &mut ratatui::Frame<'_>, area: Rect, app: &App) {
         ]),
         Line::from(vec![
             Span::styled("created  ", Style::default().fg(t.muted)),
-            Span::styled(instance.session.created.clone(), Style::default().fg(t.text)),
+            Span::styled(
+                instance.session.created.clone(),
+                Style::default().fg(t.text),
+            ),
         ]),
         Line::from(vec![
             Span::styled("state    ", Style::default().fg(t.muted)),
+            Span::styled(state, state_style),
             Span::styled(
-                if instance.session.attached { "attached" } else { "idle" },
-                state_style,
+                if instance.session.attached {
+                    ", attached"
+                } else {
+                    ""
+                },
+                Style::default().fg(t.muted),
             ),
         ]),
         Line::from(vec![
             Span::styled("windows  ", Style::default().fg(t.muted)),
             Span::styled(
-                format!("{}", instance.session.windows),
+                format!(
+                    "{} ({} panes)",
+                    instance.session.windows,
+                    instance.session.panes.len().max(1)
+                ),
                 Style::default().fg(t.text),
             ),
         ]),
@@ -1882,42 +3671,190 @@ fn draw_instance_tab(frame: &mut ratatui::Frame<'_>, area: Rect, app: &App) {
         Line::from(""),
     ];
 
-    let preview_take = area.height.saturating_sub(lines.len() as u16 + 1) as usize;
-    let preview: Vec<String> = instance
-        .session
-        .preview
-        .iter()
-        .rev()
-        .take(preview_take.max(4))
-        .cloned()
-        .collect::<Vec<String>>()
-        .into_iter()
-        .rev()
-        .collect();
+    // Spawn-time details lattice recorded, shown above the blank separator.
+    if let Some(record) = &instance.record {
+        let mut extra = Vec::new();
+        if let Some(wt) = &record.worktree {
+            extra.push(Line::from(vec![
+                Span::styled("worktree ", Style::default().fg(t.muted)),
+                Span::styled(
+                    format!("{} (from {})", wt.branch, record.original_dir),
+                    Style::default().fg(t.text),
+                ),
+            ]));
+        }
+        if let Some(user) = &record.ssh_user {
+            extra.push(Line::from(vec![
+                Span::styled("by       ", Style::default().fg(t.muted)),
+                Span::styled(user.clone(), Style::default().fg(t.text)),
+            ]));
+        }
+        if let Some(prompt) = &record.initial_prompt {
+            extra.push(Line::from(vec![
+                Span::styled("task     ", Style::default().fg(t.muted)),
+                Span::styled(
+                    prompt.lines().next().unwrap_or_default().to_owned(),
+                    Style::default().fg(t.text),
+                ),
+            ]));
+        }
+        let blank = lines.len() - 1;
+        lines.splice(blank..blank, extra);
+    }
+
+    // Every pane once there is more than the agent's, the shown one marked.
+    if instance.session.panes.len() > 1 {
+        let shown = app.viewed_pane_id(instance);
+        let mut rows = Vec::new();
+        for (i, pane) in instance.session.panes.iter().enumerate() {
+            let is_shown = pane.id == shown;
+            let what = if pane.id == instance.session.pane_id {
+                "agent".to_owned()
+            } else {
+                pane.current_path.clone()
+            };
+            rows.push(Line::from(vec![
+                Span::styled(
+                    if i == 0 { "panes    " } else { "         " },
+                    Style::default().fg(t.muted),
+                ),
+                Span::styled(
+                    format!(
+                        "{} {} ",
+                        if is_shown { "\u{25b8}" } else { " " },
+                        pane.label()
+                    ),
+                    Style::default().fg(if is_shown { t.accent } else { t.muted }),
+                ),
+                Span::styled(
+                    format!("{:<10} ", truncate(&pane.current_command, 10)),
+                    Style::default().fg(t.text),
+                ),
+                Span::styled(what, Style::default().fg(t.muted)),
+            ]));
+        }
+        let blank = lines.len() - 1;
+        lines.splice(blank..blank, rows);
+    }
+
+    let buffer = app.pane_lines(instance);
 
-    if preview.is_empty() {
+    if buffer.is_empty() {
         lines.push(Line::from(Span::styled(
             "(no output captured)",
             Style::default().fg(t.muted),
         )));
+    } else if app.interact.as_ref() == Some(&instance.key()) {
+        lines.push(Line::from(Span::styled(
+            format!("~ interactive \u{00b7} {} to return ~", app.interact_escape),
+            Style::default().fg(t.yellow).add_modifier(Modifier::BOLD),
+        )));
+    } else if let Some(pane) = app.viewed_helper(instance) {
+        lines.push(Line::from(Span::styled(
+            format!(
+                "~ pane {} \u{00b7} {} ~",
+                pane.label(),
+                pane.current_command
+            ),
+            Style::default().fg(t.accent),
+        )));
     } else {
         lines.push(Line::from(Span::styled(
             "~ live buffer ~",
             Style::default().fg(t.accent),
         )));
-        for line in preview {
-            lines.push(Line::from(Span::styled(line, Style::default().fg(t.text))));
-        }
     }
 
+    let header = Paragraph::new(Text::from(lines))
+        .style(Style::default().fg(t.text).bg(t.bg))
+        .wrap(Wrap { trim: false });
+    // Long paths and titles wrap onto more rows than there are lines.
+    let height = header.line_count(area.width) as u16;
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(height), Constraint::Min(0)])
+        .split(area);
+
+    frame.render_widget(header, chunks[0]);
+
+    // Pane lines are not wrapped so full-screen agent UIs keep their layout;
+    // the newest lines stay in view.
+    let rows = chunks[1].height as usize;
+    let skip = buffer.len().saturating_sub(rows);
     frame.render_widget(
-        Paragraph::new(Text::from(lines))
-            .style(Style::default().fg(t.text).bg(t.bg))
-            .wrap(Wrap { trim: false }),
-        area,
+        Paragraph::new(Text::from(
+            buffer.into_iter().skip(skip).collect::<Vec<_>>(),
+        ))
+        .style(Style::default().fg(t.text).bg(t.bg)),
+        chunks[1],
     );
 }
 
+/// Render the grid view: one bordered cell per session, showing the bottom
+/// of its live capture.
+fn draw_grid(frame: &mut ratatui::Frame<'_>, area: Rect, app: &App) {
+    let t = app.theme;
+    let Some(grid) = &app.grid else { return };
+    let cells = grid::cells(area, grid.panes.len(), grid.layout);
+
+    for (idx, (pane, cell)) in grid.panes.iter().zip(cells).enumerate() {
+        let instance = app.instance(&pane.key());
+        let focused = idx == grid.focused;
+        let interacting = app.interact.as_ref() == Some(&pane.key());
+
+        let border = if interacting {
+            t.yellow
+        } else if focused {
+            t.accent
+        } else {
+            t.border
+        };
+        let mut title = vec![Span::styled(
+            format!(
+                " {} ",
+                instance
+                    .map(|i| i.display_title())
+                    .unwrap_or_else(|| pane.session_name.clone())
+            ),
+            Style::default().fg(if focused { t.text } else { t.muted }),
+        )];
+        if let Some(instance) = instance {
+            let (state, state_style) = app.state_label(instance);
+            title.push(Span::styled(format!("{state} "), state_style));
+        }
+        if interacting {
+            title.push(Span::styled(
+                format!("\u{00b7} {} to return ", app.interact_escape),
+                Style::default().fg(t.yellow),
+            ));
+        }
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(border))
+            .title(Line::from(title));
+        let inner = block.inner(cell);
+        frame.render_widget(block, cell);
+
+        let lines = instance.map(|i| app.pane_lines(i)).unwrap_or_default();
+        let skip = lines.len().saturating_sub(inner.height as usize);
+        frame.render_widget(
+            Paragraph::new(Text::from(lines.into_iter().skip(skip).collect::<Vec<_>>()))
+                .style(Style::default().fg(t.text).bg(t.bg)),
+            inner,
+        );
+    }
+}
+
+/// Drop trailing blank lines so the bottom of a mostly empty pane does not
+/// push its content out of view.
+fn trim_blank_tail(mut lines: Vec<Line<'static>>) -> Vec<Line<'static>> {
+    while lines.last().is_some_and(ansi::is_blank) {
+        lines.pop();
+    }
+    lines
+}
+
 fn draw_status_line(frame: &mut ratatui::Frame<'_>, area: Rect, app: &App) {
     let t = app.theme;
 
@@ -1954,7 +3891,30 @@ fn draw_footer(frame: &mut ratatui::Frame<'_>, area: Rect, app: &App) {
     let desc_style = Style::default().fg(t.muted);
 
     let pane_count = app.split.as_ref().map(|s| s.panes.len()).unwrap_or(0);
-    let commands = if app.is_split_mode() {
+    let commands = if app.interact.is_some() {
+        Line::from(vec![
+            Span::styled("keys go to the agent   ", desc_style),
+            Span::styled(app.interact_escape.to_string(), key_style),
+            Span::styled(" return to lattice", desc_style),
+        ])
+    } else if let Some(grid) = &app.grid {
+        Line::from(vec![
+            Span::styled("\u{2190}\u{2191}\u{2193}\u{2192}", key_style),
+            Span::styled(" focus   ", desc_style),
+            Span::styled("space", key_style),
+            Span::styled(format!(" layout ({})   ", grid.layout.name()), desc_style),
+            Span::styled("i", key_style),
+            Span::styled(" interact   ", desc_style),
+            Span::styled("enter", key_style),
+            Span::styled(" attach   ", desc_style),
+            Span::styled("m", key_style),
+            Span::styled(" send   ", desc_style),
+            Span::styled("c", key_style),
+            Span::styled(" close pane   ", desc_style),
+            Span::styled("esc", key_style),
+            Span::styled(" exit grid", desc_style),
+        ])
+    } else if app.is_split_mode() {
         Line::from(vec![
             Span::styled("v", key_style),
             Span::styled(" add pane   ", desc_style),
@@ -1965,7 +3925,7 @@ fn draw_footer(frame: &mut ratatui::Frame<'_>, area: Rect, app: &App) {
             Span::styled("enter", key_style),
             Span::styled(
                 format!(
-                    " launch ({})   ",
+                    " grid ({})   ",
                     if pane_count < 2 {
                         "need 2+".to_owned()
                     } else {
@@ -1974,11 +3934,46 @@ fn draw_footer(frame: &mut ratatui::Frame<'_>, area: Rect, app: &App) {
                 ),
                 desc_style,
             ),
+            Span::styled("t", key_style),
+            Span::styled(" tmux split   ", desc_style),
             Span::styled("esc", key_style),
             Span::styled(" cancel   ", desc_style),
             Span::styled("q", key_style),
             Span::styled(" quit", desc_style),
         ])
+    } else if app
+        .current_tab_instance()
+        .is_some_and(|i| i.session.panes.len() > 1)
+    {
+        Line::from(vec![
+            Span::styled("[ ]", key_style),
+            Span::styled(" pane   ", desc_style),
+            Span::styled("enter", key_style),
+            Span::styled(" attach   ", desc_style),
+            Span::styled("f", key_style),
+            Span::styled(" focus   ", desc_style),
+            Span::styled("i", key_style),
+            Span::styled(" interact   ", desc_style),
+            Span::styled("t", key_style),
+            Span::styled(" terminal   ", desc_style),
+            Span::styled("x", key_style),
+            Span::styled(" close pane/stop   ", desc_style),
+            Span::styled("s", key_style),
+            Span::styled(" sessions   ", desc_style),
+            Span::styled("q", key_style),
+            Span::styled(" quit", desc_style),
+        ])
+    } else if app.selected_restorable().is_some() {
+        Line::from(vec![
+            Span::styled("enter", key_style),
+            Span::styled(" restore   ", desc_style),
+            Span::styled("x", key_style),
+            Span::styled(" forget   ", desc_style),
+            Span::styled("n", key_style),
+            Span::styled(" new   ", desc_style),
+            Span::styled("q", key_style),
+            Span::styled(" quit", desc_style),
+        ])
     } else {
         Line::from(vec![
             Span::styled("s", key_style),
@@ -1987,10 +3982,24 @@ fn draw_footer(frame: &mut ratatui::Frame<'_>, area: Rect, app: &App) {
             Span::styled(" jump   ", desc_style),
             Span::styled("n", key_style),
             Span::styled(" new   ", desc_style),
+            Span::styled("a", key_style),
+            Span::styled(
+                match app.unread_notifications() {
+                    0 => " inbox   ".to_owned(),
+                    n => format!(" inbox ({n})   "),
+                },
+                desc_style,
+            ),
             Span::styled("enter", key_style),
             Span::styled(" attach   ", desc_style),
             Span::styled("t", key_style),
             Span::styled(" terminal   ", desc_style),
+            Span::styled("i", key_style),
+            Span::styled(" interact   ", desc_style),
+            Span::styled("m", key_style),
+            Span::styled(" send   ", desc_style),
+            Span::styled("z", key_style),
+            Span::styled(" notify   ", desc_style),
             Span::styled("v", key_style),
             Span::styled(" split   ", desc_style),
             Span::styled("x", key_style),
@@ -2033,6 +4042,13 @@ fn draw_spawn_modal(frame: &mut ratatui::Frame<'_>, app: &App) {
         || modal.step == SpawnStep::CloneUrl
     {
         Style::default().fg(t.accent)
+    } else if modal.step == SpawnStep::Task {
+        Style::default().fg(t.green)
+    } else {
+        Style::default().fg(t.muted)
+    };
+    let task_step_style = if modal.step == SpawnStep::Task {
+        Style::default().fg(t.accent)
     } else {
         Style::default().fg(t.muted)
     };
@@ -2052,6 +4068,17 @@ fn draw_spawn_modal(frame: &mut ratatui::Frame<'_>, app: &App) {
         Line::from(vec![
             Span::styled("  2 ", path_step_style.add_modifier(Modifier::BOLD)),
             Span::styled("path", path_step_style),
+            Span::styled("   ", Style::default()),
+            Span::styled(
+                modal.working_dir.clone().unwrap_or_default(),
+                Style::default().fg(t.muted),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("  3 ", task_step_style.add_modifier(Modifier::BOLD)),
+            Span::styled("task", task_step_style),
+            Span::styled("   ", Style::default()),
+            Span::styled("(optional)", Style::default().fg(t.muted)),
         ]),
         Line::from(""),
     ];
@@ -2063,7 +4090,7 @@ fn draw_spawn_modal(frame: &mut ratatui::Frame<'_>, app: &App) {
                 Style::default().fg(t.accent),
             )));
 
-            let capacity = area.height.saturating_sub(12) as usize;
+            let capacity = area.height.saturating_sub(13) as usize;
             let (start, end) = visible_range(
                 app.available_agents.len(),
                 modal.selected_agent,
@@ -2130,7 +4157,7 @@ fn draw_spawn_modal(frame: &mut ratatui::Frame<'_>, app: &App) {
             lines.push(Line::from(""));
 
             let entries = modal.browser.entries();
-            let capacity = area.height.saturating_sub(13) as usize;
+            let capacity = area.height.saturating_sub(14) as usize;
             let (start, end) =
                 visible_range(entries.len(), modal.browser.selected(), capacity.max(1));
 
@@ -2155,7 +4182,10 @@ fn draw_spawn_modal(frame: &mut ratatui::Frame<'_>, app: &App) {
                         .fg(t.bg)
                         .bg(t.highlight_bg)
                         .add_modifier(Modifier::BOLD)
-                } else if matches!(entry.kind, EntryKind::CreateDirectory | EntryKind::CloneFromUrl) {
+                } else if matches!(
+                    entry.kind,
+                    EntryKind::CreateDirectory | EntryKind::CloneFromUrl
+                ) {
                     Style::default().fg(t.accent)
                 } else if matches!(entry.kind, EntryKind::SelectCurrent) {
                     Style::default().fg(t.green)
@@ -2265,6 +4295,59 @@ fn draw_spawn_modal(frame: &mut ratatui::Frame<'_>, app: &App) {
                 Span::styled(" back", Style::default().fg(t.muted)),
             ]));
         }
+        SpawnStep::Task => {
+            let agent_takes_arg = app
+                .available_agents
+                .get(modal.selected_agent)
+                .is_some_and(|a| !agents::needs_task_injection(a));
+            lines.push(Line::from(Span::styled(
+                if agent_takes_arg {
+                    "  first instruction (passed on the command line)"
+                } else {
+                    "  first instruction (typed in once the agent is up)"
+                },
+                Style::default().fg(t.muted),
+            )));
+            lines.push(Line::from(""));
+
+            let task_lines: Vec<&str> = modal.task.split('\n').collect();
+            let capacity = area.height.saturating_sub(14) as usize;
+            let skip = task_lines.len().saturating_sub(capacity.max(1));
+            let last = task_lines.len() - 1;
+            for (i, line) in task_lines.iter().enumerate().skip(skip) {
+                let cursor = if i == last { "_" } else { "" };
+                lines.push(Line::from(Span::styled(
+                    format!("  {line}{cursor}"),
+                    Style::default().fg(t.accent).add_modifier(Modifier::BOLD),
+                )));
+            }
+
+            lines.push(Line::from(""));
+            lines.push(Line::from(vec![
+                Span::styled(
+                    "  enter",
+                    Style::default().fg(t.text).add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(
+                    if modal.task.trim().is_empty() {
+                        " create without task   "
+                    } else {
+                        " create   "
+                    },
+                    Style::default().fg(t.muted),
+                ),
+                Span::styled(
+                    "alt+enter",
+                    Style::default().fg(t.text).add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(" newline   ", Style::default().fg(t.muted)),
+                Span::styled(
+                    "esc",
+                    Style::default().fg(t.text).add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(" back", Style::default().fg(t.muted)),
+            ]));
+        }
     }
 
     frame.render_widget(
@@ -2285,6 +4368,167 @@ fn draw_spawn_modal(frame: &mut ratatui::Frame<'_>, app: &App) {
     );
 }
 
+fn draw_send_box(frame: &mut ratatui::Frame<'_>, app: &App) {
+    let t = app.theme;
+    let Some(send) = app.send_box.as_ref() else {
+        return;
+    };
+
+    let area = centered_rect(60, 40, frame.area());
+    frame.render_widget(Clear, area);
+
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled("  to ", Style::default().fg(t.muted)),
+            Span::styled(
+                send.title.clone(),
+                Style::default().fg(t.text).add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(
+                format!("  {}", send.session_name),
+                Style::default().fg(t.muted),
+            ),
+        ]),
+        Line::from(""),
+    ];
+
+    // Show the tail of the text so the cursor stays visible.
+    let text_lines: Vec<&str> = send.text.split('\n').collect();
+    let capacity = area.height.saturating_sub(7) as usize;
+    let skip = text_lines.len().saturating_sub(capacity.max(1));
+    let last = text_lines.len() - 1;
+    for (i, line) in text_lines.iter().enumerate().skip(skip) {
+        let cursor = if i == last { "_" } else { "" };
+        lines.push(Line::from(Span::styled(
+            format!("  {line}{cursor}"),
+            Style::default().fg(t.accent).add_modifier(Modifier::BOLD),
+        )));
+    }
+
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![
+        Span::styled(
+            "  enter",
+            Style::default().fg(t.text).add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(" send   ", Style::default().fg(t.muted)),
+        Span::styled(
+            "alt+enter",
+            Style::default().fg(t.text).add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(" newline   ", Style::default().fg(t.muted)),
+        Span::styled(
+            "esc",
+            Style::default().fg(t.text).add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(" cancel", Style::default().fg(t.muted)),
+    ]));
+
+    frame.render_widget(
+        Paragraph::new(Text::from(lines))
+            .style(Style::default().fg(t.text).bg(t.bg))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(Line::from(vec![Span::styled(
+                        " send ",
+                        Style::default().fg(t.accent).add_modifier(Modifier::BOLD),
+                    )]))
+                    .border_style(Style::default().fg(t.accent))
+                    .style(Style::default().bg(t.bg)),
+            )
+            .wrap(Wrap { trim: false }),
+        area,
+    );
+}
+
+fn draw_notify_box(frame: &mut ratatui::Frame<'_>, app: &App) {
+    let t = app.theme;
+    let Some(edit) = app.notify_box.as_ref() else {
+        return;
+    };
+
+    let area = centered_rect(50, 30, frame.area());
+    frame.render_widget(Clear, area);
+
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled("  for ", Style::default().fg(t.muted)),
+            Span::styled(
+                edit.title.clone(),
+                Style::default().fg(t.text).add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(
+                format!("  {}", edit.session_name),
+                Style::default().fg(t.muted),
+            ),
+        ]),
+        Line::from(""),
+    ];
+
+    let settle = match (&edit.editing, edit.rule.settle_seconds) {
+        (Some(buf), _) => format!("{buf}_"),
+        (None, Some(secs)) => format!("{secs}s"),
+        (None, None) => format!("{}s (default)", app.config.notifications.settle_seconds),
+    };
+    for (i, (label, value)) in [
+        ("notify", edit.rule.mode.label().to_owned()),
+        ("settle time", settle),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        let style = if i == edit.selected {
+            Style::default()
+                .fg(t.bg)
+                .bg(t.highlight_bg)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(t.text)
+        };
+        lines.push(Line::from(vec![
+            Span::styled(format!("  {label:<14}"), style),
+            Span::styled(value, style),
+        ]));
+    }
+
+    let key_style = Style::default().fg(t.text).add_modifier(Modifier::BOLD);
+    let desc_style = Style::default().fg(t.muted);
+    lines.push(Line::from(""));
+    lines.push(if edit.editing.is_some() {
+        Line::from(vec![
+            Span::styled("  enter", key_style),
+            Span::styled(" save (empty for default)   ", desc_style),
+            Span::styled("esc", key_style),
+            Span::styled(" discard", desc_style),
+        ])
+    } else {
+        Line::from(vec![
+            Span::styled("  enter", key_style),
+            Span::styled(" change   ", desc_style),
+            Span::styled("esc", key_style),
+            Span::styled(" close", desc_style),
+        ])
+    });
+
+    frame.render_widget(
+        Paragraph::new(Text::from(lines))
+            .style(Style::default().fg(t.text).bg(t.bg))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(Line::from(vec![Span::styled(
+                        " notifications ",
+                        Style::default().fg(t.accent).add_modifier(Modifier::BOLD),
+                    )]))
+                    .border_style(Style::default().fg(t.accent))
+                    .style(Style::default().bg(t.bg)),
+            )
+            .wrap(Wrap { trim: false }),
+        area,
+    );
+}
+
 fn visible_range(total: usize, selected: usize, capacity: usize) -> (usize, usize) {
     if total == 0 {
         return (0, 0);
@@ -2316,6 +4560,16 @@ fn truncate(input: &str, max: usize) -> String {
     out
 }
 
+/// `secs` as a rough "N min ago".
+fn format_ago(secs: u64) -> String {
+    match secs {
+        0..60 => "just now".to_owned(),
+        60..3600 => format!("{} min ago", secs / 60),
+        3600..86400 => format!("{} h ago", secs / 3600),
+        _ => format!("{} d ago", secs / 86400),
+    }
+}
+
 fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
     let vertical = Layout::default()
         .direction(Direction::Vertical)
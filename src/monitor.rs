@@ -0,0 +1,707 @@
+//! Background watch over agent sessions. Notices when an agent finishes a
+//! turn, asks for approval, errors out or exits, records it in the inbox and
+//! tells the dashboard. Runs on its own thread, independently of the TUI
+//! event loop, so it keeps going while lattice is attached to a session.
+//!
+//! Managed sessions that no terminal is attached to are watched by tmux
+//! itself: `monitor-silence` and `monitor-activity` alerts, bells and dead
+//! panes run `lattice hook`, which appends to the `AlertLog`. Every other
+//! session, including those on the default server, is polled, comparing its
+//! screen from tick to tick.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::agents;
+use crate::config::{AppConfig, NotificationsConfig};
+use crate::instances::{AgentInstance, SharedDiscovery};
+use crate::notify;
+use crate::state::{AlertKind, AlertLog, Inbox, Notification, NotificationKind};
+use crate::status::AgentState;
+use crate::tmux;
+
+/// Screen lines kept with a notification.
+pub const NOTIFICATION_LINES: usize = 5;
+
+/// How often the alert log is checked between ticks.
+const ALERT_POLL: Duration = Duration::from_millis(200);
+
+/// A session by server and name: names are only unique per tmux server.
+type SessionKey = (tmux::Server, String);
+
+/// What the monitor saw happen in a session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ActivityEvent {
+    /// The session's output changed at `at`.
+    Changed { session: SessionKey, at: Instant },
+    /// Something worth telling the user happened; it is in the inbox too.
+    /// `terminal` is output for lattice's own terminal, from `osc` sinks.
+    Notified {
+        notification: Notification,
+        terminal: String,
+    },
+}
+
+struct SessionActivity {
+    content_hash: u64,
+    last_change: Instant,
+    /// Polled: output has moved since first sight. Alerted: since the last
+    /// silence.
+    was_active: bool,
+    notified: bool,
+}
+
+impl SessionActivity {
+    fn new(content_hash: u64, now: Instant) -> Self {
+        Self {
+            content_hash,
+            last_change: now,
+            was_active: false,
+            notified: true,
+        }
+    }
+}
+
+/// What one tick noticed about a session's output.
+#[derive(Debug, PartialEq, Eq)]
+enum Seen {
+    Changed,
+    /// Unchanged for the settle time after moving: the agent finished.
+    Settled,
+    /// The agent's pane is gone.
+    Died,
+}
+
+/// Which of a session's notifications make noise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NotifyMode {
+    #[default]
+    All,
+    /// Only approval prompts.
+    ApprovalOnly,
+    /// Nothing; events still reach the inbox.
+    Muted,
+}
+
+impl NotifyMode {
+    pub fn label(self) -> &'static str {
+        match self {
+            NotifyMode::All => "all events",
+            NotifyMode::ApprovalOnly => "approval only",
+            NotifyMode::Muted => "muted",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            NotifyMode::All => NotifyMode::ApprovalOnly,
+            NotifyMode::ApprovalOnly => NotifyMode::Muted,
+            NotifyMode::Muted => NotifyMode::All,
+        }
+    }
+}
+
+/// A session's own notification settings, kept in its `@lattice_notify`
+/// tmux option as e.g. `approval,settle=20`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct NotifyRule {
+    pub mode: NotifyMode,
+    /// Overrides `notifications.settle_seconds`.
+    pub settle_seconds: Option<u64>,
+}
+
+impl NotifyRule {
+    /// Unknown parts are ignored, so an empty or garbled option means the
+    /// defaults.
+    pub fn parse(value: &str) -> Self {
+        let mut rule = Self::default();
+        for part in value.split(',').map(str::trim) {
+            match part {
+                "mute" => rule.mode = NotifyMode::Muted,
+                "approval" => rule.mode = NotifyMode::ApprovalOnly,
+                _ => {
+                    if let Some(secs) = part.strip_prefix("settle=") {
+                        rule.settle_seconds = secs.parse().ok().filter(|s| *s > 0);
+                    }
+                }
+            }
+        }
+        rule
+    }
+
+    /// The option value; empty for the defaults.
+    pub fn value(self) -> String {
+        let mut parts = Vec::new();
+        match self.mode {
+            NotifyMode::All => {}
+            NotifyMode::ApprovalOnly => parts.push("approval".to_owned()),
+            NotifyMode::Muted => parts.push("mute".to_owned()),
+        }
+        if let Some(secs) = self.settle_seconds {
+            parts.push(format!("settle={secs}"));
+        }
+        parts.join(",")
+    }
+
+    pub fn allows(self, kind: NotificationKind) -> bool {
+        match self.mode {
+            NotifyMode::All => true,
+            NotifyMode::ApprovalOnly => kind == NotificationKind::AwaitingApproval,
+            NotifyMode::Muted => false,
+        }
+    }
+}
+
+/// Hash preview lines, stripping trailing empty lines first so that pane
+/// resize (which changes the number of trailing blanks) doesn't cause
+/// spurious hash changes.
+fn hash_preview(lines: &[String]) -> u64 {
+    let end = lines
+        .iter()
+        .rposition(|l| !l.trim().is_empty())
+        .map(|i| i + 1)
+        .unwrap_or(0);
+    let mut hasher = DefaultHasher::new();
+    lines[..end].hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Run one detection tick at `now` over `(session, preview, settle
+/// seconds)` triples.
+fn detect_tick(
+    activity: &mut HashMap<SessionKey, SessionActivity>,
+    sessions: &[(SessionKey, &[String], u64)],
+    now: Instant,
+) -> Vec<(SessionKey, Seen)> {
+    let mut seen = Vec::new();
+
+    for (key, preview, settle_seconds) in sessions {
+        let hash = hash_preview(preview);
+
+        match activity.get_mut(key) {
+            Some(entry) => {
+                if hash != entry.content_hash {
+                    entry.content_hash = hash;
+                    entry.last_change = now;
+                    entry.was_active = true;
+                    entry.notified = false;
+                    seen.push((key.clone(), Seen::Changed));
+                } else if entry.was_active
+                    && !entry.notified
+                    && now.duration_since(entry.last_change).as_secs() >= *settle_seconds
+                {
+                    entry.notified = true;
+                    seen.push((key.clone(), Seen::Settled));
+                }
+            }
+            None => {
+                activity.insert(key.clone(), SessionActivity::new(hash, now));
+            }
+        }
+    }
+
+    activity.retain(|key, _| sessions.iter().any(|(k, _, _)| k == key));
+    seen
+}
+
+/// Apply a tmux alert to a watched session. Returns what it means, and
+/// whether the session's alerts must be re-armed: tmux raises silence and
+/// activity once each until then.
+fn read_alert(entry: &mut SessionActivity, kind: AlertKind, now: Instant) -> (Option<Seen>, bool) {
+    match kind {
+        AlertKind::Activity => {
+            entry.last_change = now;
+            // The first output of a turn clears the silence raised while the
+            // agent sat idle, so the end of this turn is reported too.
+            let first = !entry.was_active;
+            entry.was_active = true;
+            if first {
+                entry.notified = false;
+            }
+            (Some(Seen::Changed), first)
+        }
+        AlertKind::Silence if entry.was_active => {
+            entry.was_active = false;
+            let settled = !entry.notified;
+            entry.notified = true;
+            (settled.then_some(Seen::Settled), true)
+        }
+        // Agents ring the bell when they finish or need the user.
+        AlertKind::Bell if !entry.notified => {
+            entry.notified = true;
+            (Some(Seen::Settled), false)
+        }
+        AlertKind::Silence | AlertKind::Bell => (None, false),
+        AlertKind::PaneDied => (Some(Seen::Died), false),
+    }
+}
+
+/// Sleep until the next tick, or until a hook appends an alert.
+fn wait_for_alerts(log: &AlertLog, offset: u64, interval: Duration) {
+    let deadline = Instant::now() + interval;
+    while log.size() == offset {
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return;
+        }
+        thread::sleep(left.min(ALERT_POLL));
+    }
+}
+
+/// The settle time that applies to `instance`.
+fn settle_seconds(config: &AppConfig, instance: &AgentInstance) -> u64 {
+    NotifyRule::parse(&instance.session.notify_rule)
+        .settle_seconds
+        .unwrap_or(config.notifications.settle_seconds)
+}
+
+/// Hand managed sessions without a terminal attached over to tmux alerts,
+/// (re)installing the hooks where they are missing or use another silence.
+/// Returns the sessions alerts now cover.
+fn watch_sessions(
+    config: &AppConfig,
+    instances: &[AgentInstance],
+    hook_command: &str,
+) -> HashSet<SessionKey> {
+    let server = tmux::Server::Lattice;
+    let mut hooked = HashSet::new();
+    for instance in instances {
+        let session = &instance.session;
+        if !instance.managed || session.server != server || session.attached {
+            continue;
+        }
+        let settle = settle_seconds(config, instance);
+        if session.alert_watch != Some(settle) {
+            let watch = tmux::watch_session(
+                server,
+                &session.name,
+                &session.pane_id,
+                settle,
+                hook_command,
+            );
+            if watch.is_err() {
+                continue;
+            }
+            // Anything raised before would never be reported.
+            let _ = tmux::rearm_alerts(server, &session.name);
+        }
+        hooked.insert(instance.key());
+    }
+    let names: Vec<&str> = hooked.iter().map(|(_, name)| name.as_str()).collect();
+    let _ = tmux::sync_alerts(server, &names);
+    hooked
+}
+
+/// What to notify about a session now in `state`, given the state it was
+/// in on the previous tick and whether its output just settled.
+fn notification_kind(
+    previous: Option<AgentState>,
+    state: AgentState,
+    settled: bool,
+) -> Option<NotificationKind> {
+    let kind = match state {
+        AgentState::AwaitingApproval => NotificationKind::AwaitingApproval,
+        AgentState::Errored => NotificationKind::Errored,
+        AgentState::Exited => NotificationKind::Exited,
+        _ => return settled.then_some(NotificationKind::Finished),
+    };
+    // Only on the way in; a session found like this at startup was
+    // reported by whoever watched it before.
+    previous.is_some_and(|p| p != state).then_some(kind)
+}
+
+/// The last `count` non-empty lines of a screen.
+fn last_lines(preview: &[String], count: usize) -> Vec<String> {
+    let mut lines: Vec<String> = preview
+        .iter()
+        .map(|l| l.trim_end())
+        .filter(|l| !l.is_empty())
+        .rev()
+        .take(count)
+        .map(ToOwned::to_owned)
+        .collect();
+    lines.reverse();
+    lines
+}
+
+/// Whether a notification may make noise, or only goes to the inbox.
+/// `since_last` is the time since the same event of the same session was
+/// last delivered, `minute` the local time of day when quiet hours are set.
+fn may_deliver(
+    notifications: &NotificationsConfig,
+    rule: NotifyRule,
+    kind: NotificationKind,
+    since_last: Option<Duration>,
+    minute: Option<u16>,
+) -> bool {
+    if notifications.do_not_disturb || !rule.allows(kind) {
+        return false;
+    }
+    if let (Some(quiet), Some(minute)) = (notifications.quiet_hours, minute)
+        && quiet.contains(minute)
+    {
+        return false;
+    }
+    since_last.is_none_or(|elapsed| elapsed.as_secs() >= notifications.min_interval)
+}
+
+/// Minutes after local midnight, from libc since std has no time zones.
+fn local_minute() -> Option<u16> {
+    // SAFETY: time(2) with a null pointer only returns the time, and
+    // localtime_r only writes to the `tm` it is given.
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    let now = unsafe { libc::time(std::ptr::null_mut()) };
+    if unsafe { libc::localtime_r(&now, &mut tm) }.is_null() {
+        return None;
+    }
+    u16::try_from(tm.tm_hour * 60 + tm.tm_min).ok()
+}
+
+/// When each session last delivered each kind of event.
+type Deliveries = HashMap<(SessionKey, NotificationKind), Instant>;
+
+fn report(
+    config: &AppConfig,
+    inbox: &Inbox,
+    events: &Sender<ActivityEvent>,
+    delivered: &mut Deliveries,
+    instance: &AgentInstance,
+    kind: NotificationKind,
+) {
+    let mut notification = Notification::new(
+        kind,
+        &instance.session.name,
+        &instance.agent.id,
+        &instance.display_title(),
+    );
+    notification.server = instance.session.server;
+    notification.lines = last_lines(&instance.session.preview, NOTIFICATION_LINES);
+    // Another lattice watching the same server may have recorded it first,
+    // and delivered it too.
+    let first = inbox.push(notification.clone()).unwrap_or(true);
+
+    let key = (instance.key(), kind);
+    let now = Instant::now();
+    let minute = config
+        .notifications
+        .quiet_hours
+        .and_then(|_| local_minute());
+    let terminal = if may_deliver(
+        &config.notifications,
+        NotifyRule::parse(&instance.session.notify_rule),
+        kind,
+        delivered.get(&key).map(|at| now.duration_since(*at)),
+        minute,
+    ) {
+        delivered.insert(key, now);
+        notify::dispatch(config, &notification, first)
+    } else {
+        String::new()
+    };
+    let _ = events.send(ActivityEvent::Notified {
+        notification,
+        terminal,
+    });
+}
+
+/// Spawn the monitor thread. What it sees is reported on `events`; the UI
+/// may be gone, so send errors are ignored. Settings changed in the UI are
+/// sent back through the returned channel. Sessions are listed through
+/// `discovery`, shared with the dashboard.
+pub fn spawn_activity_monitor(
+    config: &AppConfig,
+    events: Sender<ActivityEvent>,
+    discovery: SharedDiscovery,
+) -> Sender<AppConfig> {
+    let mut config = config.clone();
+    let interval = Duration::from_secs(config.refresh_interval.max(1));
+    let (updates_tx, updates) = mpsc::channel::<AppConfig>();
+
+    let exe = std::env::current_exe()
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_else(|_| "lattice".to_owned());
+    let alert_log = AlertLog::open();
+    // The tmux server may run with another environment, and so another
+    // state dir.
+    let hook_command = format!(
+        "{} hook --log {}",
+        agents::shell_quote(&exe),
+        agents::shell_quote(&alert_log.path().to_string_lossy())
+    );
+
+    thread::spawn(move || {
+        let inbox = Inbox::open();
+        let mut alert_offset = alert_log.size();
+        let mut activity: HashMap<SessionKey, SessionActivity> = HashMap::new();
+        let mut alerted: HashMap<SessionKey, SessionActivity> = HashMap::new();
+        let mut states: HashMap<SessionKey, AgentState> = HashMap::new();
+        let mut waiting: HashSet<SessionKey> = HashSet::new();
+        let mut delivered = Deliveries::new();
+
+        loop {
+            wait_for_alerts(&alert_log, alert_offset, interval);
+            while let Ok(update) = updates.try_recv() {
+                config = update;
+            }
+
+            // The dashboard's listing will do when it is recent enough.
+            let include_default = config.show_default_server;
+            let listing = match discovery.recent(interval, include_default) {
+                Some(listing) => Ok(listing),
+                None => {
+                    let available = agents::detect_available_agents(&config.custom_agents);
+                    discovery.discover(&available, &[], include_default)
+                }
+            };
+            let instances: Vec<AgentInstance> = listing.map(|l| l.instances).unwrap_or_default();
+            let hooked = watch_sessions(&config, &instances, &hook_command);
+            alerted.retain(|key, _| hooked.contains(key));
+            let polled: Vec<(SessionKey, &[String], u64)> = instances
+                .iter()
+                .filter(|i| !hooked.contains(&i.key()))
+                .map(|i| {
+                    (
+                        i.key(),
+                        i.session.preview.as_slice(),
+                        settle_seconds(&config, i),
+                    )
+                })
+                .collect();
+
+            let now = Instant::now();
+            let mut seen = detect_tick(&mut activity, &polled, now);
+            let (alerts, next) = alert_log.read_from(alert_offset);
+            alert_offset = next;
+            // Hooks are only set on lattice's own server.
+            for alert in alerts {
+                let key = (tmux::Server::Lattice, alert.session);
+                if !hooked.contains(&key) {
+                    continue;
+                }
+                let entry = alerted
+                    .entry(key.clone())
+                    .or_insert_with(|| SessionActivity::new(0, now));
+                let (what, rearm) = read_alert(entry, alert.kind, now);
+                if rearm {
+                    let _ = tmux::rearm_alerts(key.0, &key.1);
+                }
+                seen.extend(what.map(|what| (key, what)));
+            }
+
+            let mut settled = HashSet::new();
+            let mut died = HashSet::new();
+            for (key, seen) in seen {
+                match seen {
+                    Seen::Changed => {
+                        let _ = events.send(ActivityEvent::Changed {
+                            session: key,
+                            at: now,
+                        });
+                    }
+                    // A session that settled waits for the user until its
+                    // output moves again; the flag lives in tmux for the
+                    // picker popup.
+                    Seen::Settled => {
+                        tmux::set_attention(key.0, &key.1, true);
+                        waiting.insert(key.clone());
+                        settled.insert(key);
+                    }
+                    Seen::Died => {
+                        died.insert(key);
+                    }
+                }
+            }
+            waiting.retain(|key| match activity.get(key).or_else(|| alerted.get(key)) {
+                Some(entry) if entry.notified => true,
+                Some(_) => {
+                    tmux::set_attention(key.0, &key.1, false);
+                    false
+                }
+                None => false,
+            });
+
+            for instance in &instances {
+                let key = instance.key();
+                let state = if died.contains(&key) {
+                    AgentState::Exited
+                } else {
+                    instance.state
+                };
+                let previous = states.insert(key.clone(), state);
+                if let Some(kind) = notification_kind(previous, state, settled.contains(&key)) {
+                    report(&config, &inbox, &events, &mut delivered, instance, kind);
+                }
+            }
+            let known = |key: &SessionKey| activity.contains_key(key) || alerted.contains_key(key);
+            states.retain(|key, _| known(key));
+            delivered.retain(|(key, _), _| known(key));
+        }
+    });
+    updates_tx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::QuietHours;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn detect_tick_reports_changes_then_settling() {
+        let mut activity = HashMap::new();
+        let start = Instant::now();
+        let key = (tmux::Server::Default, "a".to_owned());
+        let tick = |activity: &mut HashMap<SessionKey, SessionActivity>, text: &str, secs: u64| {
+            let preview = vec![text.to_owned()];
+            let sessions = [(key.clone(), preview.as_slice(), 8)];
+            detect_tick(activity, &sessions, start + Duration::from_secs(secs))
+        };
+
+        // First sight only records the session.
+        assert_eq!(tick(&mut activity, "one", 0), Vec::new());
+        assert_eq!(
+            tick(&mut activity, "two", 3),
+            vec![(key.clone(), Seen::Changed)]
+        );
+        assert_eq!(tick(&mut activity, "two", 6), Vec::new());
+        assert_eq!(
+            tick(&mut activity, "two", 11),
+            vec![(key.clone(), Seen::Settled)]
+        );
+        assert_eq!(tick(&mut activity, "two", 30), Vec::new());
+    }
+
+    #[test]
+    fn alerts_report_turns_and_rearm_tmux() {
+        let now = Instant::now();
+        let mut entry = SessionActivity::new(0, now);
+        let mut read = |kind| read_alert(&mut entry, kind, now);
+
+        // Idle since lattice started watching.
+        assert_eq!(read(AlertKind::Silence), (None, false));
+        assert_eq!(read(AlertKind::Activity), (Some(Seen::Changed), true));
+        assert_eq!(read(AlertKind::Activity), (Some(Seen::Changed), false));
+        assert_eq!(read(AlertKind::Silence), (Some(Seen::Settled), true));
+        assert_eq!(read(AlertKind::Silence), (None, false));
+
+        // A bell ends the turn early; its silence only re-arms.
+        assert_eq!(read(AlertKind::Activity), (Some(Seen::Changed), true));
+        assert_eq!(read(AlertKind::Bell), (Some(Seen::Settled), false));
+        assert_eq!(read(AlertKind::Bell), (None, false));
+        assert_eq!(read(AlertKind::Silence), (None, true));
+        assert_eq!(read(AlertKind::PaneDied), (Some(Seen::Died), false));
+    }
+
+    #[test]
+    fn notifications_fire_on_entering_a_state() {
+        use AgentState::*;
+        let cases = [
+            (
+                Some(Working),
+                WaitingForInput,
+                true,
+                Some(NotificationKind::Finished),
+            ),
+            (Some(Working), WaitingForInput, false, None),
+            (
+                Some(Working),
+                AwaitingApproval,
+                false,
+                Some(NotificationKind::AwaitingApproval),
+            ),
+            // Settling at an approval prompt was already reported.
+            (Some(AwaitingApproval), AwaitingApproval, true, None),
+            (Some(Working), Exited, false, Some(NotificationKind::Exited)),
+            (
+                Some(WaitingForInput),
+                Errored,
+                true,
+                Some(NotificationKind::Errored),
+            ),
+            (None, Errored, false, None),
+        ];
+        for (previous, state, settled, expected) in cases {
+            assert_eq!(
+                notification_kind(previous, state, settled),
+                expected,
+                "{previous:?} -> {state:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn notify_rules_round_trip_through_the_option() {
+        let rule = NotifyRule::parse("approval,settle=20");
+        assert_eq!(rule.mode, NotifyMode::ApprovalOnly);
+        assert_eq!(rule.settle_seconds, Some(20));
+        assert_eq!(rule.value(), "approval,settle=20");
+        assert!(rule.allows(NotificationKind::AwaitingApproval));
+        assert!(!rule.allows(NotificationKind::Finished));
+
+        assert_eq!(NotifyRule::parse("mute").mode, NotifyMode::Muted);
+        assert_eq!(NotifyRule::parse(""), NotifyRule::default());
+        assert_eq!(NotifyRule::parse("settle=0,loud"), NotifyRule::default());
+        assert_eq!(NotifyRule::default().value(), "");
+    }
+
+    #[test]
+    fn delivery_respects_rules_quiet_hours_and_rate_limit() {
+        let mut notifications = AppConfig::default().notifications;
+        let all = NotifyRule::default();
+        let finished = NotificationKind::Finished;
+        let secs = |s: u64| Some(Duration::from_secs(s));
+        assert!(may_deliver(&notifications, all, finished, None, None));
+        assert!(may_deliver(&notifications, all, finished, secs(30), None));
+        assert!(!may_deliver(&notifications, all, finished, secs(5), None));
+
+        let approval_only = NotifyRule::parse("approval");
+        assert!(!may_deliver(
+            &notifications,
+            approval_only,
+            finished,
+            None,
+            None
+        ));
+
+        notifications.quiet_hours = QuietHours::parse("22:00-07:00");
+        assert!(!may_deliver(
+            &notifications,
+            all,
+            finished,
+            None,
+            Some(23 * 60)
+        ));
+        assert!(may_deliver(
+            &notifications,
+            all,
+            finished,
+            None,
+            Some(12 * 60)
+        ));
+        // The clock could not be read.
+        assert!(may_deliver(&notifications, all, finished, None, None));
+        assert!(local_minute().is_some_and(|minute| minute < 24 * 60));
+
+        notifications.do_not_disturb = true;
+        assert!(!may_deliver(
+            &notifications,
+            all,
+            finished,
+            None,
+            Some(12 * 60)
+        ));
+    }
+
+    #[test]
+    fn notifications_keep_the_bottom_of_the_screen() {
+        let preview: Vec<String> = ["a", "", "b  ", "c", "", ""]
+            .iter()
+            .map(|l| (*l).to_owned())
+            .collect();
+        assert_eq!(last_lines(&preview, 2), vec!["b", "c"]);
+    }
+}
@@ -0,0 +1,267 @@
+//! Delivery of notifications: the sound, plus every configured
+//! `[[notifications.sinks]]` entry that wants the event.
+
+use anyhow::{Context, Result};
+use serde_json::{Value, json};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::thread;
+
+use crate::config::{self, AppConfig, SinkConfig, SinkKind};
+use crate::state::{Notification, NotificationKind};
+use crate::tmux;
+
+/// Seconds a webhook request may take.
+const WEBHOOK_TIMEOUT: &str = "10";
+
+/// Send `notification` everywhere it is configured to go, and return what
+/// `osc` sinks want written to this process's own terminal; the UI writes
+/// it between frames so it can't land in the middle of one. Several lattice
+/// processes may watch the same sessions: `osc` sinks fire in each of them,
+/// the sound and the other sinks only for the process that recorded the
+/// event `first`.
+pub fn dispatch(config: &AppConfig, notification: &Notification, first: bool) -> String {
+    if first {
+        config::play_notification_sound(config);
+    }
+    let inside_tmux = std::env::var_os("TMUX").is_some_and(|t| !t.is_empty());
+    let mut terminal = String::new();
+    for sink in &config.notifications.sinks {
+        if !sink.wants(notification.kind) || sink.problem().is_some() {
+            continue;
+        }
+        if sink.kind == SinkKind::Osc {
+            let code = sink.osc.unwrap_or(9);
+            terminal.push_str(&osc_sequence(code, notification, inside_tmux));
+        } else if first {
+            // Nowhere to report a failing sink from the monitor thread.
+            let _ = deliver(sink, notification);
+        }
+    }
+    terminal
+}
+
+fn deliver(sink: &SinkConfig, notification: &Notification) -> Result<()> {
+    match sink.kind {
+        SinkKind::Desktop => {
+            let urgency = match notification.kind {
+                NotificationKind::AwaitingApproval | NotificationKind::Errored => "critical",
+                NotificationKind::Finished | NotificationKind::Exited => "normal",
+            };
+            let mut command = Command::new("notify-send");
+            command.args(["-a", "lattice", "-u", urgency]);
+            command.args([summary(notification), notification.lines.join("\n")]);
+            run_detached(command, None)
+        }
+        SinkKind::Webhook => {
+            let url = sink.url.as_deref().unwrap_or_default();
+            let mut command = Command::new("curl");
+            command.args(["-fsS", "-m", WEBHOOK_TIMEOUT, "-X", "POST"]);
+            command.args([
+                "-H",
+                "Content-Type: application/json",
+                "--data-binary",
+                "@-",
+            ]);
+            command.arg(url);
+            let payload = webhook_payload(sink, notification).to_string();
+            run_detached(command, Some(payload))
+        }
+        // Written by the UI, see `dispatch`.
+        SinkKind::Osc => Ok(()),
+        SinkKind::Tmux => tmux::display_message(tmux::Server::Lattice, &summary(notification)),
+        SinkKind::Command => {
+            let mut command = Command::new("sh");
+            command
+                .arg("-c")
+                .arg(sink.command.as_deref().unwrap_or_default())
+                .envs(command_env(notification));
+            run_detached(command, None)
+        }
+    }
+}
+
+/// One line saying what happened, e.g. `claude api: needs approval`.
+fn summary(notification: &Notification) -> String {
+    format!(
+        "{} {}: {}",
+        notification.agent_id,
+        notification.title,
+        notification.kind.label()
+    )
+}
+
+/// JSON body for webhooks. `text` is what Slack shows; `topic`, `title` and
+/// `message` are what ntfy reads; the rest is for custom receivers.
+fn webhook_payload(sink: &SinkConfig, notification: &Notification) -> Value {
+    let summary = summary(notification);
+    let output = notification.lines.join("\n");
+    let text = if output.is_empty() {
+        summary.clone()
+    } else {
+        format!("{summary}\n{output}")
+    };
+    let mut payload = json!({
+        "text": text,
+        "title": summary,
+        "message": if output.is_empty() { &summary } else { &output },
+        "event": notification.kind.name(),
+        "session": notification.session_name,
+        "agent": notification.agent_id,
+        "session_title": notification.title,
+        "lines": notification.lines,
+        "at": notification.at,
+    });
+    if let Some(topic) = &sink.topic {
+        payload["topic"] = json!(topic);
+    }
+    payload
+}
+
+/// OSC 9 (`ESC ] 9 ; text BEL`) or OSC 777 (`ESC ] 777 ; notify ; title ;
+/// body BEL`), wrapped for tmux to pass through when lattice runs inside
+/// it.
+fn osc_sequence(code: u16, notification: &Notification, inside_tmux: bool) -> String {
+    // Control characters would end the sequence early; `;` separates fields.
+    let clean = |text: &str| -> String {
+        text.chars()
+            .filter(|c| !c.is_control())
+            .map(|c| if c == ';' { ',' } else { c })
+            .collect()
+    };
+    let summary = clean(&summary(notification));
+    let sequence = if code == 777 {
+        let body = clean(notification.lines.last().map(String::as_str).unwrap_or(""));
+        format!("\x1b]777;notify;{summary};{body}\x07")
+    } else {
+        format!("\x1b]9;{summary}\x07")
+    };
+    if inside_tmux {
+        format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"))
+    } else {
+        sequence
+    }
+}
+
+/// Variables a command sink runs with.
+fn command_env(notification: &Notification) -> Vec<(&'static str, String)> {
+    vec![
+        ("LATTICE_EVENT", notification.kind.name().to_owned()),
+        ("LATTICE_SESSION", notification.session_name.clone()),
+        ("LATTICE_AGENT", notification.agent_id.clone()),
+        ("LATTICE_TITLE", notification.title.clone()),
+        ("LATTICE_MESSAGE", summary(notification)),
+        ("LATTICE_OUTPUT", notification.lines.join("\n")),
+        ("LATTICE_TIME", notification.at.to_string()),
+    ]
+}
+
+/// Start `command` without waiting for it, writing `input` to its stdin. A
+/// thread reaps it once it exits.
+fn run_detached(mut command: Command, input: Option<String>) -> Result<()> {
+    command
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    let mut child = command
+        .spawn()
+        .with_context(|| format!("failed to run {:?}", command.get_program()))?;
+    thread::spawn(move || {
+        if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+            let _ = stdin.write_all(input.as_bytes());
+        }
+        let _ = child.wait();
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn approval() -> Notification {
+        let mut notification = Notification::new(
+            NotificationKind::AwaitingApproval,
+            "lattice_claude_1",
+            "claude",
+            "api",
+        );
+        notification.at = 1700000000;
+        notification.lines = vec!["Bash(rm -rf target)".to_owned(), "Proceed? 1; 2".to_owned()];
+        notification
+    }
+
+    fn sink(kind: SinkKind) -> SinkConfig {
+        SinkConfig {
+            kind,
+            events: Vec::new(),
+            url: None,
+            topic: None,
+            osc: None,
+            command: None,
+        }
+    }
+
+    #[test]
+    fn webhook_payload_suits_slack_and_ntfy() {
+        let mut webhook = sink(SinkKind::Webhook);
+        webhook.topic = Some("agents".to_owned());
+        let payload = webhook_payload(&webhook, &approval());
+        assert_eq!(
+            payload["text"],
+            "claude api: needs approval\nBash(rm -rf target)\nProceed? 1; 2"
+        );
+        assert_eq!(payload["title"], "claude api: needs approval");
+        assert_eq!(payload["topic"], "agents");
+        assert_eq!(payload["event"], "awaiting_approval");
+        assert_eq!(payload["session"], "lattice_claude_1");
+        assert!(webhook_payload(&sink(SinkKind::Webhook), &approval())["topic"].is_null());
+    }
+
+    #[test]
+    fn osc_sequences_escape_and_pass_through_tmux() {
+        let notification = approval();
+        assert_eq!(
+            osc_sequence(9, &notification, false),
+            "\x1b]9;claude api: needs approval\x07"
+        );
+        assert_eq!(
+            osc_sequence(777, &notification, false),
+            "\x1b]777;notify;claude api: needs approval;Proceed? 1, 2\x07"
+        );
+        assert_eq!(
+            osc_sequence(9, &notification, true),
+            "\x1bPtmux;\x1b\x1b]9;claude api: needs approval\x07\x1b\\"
+        );
+    }
+
+    #[test]
+    fn osc_output_is_returned_for_every_process() {
+        let mut config = AppConfig::default();
+        config.notifications.sound_on_completion = false;
+        let mut finished_only = sink(SinkKind::Osc);
+        finished_only.events = vec![NotificationKind::Finished];
+        config.notifications.sinks = vec![sink(SinkKind::Osc), finished_only];
+
+        let terminal = dispatch(&config, &approval(), false);
+        assert_eq!(terminal.matches("]9;claude api: needs approval").count(), 1);
+    }
+
+    #[test]
+    fn command_env_describes_the_event() {
+        let env = command_env(&approval());
+        let get = |key: &str| env.iter().find(|(k, _)| *k == key).map(|(_, v)| v.as_str());
+        assert_eq!(get("LATTICE_EVENT"), Some("awaiting_approval"));
+        assert_eq!(get("LATTICE_SESSION"), Some("lattice_claude_1"));
+        assert_eq!(get("LATTICE_TIME"), Some("1700000000"));
+        assert_eq!(
+            get("LATTICE_OUTPUT"),
+            Some("Bash(rm -rf target)\nProceed? 1; 2")
+        );
+    }
+}
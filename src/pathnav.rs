@@ -129,7 +129,7 @@ impl Browser {
             })
             .collect();
 
-        dirs.sort_by(|a, b| a.0.to_ascii_lowercase().cmp(&b.0.to_ascii_lowercase()));
+        dirs.sort_by_key(|a| a.0.to_ascii_lowercase());
 
         let mut entries = Vec::new();
         entries.push(Entry {
@@ -0,0 +1,204 @@
+use anyhow::{Result, bail};
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEventKind},
+    execute,
+    terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
+};
+use ratatui::{
+    Terminal,
+    backend::CrosstermBackend,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::Paragraph,
+};
+use std::io;
+
+use crate::agents;
+use crate::config::AppConfig;
+use crate::instances::{self, AgentInstance};
+use crate::tmux;
+use crate::{UiTheme, truncate, visible_range};
+
+/// One agent the picker can switch to.
+struct Entry {
+    instance: AgentInstance,
+    attention: bool,
+}
+
+/// `lattice pick`: list the other agents on the current tmux server, agents
+/// waiting for input first, and switch the client to the chosen one. Meant
+/// to run in the popup bound to `tmux_pick_key`.
+pub fn run(config: &AppConfig) -> Result<()> {
+    let Some(current) = tmux::current_session() else {
+        bail!("lattice pick must run inside tmux");
+    };
+
+    let available = agents::detect_available_agents(&config.custom_agents);
+    let mut servers: Vec<(tmux::Server, bool, Vec<String>)> = Vec::new();
+    let mut entries = Vec::new();
+    for instance in instances::discover(&available, config.show_default_server)? {
+        let server = instance.session.server;
+        if !servers.iter().any(|(s, ..)| *s == server) {
+            let here = tmux::is_current_server(server);
+            let waiting = if here {
+                tmux::attention_sessions(server)
+            } else {
+                Vec::new()
+            };
+            servers.push((server, here, waiting));
+        }
+        let Some((_, here, waiting)) = servers.iter().find(|(s, ..)| *s == server) else {
+            continue;
+        };
+        // Only sessions on this client's server can be switched to.
+        if !here || instance.session.name == current {
+            continue;
+        }
+        entries.push(Entry {
+            attention: waiting.contains(&instance.session.name),
+            instance,
+        });
+    }
+    entries.sort_by_key(|e| !e.attention);
+
+    let Some(target) = choose(config, &entries)? else {
+        return Ok(());
+    };
+    let session = &target.instance.session;
+    tmux::carry_marks(session.server, &current, &session.name)?;
+    tmux::set_attention(session.server, &session.name, false);
+    tmux::switch_client(session.server, &session.name)
+}
+
+/// Show the list until an entry is picked (`Some`) or the picker is closed.
+fn choose<'a>(config: &AppConfig, entries: &'a [Entry]) -> Result<Option<&'a Entry>> {
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+    terminal.hide_cursor()?;
+
+    let theme = UiTheme::from_config(&config.theme);
+    let mut selected = 0;
+    let result = loop {
+        terminal.draw(|frame| draw(frame, frame.area(), theme, entries, selected))?;
+
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => break None,
+            KeyCode::Down | KeyCode::Char('j') | KeyCode::Tab => {
+                selected = (selected + 1).min(entries.len().saturating_sub(1));
+            }
+            KeyCode::Up | KeyCode::Char('k') | KeyCode::BackTab => {
+                selected = selected.saturating_sub(1);
+            }
+            KeyCode::Enter => break entries.get(selected),
+            KeyCode::Char(c @ '1'..='9') => {
+                let index = c as usize - '1' as usize;
+                if index < entries.len() {
+                    break entries.get(index);
+                }
+            }
+            _ => {}
+        }
+    };
+
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+    Ok(result)
+}
+
+fn draw(
+    frame: &mut ratatui::Frame<'_>,
+    area: Rect,
+    t: UiTheme,
+    entries: &[Entry],
+    selected: usize,
+) {
+    let mut lines = vec![
+        Line::from(Span::styled(
+            "~ switch agent ~",
+            Style::default().fg(t.accent),
+        )),
+        Line::from(""),
+    ];
+
+    if entries.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "no other agents on this tmux server",
+            Style::default().fg(t.muted),
+        )));
+    }
+
+    let capacity = area.height.saturating_sub(4).max(1) as usize;
+    let (start, end) = visible_range(entries.len(), selected, capacity);
+    let width = area.width as usize;
+    for (index, entry) in entries.iter().enumerate().take(end).skip(start) {
+        let instance = &entry.instance;
+        let number = if index < 9 {
+            format!("{} ", index + 1)
+        } else {
+            "  ".to_owned()
+        };
+        let marker = if entry.attention { "! " } else { "  " };
+        let label = format!(
+            "{:<10} {}",
+            truncate(&instance.agent.label, 10),
+            agents::short_instance_name(&instance.session.name)
+        );
+        let title = truncate(
+            &instance.display_title(),
+            width.saturating_sub(label.chars().count() + 8).max(8),
+        );
+
+        let base = if index == selected {
+            Style::default()
+                .fg(t.bg)
+                .bg(t.highlight_bg)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(t.text)
+        };
+        let marker_style = if index == selected {
+            base
+        } else {
+            Style::default().fg(t.yellow).add_modifier(Modifier::BOLD)
+        };
+        let muted = if index == selected {
+            base
+        } else {
+            Style::default().fg(t.muted)
+        };
+        lines.push(Line::from(vec![
+            Span::styled(number, muted),
+            Span::styled(marker, marker_style),
+            Span::styled(title, base),
+            Span::styled(format!("  {label}"), muted),
+        ]));
+    }
+
+    let footer = Line::from(Span::styled(
+        "enter switch   ! needs attention   esc close",
+        Style::default().fg(t.muted),
+    ));
+    let body = Rect {
+        height: area.height.saturating_sub(1),
+        ..area
+    };
+    frame.render_widget(Paragraph::new(lines), body);
+    frame.render_widget(
+        Paragraph::new(footer),
+        Rect {
+            y: area.y + area.height.saturating_sub(1),
+            height: area.height.min(1),
+            ..area
+        },
+    );
+}
@@ -0,0 +1,253 @@
+//! Process tree under tmux panes, read from `/proc`. Agents are often not
+//! the pane's own process: they run under `npx`, `node`, `python -m`, a
+//! wrapper script or a `direnv exec` shell.
+//!
+//! Walking the tree needs Linux's `/proc/<pid>/task/*/children`; elsewhere
+//! `tree` and `children` find nothing and agents are recognised from tmux's
+//! `pane_current_command` alone.
+
+#[cfg(target_os = "linux")]
+use std::collections::{HashSet, VecDeque};
+use std::{fs, path::Path};
+
+/// One process below a pane.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Process {
+    pub pid: u32,
+    /// Command line, split on NULs.
+    pub argv: Vec<String>,
+    /// Target of `/proc/<pid>/exe`; empty when it cannot be read.
+    pub exe: String,
+    /// Target of `/proc/<pid>/cwd`; empty when it cannot be read.
+    pub cwd: String,
+}
+
+/// Interpreters that run an agent from a script or module argument.
+const INTERPRETERS: &[&str] = &["node", "nodejs", "bun", "deno", "python", "python3"];
+
+/// Script extensions stripped from an interpreter's script argument.
+const SCRIPT_EXTENSIONS: &[&str] = &["js", "mjs", "cjs", "ts", "py"];
+
+/// Script names that say nothing about the program they start.
+const GENERIC_SCRIPTS: &[&str] = &["index", "cli", "main", "__main__"];
+
+impl Process {
+    /// Read `pid` from `/proc`, or `None` once it has exited.
+    pub fn read(pid: u32) -> Option<Self> {
+        let raw = fs::read(format!("/proc/{pid}/cmdline")).ok()?;
+        let link = |name: &str| {
+            fs::read_link(format!("/proc/{pid}/{name}"))
+                .map(|p| p.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        };
+        Some(Self {
+            pid,
+            argv: split_cmdline(&raw),
+            exe: link("exe"),
+            cwd: link("cwd"),
+        })
+    }
+
+    /// Names the process could be known by: its executable, `argv[0]`, and
+    /// for interpreters the script or `-m` module it runs.
+    pub fn names(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        let mut push = |name: String| {
+            if !name.is_empty() && !names.contains(&name) {
+                names.push(name);
+            }
+        };
+        push(leaf(&self.exe));
+        let Some(first) = self.argv.first() else {
+            return names;
+        };
+        let program = leaf(first);
+        push(program.clone());
+
+        if is_interpreter(&program) {
+            let mut args = self.argv[1..].iter();
+            while let Some(arg) = args.next() {
+                if arg == "-m" {
+                    if let Some(module) = args.next() {
+                        push(module.clone());
+                    }
+                    break;
+                }
+                if !arg.starts_with('-') {
+                    push(script_name(arg));
+                    break;
+                }
+            }
+        }
+        names
+    }
+}
+
+/// `pid` itself followed by all of its descendants, parents before children.
+#[cfg(target_os = "linux")]
+pub fn tree(pid: u32) -> Vec<Process> {
+    let mut seen = HashSet::new();
+    let mut queue = VecDeque::from([pid]);
+    let mut out = Vec::new();
+    while let Some(pid) = queue.pop_front() {
+        if pid == 0 || !seen.insert(pid) {
+            continue;
+        }
+        if let Some(process) = Process::read(pid) {
+            out.push(process);
+            queue.extend(children(pid).unwrap_or_default());
+        }
+    }
+    out
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn tree(_pid: u32) -> Vec<Process> {
+    Vec::new()
+}
+
+/// Direct children of `pid`, read from `/proc/<pid>/task/*/children`.
+#[cfg(target_os = "linux")]
+pub fn children(pid: u32) -> Option<Vec<u32>> {
+    let tasks = fs::read_dir(format!("/proc/{pid}/task")).ok()?;
+    let mut pids = Vec::new();
+    for task in tasks.flatten() {
+        let children = fs::read_to_string(task.path().join("children")).ok()?;
+        pids.extend(
+            children
+                .split_whitespace()
+                .filter_map(|c| c.parse::<u32>().ok()),
+        );
+    }
+    Some(pids)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn children(_pid: u32) -> Option<Vec<u32>> {
+    None
+}
+
+fn split_cmdline(raw: &[u8]) -> Vec<String> {
+    raw.split(|b| *b == 0)
+        .filter(|arg| !arg.is_empty())
+        .map(|arg| String::from_utf8_lossy(arg).into_owned())
+        .collect()
+}
+
+fn leaf(path: &str) -> String {
+    let path = path.trim_end_matches(" (deleted)");
+    Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+fn is_interpreter(program: &str) -> bool {
+    INTERPRETERS.iter().any(|i| {
+        program == *i
+            || program
+                .strip_prefix(i)
+                .is_some_and(|v| v.chars().all(|c| c.is_ascii_digit() || c == '.'))
+    })
+}
+
+/// What an interpreter's script is called: the npm package it belongs to,
+/// or the file name without its extension. Generic entry points such as
+/// `index.js` are named after their directory.
+fn script_name(path: &str) -> String {
+    if let Some((_, rest)) = path.rsplit_once("node_modules/") {
+        let mut parts = rest.split('/');
+        let package = match parts.next() {
+            Some(scope) if scope.starts_with('@') => parts.next(),
+            other => other,
+        };
+        if let Some(package) = package.filter(|p| !p.is_empty()) {
+            return package.to_owned();
+        }
+    }
+
+    let name = leaf(path);
+    let stem = match name.rsplit_once('.') {
+        Some((stem, ext)) if SCRIPT_EXTENSIONS.contains(&ext) => stem,
+        _ => &name,
+    };
+    if GENERIC_SCRIPTS.contains(&stem)
+        && let Some(dir) = Path::new(path).parent()
+    {
+        let dir = leaf(&dir.to_string_lossy());
+        if !dir.is_empty() && dir != "bin" && dir != "dist" {
+            return dir;
+        }
+    }
+    stem.to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn process(exe: &str, argv: &[&str]) -> Process {
+        Process {
+            exe: exe.to_owned(),
+            argv: argv.iter().map(|a| (*a).to_owned()).collect(),
+            ..Process::default()
+        }
+    }
+
+    #[test]
+    fn names_look_through_interpreters() {
+        let cases: &[(&str, &[&str], &[&str])] = &[
+            (
+                "/usr/bin/node",
+                &["node", "/usr/local/bin/codex"],
+                &["node", "codex"],
+            ),
+            (
+                "/usr/bin/node",
+                &["node", "--no-warnings", "/opt/gemini/index.mjs", "--yolo"],
+                &["node", "gemini"],
+            ),
+            (
+                "/usr/bin/node",
+                &[
+                    "node",
+                    "/usr/lib/node_modules/@anthropic-ai/claude-code/cli.js",
+                ],
+                &["node", "claude-code"],
+            ),
+            (
+                "/usr/bin/python3.12",
+                &["python3", "-u", "-m", "aider", "--model", "x"],
+                &["python3.12", "python3", "aider"],
+            ),
+            (
+                "/usr/local/bin/claude",
+                &["claude", "--continue"],
+                &["claude"],
+            ),
+            ("", &["/bin/bash", "./run.sh"], &["bash"]),
+        ];
+        for (exe, argv, expected) in cases {
+            assert_eq!(process(exe, argv).names(), *expected, "{argv:?}");
+        }
+    }
+
+    #[test]
+    fn cmdline_splits_on_nuls() {
+        assert_eq!(
+            split_cmdline(b"npm\0exec\0@openai/codex\0"),
+            vec!["npm", "exec", "@openai/codex"]
+        );
+        assert!(split_cmdline(b"").is_empty());
+        assert_eq!(leaf("/usr/bin/node (deleted)"), "node");
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn tree_starts_at_the_given_process() {
+        let me = std::process::id();
+        assert_eq!(tree(me).first().map(|p| p.pid), Some(me));
+        assert_eq!(tree(0), Vec::new());
+    }
+}
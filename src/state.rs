@@ -0,0 +1,773 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{env, fs};
+
+use crate::git::Worktree;
+use crate::tmux::{Server, TmuxSplit};
+
+/// Everything lattice knows about a session it spawned. Written once at
+/// spawn time so later steps (cleanup, reporting) don't have to guess from
+/// live tmux state.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionRecord {
+    pub session_name: String,
+    pub agent_id: String,
+    /// Directory the user picked.
+    pub original_dir: String,
+    /// Directory the agent was started in (the worktree, when there is one).
+    pub working_dir: String,
+    #[serde(default)]
+    pub worktree: Option<Worktree>,
+    #[serde(default)]
+    pub initial_prompt: Option<String>,
+    /// Login of the SSH user that spawned the session, if it came over SSH.
+    #[serde(default)]
+    pub ssh_user: Option<String>,
+    pub created_at: u64,
+    pub updated_at: u64,
+}
+
+impl SessionRecord {
+    pub fn new(
+        session_name: &str,
+        agent_id: &str,
+        original_dir: &str,
+        working_dir: &str,
+        worktree: Option<Worktree>,
+        initial_prompt: Option<&str>,
+    ) -> Self {
+        let now = unix_now();
+        Self {
+            session_name: session_name.to_owned(),
+            agent_id: agent_id.to_owned(),
+            original_dir: original_dir.to_owned(),
+            working_dir: working_dir.to_owned(),
+            worktree,
+            initial_prompt: initial_prompt.map(ToOwned::to_owned),
+            ssh_user: ssh_user(),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
+
+/// `$XDG_STATE_HOME/lattice`, falling back to `~/.local/state/lattice`.
+pub fn state_dir() -> PathBuf {
+    if let Ok(dir) = env::var("XDG_STATE_HOME")
+        && !dir.is_empty()
+    {
+        return PathBuf::from(dir).join("lattice");
+    }
+    let home = env::var("HOME").unwrap_or_else(|_| ".".to_owned());
+    PathBuf::from(home)
+        .join(".local")
+        .join("state")
+        .join("lattice")
+}
+
+/// One JSON file per session under `<state_dir>/sessions/`.
+pub struct Registry {
+    dir: PathBuf,
+}
+
+impl Registry {
+    pub fn open() -> Self {
+        Self::at(state_dir().join("sessions"))
+    }
+
+    pub fn at(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn path_for(&self, session_name: &str) -> PathBuf {
+        self.dir.join(format!("{session_name}.json"))
+    }
+
+    /// Write (or overwrite) a record, bumping `updated_at`.
+    pub fn save(&self, record: &SessionRecord) -> Result<()> {
+        fs::create_dir_all(&self.dir)
+            .with_context(|| format!("failed to create {}", self.dir.display()))?;
+
+        let mut record = record.clone();
+        record.updated_at = unix_now().max(record.created_at);
+        write_json(&self.path_for(&record.session_name), &record)
+    }
+
+    /// All readable records, sorted by session name. Unparseable files are
+    /// skipped.
+    pub fn load_all(&self) -> Vec<SessionRecord> {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        let mut records: Vec<SessionRecord> = entries
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|p| read_record(&p))
+            .collect();
+        records.sort_by(|a, b| a.session_name.cmp(&b.session_name));
+        records
+    }
+
+    /// Delete a session's record. Missing records are not an error.
+    pub fn remove(&self, session_name: &str) -> Result<()> {
+        remove_if_present(&self.path_for(session_name))
+    }
+}
+
+/// A tmux split and the lattice process that opened it. Saved before any
+/// pane is moved, so a lattice that died mid-split can be cleaned up after.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SplitRecord {
+    pub owner_pid: u32,
+    pub split: TmuxSplit,
+}
+
+impl SplitRecord {
+    pub fn new(split: TmuxSplit) -> Self {
+        Self {
+            owner_pid: std::process::id(),
+            split,
+        }
+    }
+
+    /// Whether the lattice that opened the split has exited.
+    pub fn is_orphaned(&self) -> bool {
+        let Some(pid) = libc::pid_t::try_from(self.owner_pid)
+            .ok()
+            .filter(|pid| *pid > 0)
+        else {
+            return true;
+        };
+        // Signal 0 only checks that the process exists; EPERM means it does
+        // but belongs to someone else.
+        // SAFETY: kill(2) with signal 0 sends nothing.
+        let alive = unsafe { libc::kill(pid, 0) } == 0
+            || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM);
+        !alive
+    }
+}
+
+/// One JSON file per open tmux split under `<state_dir>/splits/`.
+pub struct SplitStore {
+    dir: PathBuf,
+}
+
+impl SplitStore {
+    pub fn open() -> Self {
+        Self::at(state_dir().join("splits"))
+    }
+
+    pub fn at(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn path_for(&self, split_name: &str) -> PathBuf {
+        self.dir.join(format!("{split_name}.json"))
+    }
+
+    pub fn save(&self, record: &SplitRecord) -> Result<()> {
+        fs::create_dir_all(&self.dir)
+            .with_context(|| format!("failed to create {}", self.dir.display()))?;
+        write_json(&self.path_for(&record.split.name), record)
+    }
+
+    /// All readable records; unparseable files are skipped.
+    pub fn load_all(&self) -> Vec<SplitRecord> {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        entries
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|p| serde_json::from_str(&fs::read_to_string(p).ok()?).ok())
+            .collect()
+    }
+
+    pub fn remove(&self, split_name: &str) -> Result<()> {
+        remove_if_present(&self.path_for(split_name))
+    }
+}
+
+/// What a notification is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    /// The agent's output settled after a turn.
+    Finished,
+    AwaitingApproval,
+    Exited,
+    Errored,
+}
+
+impl NotificationKind {
+    /// Name used in config files and payloads.
+    pub fn name(self) -> &'static str {
+        match self {
+            NotificationKind::Finished => "finished",
+            NotificationKind::AwaitingApproval => "awaiting_approval",
+            NotificationKind::Exited => "exited",
+            NotificationKind::Errored => "errored",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            NotificationKind::Finished => "finished",
+            NotificationKind::AwaitingApproval => "needs approval",
+            NotificationKind::Exited => "exited",
+            NotificationKind::Errored => "error",
+        }
+    }
+}
+
+/// Something that happened in an agent session, kept in the inbox.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Notification {
+    pub kind: NotificationKind,
+    pub session_name: String,
+    /// Server the session is on; entries written before it was recorded
+    /// are from lattice's own.
+    #[serde(default)]
+    pub server: Server,
+    pub agent_id: String,
+    /// The session's display title at the time.
+    pub title: String,
+    pub at: u64,
+    /// Last non-empty lines of the agent's screen.
+    #[serde(default)]
+    pub lines: Vec<String>,
+    #[serde(default)]
+    pub read: bool,
+}
+
+impl Notification {
+    pub fn new(kind: NotificationKind, session_name: &str, agent_id: &str, title: &str) -> Self {
+        Self {
+            kind,
+            session_name: session_name.to_owned(),
+            server: Server::Lattice,
+            agent_id: agent_id.to_owned(),
+            title: title.to_owned(),
+            at: unix_now(),
+            lines: Vec::new(),
+            read: false,
+        }
+    }
+
+    /// Whether `other` is the same event, e.g. seen by another lattice
+    /// process watching the same sessions.
+    fn same_event(&self, other: &Notification) -> bool {
+        self.kind == other.kind
+            && self.session_name == other.session_name
+            && self.server == other.server
+            && self.at.abs_diff(other.at) < DUPLICATE_SECONDS
+    }
+}
+
+/// Notifications kept in the inbox; older ones are dropped.
+const INBOX_LIMIT: usize = 200;
+
+/// Events of the same kind in the same session this close together are
+/// one event.
+const DUPLICATE_SECONDS: u64 = 10;
+
+/// Notification history in `<state_dir>/inbox.json`, oldest first. Shared by
+/// every lattice process, so it survives reconnects; changes are made under
+/// a lock so concurrent writers don't drop each other's entries.
+pub struct Inbox {
+    path: PathBuf,
+}
+
+impl Inbox {
+    pub fn open() -> Self {
+        Self::at(state_dir().join("inbox.json"))
+    }
+
+    pub fn at(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// All notifications; a missing inbox is empty.
+    pub fn load(&self) -> Result<Vec<Notification>> {
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => {
+                return Err(err).with_context(|| format!("failed to read {}", self.path.display()));
+            }
+        };
+        serde_json::from_str(&contents)
+            .with_context(|| format!("failed to parse {}", self.path.display()))
+    }
+
+    /// Append a notification unless the inbox already has it. Returns
+    /// whether it was added.
+    pub fn push(&self, notification: Notification) -> Result<bool> {
+        let mut added = false;
+        self.update(|entries| {
+            if entries.iter().any(|e| e.same_event(&notification)) {
+                return;
+            }
+            entries.push(notification);
+            let excess = entries.len().saturating_sub(INBOX_LIMIT);
+            entries.drain(..excess);
+            added = true;
+        })?;
+        Ok(added)
+    }
+
+    pub fn mark_read(&self, notification: &Notification) -> Result<()> {
+        self.update(|entries| {
+            for entry in entries.iter_mut().filter(|e| e.same_event(notification)) {
+                entry.read = true;
+            }
+        })
+    }
+
+    pub fn clear(&self) -> Result<()> {
+        if !self.path.exists() {
+            return Ok(());
+        }
+        let _lock = lock(&self.path)?;
+        remove_if_present(&self.path)
+    }
+
+    /// Read, change and write the inbox while holding its lock. A file that
+    /// does not parse is left alone rather than overwritten.
+    fn update(&self, change: impl FnOnce(&mut Vec<Notification>)) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create {}", parent.display()))?;
+        }
+        let _lock = lock(&self.path)?;
+        let mut entries = self.load()?;
+        change(&mut entries);
+        write_locked(&self.path, &entries)
+    }
+}
+
+/// A tmux alert on one of lattice's sessions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertKind {
+    /// No output for the session's `monitor-silence` seconds.
+    Silence,
+    /// Output after a silence.
+    Activity,
+    /// The agent rang the terminal bell.
+    Bell,
+    /// The pane's process exited.
+    PaneDied,
+}
+
+impl AlertKind {
+    pub const ALL: [AlertKind; 4] = [
+        AlertKind::Silence,
+        AlertKind::Activity,
+        AlertKind::Bell,
+        AlertKind::PaneDied,
+    ];
+
+    /// Name on the `lattice hook` command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            AlertKind::Silence => "silence",
+            AlertKind::Activity => "activity",
+            AlertKind::Bell => "bell",
+            AlertKind::PaneDied => "pane-died",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == value)
+    }
+
+    /// The tmux hook that reports it.
+    pub fn hook(self) -> &'static str {
+        match self {
+            AlertKind::Silence => "alert-silence",
+            AlertKind::Activity => "alert-activity",
+            AlertKind::Bell => "alert-bell",
+            AlertKind::PaneDied => "pane-died",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Alert {
+    pub kind: AlertKind,
+    pub session: String,
+    pub pane: String,
+    pub at: u64,
+}
+
+impl Alert {
+    pub fn new(kind: AlertKind, session: &str, pane: &str) -> Self {
+        Self {
+            kind,
+            session: session.to_owned(),
+            pane: pane.to_owned(),
+            at: unix_now(),
+        }
+    }
+}
+
+/// Size past which the alert log starts over.
+const ALERT_LOG_LIMIT: u64 = 64 * 1024;
+
+/// Alerts appended by `lattice hook` as tmux runs it, one JSON object per
+/// line, in `<state_dir>/alerts.jsonl`. Every lattice process reads it from
+/// its own offset.
+pub struct AlertLog {
+    path: PathBuf,
+}
+
+impl AlertLog {
+    pub fn open() -> Self {
+        Self::at(state_dir().join("alerts.jsonl"))
+    }
+
+    pub fn at(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Current size, where a new reader starts.
+    pub fn size(&self) -> u64 {
+        fs::metadata(&self.path).map(|m| m.len()).unwrap_or(0)
+    }
+
+    pub fn append(&self, alert: &Alert) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create {}", parent.display()))?;
+        }
+        // Readers notice the file shrinking and start from the top.
+        let restart = self.size() > ALERT_LOG_LIMIT;
+        let mut line = serde_json::to_string(alert).context("failed to serialize alert")?;
+        line.push('\n');
+        fs::OpenOptions::new()
+            .create(true)
+            .append(!restart)
+            .write(true)
+            .truncate(restart)
+            .open(&self.path)
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+            .with_context(|| format!("failed to write {}", self.path.display()))
+    }
+
+    /// Alerts written after `offset`, and the offset to read from next.
+    /// Unreadable lines are skipped; a line still being written is left
+    /// for the next read.
+    pub fn read_from(&self, offset: u64) -> (Vec<Alert>, u64) {
+        let Ok(contents) = fs::read(&self.path) else {
+            return (Vec::new(), 0);
+        };
+        let start = if (contents.len() as u64) < offset {
+            0
+        } else {
+            offset as usize
+        };
+        let rest = &contents[start..];
+        let complete = rest.iter().rposition(|b| *b == b'\n').map_or(0, |i| i + 1);
+        let alerts = String::from_utf8_lossy(&rest[..complete])
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect();
+        (alerts, (start + complete) as u64)
+    }
+}
+
+/// Write to a temp file and rename so readers never see a partial file,
+/// holding the file's lock so writers take turns.
+fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    let _lock = lock(path)?;
+    write_locked(path, value)
+}
+
+/// `write_json` for a caller that already holds the lock.
+fn write_locked<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    static WRITES: AtomicU64 = AtomicU64::new(0);
+
+    let json = serde_json::to_string_pretty(value).context("failed to serialize record")?;
+    // Unique per process and write, so concurrent writers never share one.
+    let write = WRITES.fetch_add(1, Ordering::Relaxed);
+    let tmp = path.with_extension(format!("json.{}.{write}.tmp", std::process::id()));
+    if let Err(err) = fs::write(&tmp, json) {
+        let _ = fs::remove_file(&tmp);
+        return Err(err).with_context(|| format!("failed to write {}", tmp.display()));
+    }
+    if let Err(err) = fs::rename(&tmp, path) {
+        let _ = fs::remove_file(&tmp);
+        return Err(err).with_context(|| format!("failed to write {}", path.display()));
+    }
+    Ok(())
+}
+
+/// Take the exclusive lock guarding `path`, held until the returned file is
+/// dropped. It is one `.lock` file per directory: `path` itself is replaced
+/// on every write, which would leave a lock on it guarding the old file.
+fn lock(path: &Path) -> Result<File> {
+    let lock_path = path.with_file_name(".lock");
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)
+        .with_context(|| format!("failed to open {}", lock_path.display()))?;
+    file.lock()
+        .with_context(|| format!("failed to lock {}", lock_path.display()))?;
+    Ok(file)
+}
+
+fn remove_if_present(path: &Path) -> Result<()> {
+    match fs::remove_file(path) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(err).with_context(|| format!("failed to remove {}", path.display())),
+    }
+}
+
+fn read_record(path: &Path) -> Option<SessionRecord> {
+    let contents = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+/// The local user, but only when lattice is running inside an SSH login.
+fn ssh_user() -> Option<String> {
+    let over_ssh = env::var_os("SSH_CONNECTION").is_some() || env::var_os("SSH_CLIENT").is_some();
+    if !over_ssh {
+        return None;
+    }
+    env::var("USER")
+        .or_else(|_| env::var("LOGNAME"))
+        .ok()
+        .filter(|u| !u.is_empty())
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn temp_registry(name: &str) -> Registry {
+        let dir = env::temp_dir().join(format!("lattice_state_{name}_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        Registry::at(dir)
+    }
+
+    #[test]
+    fn registry_round_trips_records() {
+        let registry = temp_registry("roundtrip");
+        let record = SessionRecord::new(
+            "lattice_claude_1700000000",
+            "claude",
+            "/src/app",
+            "/src/app/.lattice/worktrees/1700000000",
+            Some(Worktree {
+                path: PathBuf::from("/src/app/.lattice/worktrees/1700000000"),
+                branch: "lattice/1700000000".to_owned(),
+                repo_root: PathBuf::from("/src/app"),
+            }),
+            Some("fix the flaky test"),
+        );
+
+        registry.save(&record).expect("save");
+        let all = registry.load_all();
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].worktree, record.worktree);
+        assert_eq!(all[0].initial_prompt.as_deref(), Some("fix the flaky test"));
+
+        registry
+            .remove("lattice_claude_1700000000")
+            .expect("remove");
+        assert!(registry.load_all().is_empty());
+        // Removing twice is fine
+        registry
+            .remove("lattice_claude_1700000000")
+            .expect("remove again");
+
+        let _ = fs::remove_dir_all(&registry.dir);
+    }
+
+    #[test]
+    fn load_all_skips_unreadable_files() {
+        let registry = temp_registry("garbage");
+        let record = SessionRecord::new("lattice_codex_1", "codex", "/a", "/a", None, None);
+        registry.save(&record).expect("save");
+        fs::write(registry.dir.join("broken.json"), "{not json").expect("write");
+        fs::write(registry.dir.join("notes.txt"), "hi").expect("write");
+
+        let all = registry.load_all();
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].session_name, "lattice_codex_1");
+
+        let _ = fs::remove_dir_all(&registry.dir);
+    }
+
+    #[test]
+    fn split_store_round_trips_records() {
+        let dir = env::temp_dir().join(format!("lattice_splits_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let store = SplitStore::at(&dir);
+        let record = SplitRecord::new(TmuxSplit {
+            name: "lattice_split_1700000000".to_owned(),
+            server: crate::tmux::Server::Lattice,
+            panes: vec![crate::tmux::LentPane {
+                session: "lattice_codex_1".to_owned(),
+                pane_id: "%3".to_owned(),
+                placeholder_id: "%9".to_owned(),
+            }],
+        });
+        assert!(!record.is_orphaned());
+        let gone = SplitRecord {
+            owner_pid: u32::MAX,
+            ..record.clone()
+        };
+        assert!(gone.is_orphaned());
+
+        store.save(&record).expect("save");
+        assert_eq!(store.load_all(), vec![record]);
+        store.remove("lattice_split_1700000000").expect("remove");
+        assert!(store.load_all().is_empty());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn inbox_keeps_one_entry_per_event() {
+        let dir = env::temp_dir().join(format!("lattice_inbox_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let inbox = Inbox::at(dir.join("inbox.json"));
+        assert!(inbox.load().expect("load").is_empty());
+
+        let mut done = Notification::new(
+            NotificationKind::Finished,
+            "lattice_codex_1",
+            "codex",
+            "api",
+        );
+        done.lines = vec!["All tests pass.".to_owned()];
+        assert!(inbox.push(done.clone()).expect("push"));
+        // A second lattice reporting the same turn a moment later.
+        let mut again = done.clone();
+        again.at += 2;
+        assert!(!inbox.push(again).expect("push"));
+        // The same name on another server is another session.
+        let mut elsewhere = done.clone();
+        elsewhere.server = Server::Default;
+        assert!(inbox.push(elsewhere).expect("push"));
+        let approval = Notification::new(
+            NotificationKind::AwaitingApproval,
+            "lattice_codex_1",
+            "codex",
+            "api",
+        );
+        assert!(inbox.push(approval.clone()).expect("push"));
+
+        inbox.mark_read(&done).expect("mark read");
+        let entries = inbox.load().expect("load");
+        assert_eq!(entries.len(), 3);
+        let read: Vec<bool> = entries.iter().map(|e| e.read).collect();
+        assert_eq!(read, vec![true, false, false]);
+        assert_eq!(entries[0].lines, done.lines);
+
+        inbox.clear().expect("clear");
+        assert!(inbox.load().expect("load").is_empty());
+
+        // A broken inbox is reported, not replaced.
+        fs::write(&inbox.path, "[{").expect("write");
+        assert!(inbox.load().is_err());
+        assert!(inbox.push(approval).is_err());
+        assert_eq!(fs::read_to_string(&inbox.path).expect("read"), "[{");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn concurrent_inbox_writers_keep_every_entry() {
+        let dir = env::temp_dir().join(format!("lattice_inbox_race_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("inbox.json");
+
+        let writers: Vec<_> = (0..8)
+            .map(|n| {
+                let inbox = Inbox::at(&path);
+                std::thread::spawn(move || {
+                    for i in 0..5 {
+                        let session = format!("lattice_codex_{n}_{i}");
+                        let notification =
+                            Notification::new(NotificationKind::Finished, &session, "codex", "x");
+                        inbox.push(notification).expect("push");
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().expect("writer");
+        }
+
+        assert_eq!(Inbox::at(&path).load().expect("load").len(), 40);
+        let leftovers = fs::read_dir(&dir)
+            .expect("read dir")
+            .flatten()
+            .filter(|e| e.path().to_string_lossy().ends_with(".tmp"))
+            .count();
+        assert_eq!(leftovers, 0);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn alert_log_reads_new_lines_from_an_offset() {
+        let dir = env::temp_dir().join(format!("lattice_alerts_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let log = AlertLog::at(dir.join("alerts.jsonl"));
+        assert_eq!(log.read_from(0), (Vec::new(), 0));
+
+        let silence = Alert::new(AlertKind::Silence, "lattice_codex_1", "%1");
+        log.append(&silence).expect("append");
+        let (alerts, offset) = log.read_from(0);
+        assert_eq!(alerts, vec![silence.clone()]);
+        assert_eq!(offset, log.size());
+
+        // Half a line from a hook still writing.
+        let bell = Alert::new(AlertKind::Bell, "lattice_codex_1", "%1");
+        log.append(&bell).expect("append");
+        let mut file = fs::OpenOptions::new()
+            .append(true)
+            .open(&log.path)
+            .expect("open");
+        file.write_all(b"{\"kind\":").expect("write");
+        let (alerts, next) = log.read_from(offset);
+        assert_eq!(alerts, vec![bell]);
+        assert!(next < log.size());
+
+        // Started over by another writer.
+        fs::write(&log.path, "").expect("truncate");
+        log.append(&silence).expect("append");
+        assert_eq!(log.read_from(next).0, vec![silence]);
+
+        assert_eq!(AlertKind::parse("pane-died"), Some(AlertKind::PaneDied));
+        assert_eq!(AlertKind::parse("alert-bell"), None);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,368 @@
+use crate::procs;
+use regex::{Regex, RegexBuilder};
+use serde::Serialize;
+
+/// What an agent is doing, as read from its pane.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AgentState {
+    /// Just launched and not showing its prompt yet.
+    Starting,
+    /// Busy on a turn, or its output is still moving.
+    Working,
+    /// Idle at its prompt.
+    WaitingForInput,
+    /// Asking the user to allow a command or an edit.
+    AwaitingApproval,
+    /// Stopped by a rate or usage limit.
+    RateLimited,
+    /// Showing an error it did not recover from.
+    Errored,
+    /// The agent quit and its pane is back at the shell.
+    Exited,
+}
+
+impl AgentState {
+    pub fn label(self) -> &'static str {
+        match self {
+            AgentState::Starting => "starting",
+            AgentState::Working => "working",
+            AgentState::WaitingForInput => "waiting for input",
+            AgentState::AwaitingApproval => "needs approval",
+            AgentState::RateLimited => "rate limited",
+            AgentState::Errored => "error",
+            AgentState::Exited => "exited",
+        }
+    }
+}
+
+/// Seconds after launch during which an agent without a visible prompt is
+/// still considered starting.
+const STARTING_SECONDS: u64 = 20;
+
+/// Output within this many seconds counts as the agent working.
+const ACTIVE_SECONDS: u64 = 3;
+
+/// Only the bottom of the screen describes the current state; anything
+/// higher up is history.
+const TAIL_LINES: usize = 12;
+
+/// Generic prompts and crash text that tools print as well only count on
+/// this many bottom lines, where the agent itself would be waiting.
+const PROMPT_LINES: usize = 3;
+
+const SHELLS: &[&str] = &["sh", "bash", "zsh", "fish", "dash", "ksh", "tcsh", "nu"];
+
+/// Agent UI text, matched case-insensitively anywhere in the tail, that
+/// identifies each state.
+const APPROVAL_MARKERS: &[&str] = &[
+    "do you want to proceed",
+    "do you want to make this edit",
+    "do you want to create",
+    "would you like to run the following command",
+    "allow command?",
+    "allow execution",
+    "apply this change",
+    "yes, and don't ask again",
+];
+const BUSY_MARKERS: &[&str] = &["esc to interrupt", "ctrl+c to interrupt", "esc to cancel"];
+const ERROR_MARKERS: &[&str] = &["api error"];
+
+/// Text any command can print, matched only within `PROMPT_LINES`: a
+/// `[y/n]`, panic or rate limit in tool output the agent has moved past is
+/// not its state.
+const YES_NO_MARKERS: &[&str] = &["(y)es/(n)o", "[y/n]", "(y/n)"];
+const RATE_LIMIT_MARKERS: &[&str] = &[
+    "rate limit",
+    "rate-limit",
+    "usage limit",
+    "quota exceeded",
+    "too many requests",
+    "resource_exhausted",
+];
+const CRASH_MARKERS: &[&str] = &[
+    "panicked at",
+    "traceback (most recent call last)",
+    "fatal error",
+    "unhandled exception",
+];
+
+/// Screen patterns an agent adapter adds to the built-in markers: regular
+/// expressions, each matched against one screen line at a time, ignoring
+/// case.
+#[derive(Debug, Clone, Default)]
+pub struct ScreenPatterns {
+    /// The agent sits at its prompt.
+    pub idle: Vec<Regex>,
+    /// The agent is in the middle of a turn.
+    pub busy: Vec<Regex>,
+    /// The agent asks to allow a command or edit.
+    pub approval: Vec<Regex>,
+    /// The agent hit an error.
+    pub error: Vec<Regex>,
+}
+
+impl ScreenPatterns {
+    fn sources(&self) -> [Vec<&str>; 4] {
+        [&self.idle, &self.busy, &self.approval, &self.error]
+            .map(|list| list.iter().map(Regex::as_str).collect())
+    }
+}
+
+impl PartialEq for ScreenPatterns {
+    fn eq(&self, other: &Self) -> bool {
+        self.sources() == other.sources()
+    }
+}
+
+impl Eq for ScreenPatterns {}
+
+/// Compile an adapter's screen pattern.
+pub fn compile_pattern(pattern: &str) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(pattern).case_insensitive(true).build()
+}
+
+/// Everything the classifier looks at for one pane.
+pub struct Signals<'a> {
+    pub preview: &'a [String],
+    /// tmux's `pane_current_command`.
+    pub current_command: &'a str,
+    /// Whether the pane's own process has children, `None` when `/proc`
+    /// cannot tell.
+    pub has_children: Option<bool>,
+    pub ready_markers: &'a [String],
+    pub patterns: &'a ScreenPatterns,
+    /// Seconds since the session was created.
+    pub age: u64,
+    /// Seconds since the pane's window last produced output.
+    pub idle: u64,
+}
+
+pub fn classify(signals: &Signals<'_>) -> AgentState {
+    let at_shell = SHELLS.contains(&signals.current_command);
+    if at_shell && signals.has_children != Some(true) {
+        // Managed sessions type the launch command into a shell, which is
+        // all there is for a moment after creation too.
+        return if signals.age < STARTING_SECONDS {
+            AgentState::Starting
+        } else {
+            AgentState::Exited
+        };
+    }
+
+    let tail = screen_tail(signals.preview, TAIL_LINES);
+    let bottom = screen_tail(signals.preview, PROMPT_LINES);
+    let shows = |markers: &[&str], patterns: &[Regex]| {
+        markers.iter().any(|m| tail.contains(m))
+            || patterns
+                .iter()
+                .any(|p| tail.lines().any(|line| p.is_match(line)))
+    };
+    let near_prompt = |markers: &[&str]| markers.iter().any(|m| bottom.contains(m));
+    let patterns = signals.patterns;
+    if shows(APPROVAL_MARKERS, &patterns.approval) || near_prompt(YES_NO_MARKERS) {
+        AgentState::AwaitingApproval
+    } else if shows(BUSY_MARKERS, &patterns.busy) {
+        AgentState::Working
+    } else if near_prompt(RATE_LIMIT_MARKERS) {
+        AgentState::RateLimited
+    } else if shows(ERROR_MARKERS, &patterns.error) || near_prompt(CRASH_MARKERS) {
+        AgentState::Errored
+    } else if shows(&[], &patterns.idle) {
+        AgentState::WaitingForInput
+    } else if signals.age < STARTING_SECONDS
+        && !signals
+            .ready_markers
+            .iter()
+            .any(|m| tail.contains(&m.to_lowercase()))
+    {
+        AgentState::Starting
+    } else if signals.idle < ACTIVE_SECONDS {
+        AgentState::Working
+    } else {
+        AgentState::WaitingForInput
+    }
+}
+
+/// The last `count` non-empty lines, lowercased and joined.
+fn screen_tail(preview: &[String], count: usize) -> String {
+    let mut lines: Vec<&str> = preview
+        .iter()
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty())
+        .rev()
+        .take(count)
+        .collect();
+    lines.reverse();
+    lines.join("\n").to_lowercase()
+}
+
+/// Whether process `pid` has any child processes.
+pub fn has_children(pid: u32) -> Option<bool> {
+    procs::children(pid).map(|c| !c.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const NO_PATTERNS: &ScreenPatterns = &ScreenPatterns {
+        idle: Vec::new(),
+        busy: Vec::new(),
+        approval: Vec::new(),
+        error: Vec::new(),
+    };
+
+    fn patterns(list: &[&str]) -> Vec<Regex> {
+        list.iter()
+            .map(|p| compile_pattern(p).expect("valid pattern"))
+            .collect()
+    }
+
+    fn signals<'a>(preview: &'a [String], command: &'a str) -> Signals<'a> {
+        Signals {
+            preview,
+            current_command: command,
+            has_children: Some(true),
+            ready_markers: &[],
+            patterns: NO_PATTERNS,
+            age: 600,
+            idle: 60,
+        }
+    }
+
+    fn screen(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|l| (*l).to_owned()).collect()
+    }
+
+    #[test]
+    fn screens_classify_by_their_markers() {
+        let cases = [
+            (vec!["> "], AgentState::WaitingForInput),
+            (
+                vec!["• Working (12s • esc to interrupt)"],
+                AgentState::Working,
+            ),
+            (
+                vec!["Bash(rm -rf target)", "Do you want to proceed?", "❯ 1. Yes"],
+                AgentState::AwaitingApproval,
+            ),
+            (
+                vec!["You've hit your usage limit. Try again at 5pm."],
+                AgentState::RateLimited,
+            ),
+            (
+                vec!["API Error: 500 Internal Server Error", "> "],
+                AgentState::Errored,
+            ),
+            (
+                vec!["Overwrite config.toml? [y/N]"],
+                AgentState::AwaitingApproval,
+            ),
+            (
+                vec!["thread 'main' panicked at src/main.rs:2:5"],
+                AgentState::Errored,
+            ),
+        ];
+        for (lines, expected) in cases {
+            let preview = screen(&lines);
+            assert_eq!(
+                classify(&signals(&preview, "claude")),
+                expected,
+                "{lines:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn old_markers_above_the_tail_are_ignored() {
+        let mut lines = vec!["API Error: overloaded"];
+        lines.extend(["output"; TAIL_LINES]);
+        let preview = screen(&lines);
+        assert_eq!(
+            classify(&signals(&preview, "codex")),
+            AgentState::WaitingForInput
+        );
+    }
+
+    #[test]
+    fn tool_output_above_the_prompt_is_not_the_agent_state() {
+        let preview = screen(&[
+            "⏺ Bash(cargo test)",
+            "  ⎿  thread 'main' panicked at src/lib.rs:3:5",
+            "     fatal error: aborting due to previous error",
+            "     Remove the lock file? [y/n]",
+            "     error: 429 Too Many Requests",
+            "⏺ The test panics because the fixture is missing.",
+            "╭──────────────────────────────╮",
+            "│ >                            │",
+            "╰──────────────────────────────╯",
+            "  ? for shortcuts",
+        ]);
+        assert_eq!(
+            classify(&signals(&preview, "claude")),
+            AgentState::WaitingForInput
+        );
+
+        // Working on rate limiting is still working.
+        let preview = screen(&[
+            "⏺ Update(src/client.rs)",
+            "  ⎿  Back off when the API reports a rate limit",
+            "✻ Writing tests… (12s · esc to interrupt)",
+        ]);
+        assert_eq!(classify(&signals(&preview, "claude")), AgentState::Working);
+    }
+
+    #[test]
+    fn adapter_patterns_extend_the_markers() {
+        let allow = compile_pattern(r"Allow .* to run\?").expect("valid pattern");
+        assert!(allow.is_match("allow `rm` to run? [y]"));
+        assert!(!allow.is_match("allow `rm` to run"));
+        assert!(compile_pattern("(unclosed").is_err());
+
+        let patterns = ScreenPatterns {
+            idle: patterns(&["^tool>"]),
+            approval: patterns(&[r"Allow .* to run\?"]),
+            busy: patterns(&[r"thinking\.+ \d+s"]),
+            error: Vec::new(),
+        };
+        let cases = [
+            ("tool> ", AgentState::WaitingForInput),
+            ("Allow shell to run?", AgentState::AwaitingApproval),
+            ("Thinking... 4s", AgentState::Working),
+        ];
+        for (line, expected) in cases {
+            let preview = screen(&[line]);
+            let mut signals = signals(&preview, "tool");
+            signals.idle = 0;
+            signals.patterns = &patterns;
+            assert_eq!(classify(&signals), expected, "{line}");
+        }
+    }
+
+    #[test]
+    fn timing_and_process_signals() {
+        let preview = screen(&["booting"]);
+        let ready = vec!["? for shortcuts".to_owned()];
+
+        let mut young = signals(&preview, "codex");
+        young.age = 5;
+        young.ready_markers = &ready;
+        assert_eq!(classify(&young), AgentState::Starting);
+
+        let mut busy = signals(&preview, "codex");
+        busy.idle = 1;
+        assert_eq!(classify(&busy), AgentState::Working);
+
+        let mut gone = signals(&preview, "zsh");
+        gone.has_children = Some(false);
+        assert_eq!(classify(&gone), AgentState::Exited);
+        gone.age = 2;
+        assert_eq!(classify(&gone), AgentState::Starting);
+
+        // A wrapper script still running the agent under the shell.
+        let wrapped = signals(&preview, "bash");
+        assert_eq!(classify(&wrapped), AgentState::WaitingForInput);
+    }
+}
@@ -1,5 +1,75 @@
 use anyhow::{Context, Result, anyhow};
-use std::process::Command;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::state::AlertKind;
+
+/// Which tmux server a command talks to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Server {
+    /// Where lattice spawns agents: the configured `tmux_socket`, or the
+    /// default server when none is set.
+    #[default]
+    Lattice,
+    /// The user's default server, listed alongside a dedicated socket.
+    Default,
+}
+
+impl Server {
+    pub fn name(self) -> &'static str {
+        match self {
+            Server::Lattice => "lattice",
+            Server::Default => "default",
+        }
+    }
+}
+
+static SOCKET: OnceLock<Option<String>> = OnceLock::new();
+
+/// Route lattice's own tmux commands to a dedicated server. A value with a
+/// `/` is a socket path (`-S`), anything else a socket name (`-L`). Must be
+/// called before the first tmux command.
+pub fn set_socket(socket: Option<String>) {
+    let _ = SOCKET.set(socket.filter(|s| !s.trim().is_empty()));
+}
+
+/// Whether lattice runs its sessions on a server of its own.
+pub fn has_dedicated_socket() -> bool {
+    SOCKET.get().is_some_and(Option::is_some)
+}
+
+fn socket_args(server: Server) -> Option<(&'static str, &'static str)> {
+    let socket = SOCKET.get()?.as_deref()?;
+    match server {
+        Server::Lattice => Some((socket_flag(socket), socket)),
+        Server::Default => None,
+    }
+}
+
+fn socket_flag(socket: &str) -> &'static str {
+    if socket.contains('/') { "-S" } else { "-L" }
+}
+
+/// A `tmux` command aimed at `server`. Every tmux invocation goes through
+/// here so the socket setting is honoured everywhere.
+pub fn command(server: Server) -> Command {
+    let mut cmd = Command::new("tmux");
+    // -u: without a UTF-8 locale (e.g. a bare SSH ForceCommand login) tmux
+    // turns the tabs in `-F` output into `_`. lattice's UI needs a UTF-8
+    // terminal anyway.
+    cmd.arg("-u");
+    if let Some((flag, socket)) = socket_args(server) {
+        cmd.arg(flag).arg(socket);
+    }
+    cmd
+}
 
 pub fn is_tmux_available() -> bool {
     Command::new("tmux")
@@ -12,98 +82,341 @@ pub fn is_tmux_available() -> bool {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Session {
     pub name: String,
+    pub server: Server,
     pub attached: bool,
     pub windows: u32,
     pub created: String,
+    /// Session creation time as a unix timestamp.
+    pub created_unix: u64,
     pub current_command: String,
     pub pane_current_path: String,
     pub pane_title: String,
+    /// Raw `@lattice_notify` session option: the session's notification
+    /// rule, see `monitor::NotifyRule`.
+    pub notify_rule: String,
+    /// Silence (in seconds) lattice's alert hooks were set up with, from
+    /// the `@lattice_watch` session option; `None` when they are not.
+    pub alert_watch: Option<u64>,
+    /// Id (`%N`) of the pane the metadata and preview come from.
+    pub pane_id: String,
+    /// Pid of the process tmux started in that pane (usually a shell).
+    pub pane_pid: u32,
+    /// Every pane of the session across all windows, in window and pane
+    /// order.
+    pub panes: Vec<Pane>,
+    /// Unix second the pane's window last had output.
+    pub activity_unix: u64,
     pub preview: Vec<String>,
     pub last_line: String,
 }
 
-pub fn list_sessions() -> Result<Vec<Session>> {
-    let raw = match run_tmux(&[
-        "list-sessions",
-        "-F",
-        "#{session_name}\t#{session_attached}\t#{session_windows}\t#{session_created_string}",
-    ]) {
+impl Session {
+    /// Make `pane_id` the pane the session's metadata describes. The
+    /// preview was captured from the other pane, so it is dropped until a
+    /// listing captures this one.
+    pub fn use_pane(&mut self, pane_id: &str) {
+        let Some(pane) = self.panes.iter().find(|p| p.id == pane_id) else {
+            return;
+        };
+        self.pane_id = pane.id.clone();
+        self.pane_pid = pane.pid;
+        self.current_command = pane.current_command.clone();
+        self.pane_current_path = pane.current_path.clone();
+        self.preview.clear();
+        self.last_line = "(no output yet)".to_owned();
+    }
+}
+
+/// One pane of a session.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Pane {
+    pub id: String,
+    pub window: u32,
+    pub index: u32,
+    pub pid: u32,
+    pub current_command: String,
+    pub current_path: String,
+    /// The active pane of the session's active window.
+    pub active: bool,
+    /// Marked with `mark_agent_pane` as the pane the agent runs in.
+    pub agent: bool,
+}
+
+impl Pane {
+    /// `window.pane`, as tmux shows it.
+    pub fn label(&self) -> String {
+        format!("{}.{}", self.window, self.index)
+    }
+}
+
+/// Fields queried per pane by `list-panes -a`. `pane_title` goes last since
+/// it is free text.
+const PANE_FORMAT: &str = "#{session_name}\t#{session_attached_list}\t#{session_windows}\t#{t:session_created}\t#{session_created}\t#{window_active}\t#{pane_active}\t#{pane_id}\t#{pane_current_command}\t#{pane_current_path}\t#{window_activity}\t#{history_size}\t#{cursor_x}\t#{cursor_y}\t#{pane_pid}\t#{window_index}\t#{pane_index}\t#{@lattice_agent}\t#{@lattice_notify}\t#{@lattice_watch}\t#{pane_title}";
+
+const PANE_FIELDS: usize = 21;
+
+/// Pane option marking the pane an agent runs in.
+const AGENT_PANE_OPTION: &str = "@lattice_agent";
+
+/// Cheap signals that change whenever a pane's contents do. tmux 3.x has no
+/// per-pane activity timestamp, so the window's (second resolution) is
+/// combined with the scrollback size and cursor position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ActivityKey {
+    activity: u64,
+    history_size: u64,
+    cursor: (u32, u32),
+}
+
+/// A pane's activity key as of one of its captures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CaptureStamp {
+    key: ActivityKey,
+    /// Unix second the capture was taken in.
+    captured_at: u64,
+}
+
+impl CaptureStamp {
+    /// Stamp a capture taken now, with `key` read just before it.
+    pub fn new(key: ActivityKey) -> Self {
+        Self {
+            key,
+            captured_at: unix_now(),
+        }
+    }
+
+    /// Whether the pane is unchanged since the capture, now that its key
+    /// is `key`.
+    pub fn is_current(&self, key: ActivityKey) -> bool {
+        // Activity in the same second as the capture may have happened
+        // after it, so only trust captures taken in a later second.
+        self.key == key && key.activity < self.captured_at
+    }
+}
+
+struct CachedCapture {
+    stamp: CaptureStamp,
+    lines: Vec<String>,
+}
+
+/// Pane captures from earlier refreshes, keyed by server and pane id, so
+/// panes that have not changed are not captured again.
+#[derive(Default)]
+pub struct CaptureCache {
+    entries: HashMap<(Server, String), CachedCapture>,
+    /// Agent panes found in unmarked sessions, by server and session name.
+    agent_panes: HashMap<(Server, String), String>,
+}
+
+impl CaptureCache {
+    /// Remember which pane each session's agent runs in, so listings
+    /// describe and capture it rather than the active pane. Returns whether
+    /// any of them is new.
+    pub fn remember_agent_panes(&mut self, panes: HashMap<(Server, String), String>) -> bool {
+        let new = panes
+            .iter()
+            .any(|(session, pane)| self.agent_panes.get(session) != Some(pane));
+        self.agent_panes = panes;
+        new
+    }
+
+    fn is_fresh(&self, id: &(Server, String), key: ActivityKey) -> bool {
+        self.entries
+            .get(id)
+            .is_some_and(|c| c.stamp.is_current(key))
+    }
+}
+
+/// One row of `list-panes -a` output.
+struct PaneRow {
+    session: Session,
+    active: bool,
+    agent: bool,
+    key: ActivityKey,
+}
+
+impl PaneRow {
+    /// Which of a session's panes stands for it: the agent's, then the
+    /// active one.
+    fn rank(&self) -> (bool, bool) {
+        (self.agent, self.active)
+    }
+}
+
+/// List all sessions using one `list-panes -a` query per server for
+/// metadata. With `include_default`, sessions on the user's default server
+/// are listed too when lattice has a dedicated socket. Each session's agent
+/// pane, else its active pane, is captured only when it is not in `cache`,
+/// its activity changed, or its session is in `visible`.
+pub fn list_sessions_cached(
+    cache: &mut CaptureCache,
+    visible: &[String],
+    include_default: bool,
+) -> Result<Vec<Session>> {
+    let mut sessions = snapshot(
+        &mut |args| run_tmux_on(Server::Lattice, args),
+        Server::Lattice,
+        cache,
+        visible,
+    )?;
+    if include_default && has_dedicated_socket() {
+        // The default server is someone else's; don't fail the listing over it.
+        sessions.extend(
+            snapshot(
+                &mut |args| run_tmux_on(Server::Default, args),
+                Server::Default,
+                cache,
+                visible,
+            )
+            .unwrap_or_default(),
+        );
+    }
+    Ok(sessions)
+}
+
+fn snapshot(
+    run: &mut dyn FnMut(&[&str]) -> Result<String>,
+    server: Server,
+    cache: &mut CaptureCache,
+    visible: &[String],
+) -> Result<Vec<Session>> {
+    let raw = match run(&["list-panes", "-a", "-F", PANE_FORMAT]) {
         Ok(out) => out,
         Err(err) if is_no_server_error(&err.to_string()) => return Ok(Vec::new()),
         Err(err) => return Err(err),
     };
 
-    let mut sessions = parse_session_list(&raw)?;
-
-    for session in &mut sessions {
-        if let Ok(info) = run_tmux(&[
-            "display-message",
-            "-p",
-            "-t",
-            &format!("{}:", session.name),
-            "#{pane_current_command}\t#{pane_current_path}\t#{pane_title}",
-        ]) {
-            let parts: Vec<&str> = info.trim_end().splitn(3, '\t').collect();
-            if let Some(cmd) = parts.first() {
-                let cmd = cmd.trim();
-                if !cmd.is_empty() {
-                    session.current_command = cmd.to_owned();
-                }
-            }
-            if let Some(path) = parts.get(1) {
-                let path = path.trim();
-                if !path.is_empty() {
-                    session.pane_current_path = path.to_owned();
-                }
-            }
-            if let Some(title) = parts.get(2) {
-                let title = title.trim();
-                if !title.is_empty() {
-                    session.pane_title = title.to_owned();
+    // Keep each session's agent pane, else its active pane, else its first
+    // one, and collect all of its panes.
+    let mut rows: Vec<PaneRow> = Vec::new();
+    for mut row in parse_pane_list(&raw)? {
+        row.session.server = server;
+        let remembered = cache.agent_panes.get(&(server, row.session.name.clone()));
+        row.agent |= remembered == Some(&row.session.pane_id);
+        match rows.iter_mut().find(|r| r.session.name == row.session.name) {
+            Some(existing) => {
+                let mut panes = std::mem::take(&mut existing.session.panes);
+                panes.append(&mut row.session.panes);
+                if row.rank() > existing.rank() {
+                    *existing = row;
                 }
+                panes.sort_by_key(|p| (p.window, p.index));
+                existing.session.panes = panes;
             }
+            None => rows.push(row),
         }
+    }
 
-        let target = format!("{}:0.0", session.name);
-        if let Ok(preview) = run_tmux(&[
-            "capture-pane",
-            "-p",
-            "-t",
-            &target,
-            "-S",
-            "-30",
-        ]) {
+    let mut sessions = Vec::with_capacity(rows.len());
+    for mut row in rows {
+        let id = (server, row.session.pane_id.clone());
+        let forced = visible.contains(&row.session.name);
+        if (forced || !cache.is_fresh(&id, row.key))
+            && let Ok(preview) = run(&["capture-pane", "-p", "-t", &id.1, "-S", "-30"])
+        {
             let lines: Vec<String> = preview
                 .lines()
                 .map(str::trim_end)
                 .map(ToOwned::to_owned)
                 .collect();
-            let last = last_non_empty_line(&lines).unwrap_or("(no output yet)");
-            session.last_line = last.to_owned();
-            session.preview = lines;
+            cache.entries.insert(
+                id.clone(),
+                CachedCapture {
+                    stamp: CaptureStamp::new(row.key),
+                    lines,
+                },
+            );
+        }
+
+        if let Some(cached) = cache.entries.get(&id) {
+            let last = last_non_empty_line(&cached.lines).unwrap_or("(no output yet)");
+            row.session.last_line = last.to_owned();
+            row.session.preview = cached.lines.clone();
         }
+        sessions.push(row.session);
     }
 
+    // Forget this server's panes that are gone
+    cache.entries.retain(|(s, pane_id), _| {
+        *s != server || sessions.iter().any(|session| session.pane_id == *pane_id)
+    });
+
     sessions.sort_by(|a, b| a.name.cmp(&b.name));
     Ok(sessions)
 }
 
-pub fn create_session(name: &str, working_dir: &str, shell_command: &str) -> Result<()> {
+/// Fields queried per pane by `pane_activity`.
+const ACTIVITY_FORMAT: &str =
+    "#{pane_id}\t#{window_activity}\t#{history_size}\t#{cursor_x}\t#{cursor_y}";
+
+/// Activity keys of every pane on `server` by pane id, from one
+/// `list-panes -a`: a cheaper way to tell whether panes changed than
+/// capturing them again.
+pub fn pane_activity(server: Server) -> Result<HashMap<String, ActivityKey>> {
+    let raw = run_tmux_on(server, &["list-panes", "-a", "-F", ACTIVITY_FORMAT])?;
+    Ok(parse_activity(&raw))
+}
+
+fn parse_activity(raw: &str) -> HashMap<String, ActivityKey> {
+    raw.lines()
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.split('\t').collect();
+            let [id, activity, history_size, x, y] = parts[..] else {
+                return None;
+            };
+            let key = ActivityKey {
+                activity: activity.parse().ok()?,
+                history_size: history_size.parse().ok()?,
+                cursor: (x.parse().ok()?, y.parse().ok()?),
+            };
+            Some((id.to_owned(), key))
+        })
+        .collect()
+}
+
+/// Names of all sessions on the server; empty when no server is running.
+pub fn session_names() -> Result<Vec<String>> {
+    match run_tmux(&["list-sessions", "-F", "#{session_name}"]) {
+        Ok(raw) => Ok(raw
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .map(ToOwned::to_owned)
+            .collect()),
+        Err(err) if is_no_server_error(&err.to_string()) => Ok(Vec::new()),
+        Err(err) => Err(err),
+    }
+}
+
+/// Start `shell_command` in a new session, returning the id of the pane it
+/// runs in.
+pub fn create_session(name: &str, working_dir: &str, shell_command: &str) -> Result<String> {
     // Step 1: Create session with the user's default shell so .bashrc/.zshrc are
     // sourced and PATH (nvm, pyenv, etc.) is fully configured.
-    let status = Command::new("tmux")
+    let output = command(Server::Lattice)
         .arg("new-session")
         .arg("-d")
+        .arg("-P")
+        .arg("-F")
+        .arg("#{pane_id}")
         .arg("-s")
         .arg(name)
         .arg("-c")
         .arg(working_dir)
-        .status()
+        .output()
         .with_context(|| format!("failed to run tmux new-session for {name}"))?;
 
-    if !status.success() {
-        return Err(anyhow!("tmux new-session exited with status {status}"));
+    if !output.status.success() {
+        return Err(anyhow!(
+            "tmux new-session exited with status {}",
+            output.status
+        ));
     }
+    // The agent runs in the first pane; mark it before the user can split
+    // the window. Best effort: unmarked, the agent's pane is still found
+    // from its processes.
+    let pane_id = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+    let _ = mark_agent_pane(Server::Lattice, &pane_id);
 
     // Step 2: Send the command as keystrokes into the session's shell.
     // This way the shell runs the command in its fully initialized environment,
@@ -111,7 +424,7 @@ pub fn create_session(name: &str, working_dir: &str, shell_command: &str) -> Res
     // NOTE: Append ":" to the session name so tmux treats dots as literal chars
     // rather than session.window.pane separators.
     let target = format!("{name}:");
-    let send_status = Command::new("tmux")
+    let send_status = command(Server::Lattice)
         .arg("send-keys")
         .arg("-t")
         .arg(&target)
@@ -124,14 +437,14 @@ pub fn create_session(name: &str, working_dir: &str, shell_command: &str) -> Res
         return Err(anyhow!("tmux send-keys exited with status {send_status}"));
     }
 
-    Ok(())
+    Ok(pane_id)
 }
 
 /// Split the active window of an existing session, adding a new shell pane
 /// in the given working directory.  The split is horizontal (side-by-side).
-pub fn split_window(session_name: &str, working_dir: &str) -> Result<()> {
+pub fn split_window(server: Server, session_name: &str, working_dir: &str) -> Result<()> {
     let target = format!("{session_name}:");
-    let status = Command::new("tmux")
+    let status = command(server)
         .arg("split-window")
         .arg("-h")
         .arg("-t")
@@ -148,118 +461,903 @@ pub fn split_window(session_name: &str, working_dir: &str) -> Result<()> {
     Ok(())
 }
 
-#[allow(dead_code)]
-pub fn send_keys(session_name: &str, text: &str) -> Result<()> {
-    let target = format!("{session_name}:");
-    let status = Command::new("tmux")
-        .arg("send-keys")
-        .arg("-t")
-        .arg(&target)
-        .arg(text)
-        .arg("Enter")
-        .status()
-        .with_context(|| format!("failed to send keys to session {session_name}"))?;
+/// Mark `pane_id` as the pane its session's agent runs in, so listings
+/// report that pane rather than whichever one is active.
+pub fn mark_agent_pane(server: Server, pane_id: &str) -> Result<()> {
+    run_tmux_on(
+        server,
+        &["set-option", "-p", "-t", pane_id, AGENT_PANE_OPTION, "1"],
+    )?;
+    Ok(())
+}
 
-    if status.success() {
-        Ok(())
-    } else {
-        Err(anyhow!("tmux send-keys exited with status {status}"))
-    }
+/// Make `pane_id` the active pane of its session, switching to its window.
+pub fn select_pane(server: Server, pane_id: &str) -> Result<()> {
+    run_tmux_on(server, &["select-window", "-t", pane_id])?;
+    run_tmux_on(server, &["select-pane", "-t", pane_id])?;
+    Ok(())
 }
 
-/// Send keystrokes to a session after a delay, in a fire-and-forget background
-/// process.  This gives TUI-based agents (e.g. Codex) time to boot before
-/// receiving input.
-pub fn send_keys_delayed(session_name: &str, text: &str, delay_secs: u32) -> Result<()> {
-    let target = format!("{session_name}:");
-    // Single-quote the text for the shell, escaping inner single quotes.
-    let escaped = text.replace('\'', "'\\''");
-    // Send the text literally with -l (no key-name lookup), pause briefly for
-    // the TUI to process, then send Enter as a separate keypress.
-    let script = format!(
-        "sleep {delay_secs} && tmux send-keys -t '{target}' -l '{escaped}' && sleep 1 && tmux send-keys -t '{target}' Enter"
+pub fn kill_pane(server: Server, pane_id: &str) -> Result<()> {
+    run_tmux_on(server, &["kill-pane", "-t", pane_id])?;
+    Ok(())
+}
+
+/// Deliver `text` to `target` (a pane id, or `session:` for a session's
+/// active pane) and submit it with Enter.
+///
+/// The text goes through a tmux paste buffer (`load-buffer` + `paste-buffer
+/// -p`) rather than raw `send-keys`, so multi-line input and shell
+/// metacharacters arrive intact and agents that enable bracketed paste see a
+/// single paste instead of one keypress per character.
+pub fn send_text(server: Server, target: &str, text: &str) -> Result<()> {
+    send_text_with(
+        &mut |args, input| run_tmux_input(server, args, input),
+        target,
+        text,
+        Duration::from_millis(200),
+    )
+}
+
+fn send_text_with(run: TmuxRun<'_>, target: &str, text: &str, settle: Duration) -> Result<()> {
+    paste_text_with(run, target, text).with_context(|| format!("failed to paste into {target}"))?;
+
+    // Give the agent a moment to process the paste before submitting, so the
+    // Enter is not swallowed as part of it.
+    thread::sleep(settle);
+    run(&["send-keys", "-t", target, "Enter"], None)
+        .with_context(|| format!("failed to send keys to {target}"))?;
+    Ok(())
+}
+
+/// Paste `text` into `target` as one bracketed paste, without submitting it.
+pub fn paste_text(server: Server, target: &str, text: &str) -> Result<()> {
+    paste_text_with(
+        &mut |args, input| run_tmux_input(server, args, input),
+        target,
+        text,
+    )
+}
+
+/// Runs one tmux command, feeding it the given stdin.
+type TmuxRun<'a> = &'a mut dyn FnMut(&[&str], Option<&str>) -> Result<String>;
+
+static PASTE_BUFFERS: AtomicU64 = AtomicU64::new(0);
+
+fn paste_text_with(run: TmuxRun<'_>, target: &str, text: &str) -> Result<()> {
+    // Pastes run concurrently (background deliveries, restores, the TUI), so
+    // each gets a buffer of its own.
+    let buffer = format!(
+        "lattice_send_{}_{}",
+        std::process::id(),
+        PASTE_BUFFERS.fetch_add(1, Ordering::Relaxed)
     );
-    Command::new("sh")
-        .arg("-c")
-        .arg(&script)
-        .stdin(std::process::Stdio::null())
-        .stdout(std::process::Stdio::null())
-        .stderr(std::process::Stdio::null())
-        .spawn()
-        .with_context(|| format!("failed to spawn delayed send-keys for {session_name}"))?;
+    run(&["load-buffer", "-b", &buffer, "-"], Some(text))?;
+
+    // -p wraps the paste in bracketed-paste markers when the pane asked for
+    // them; -d deletes the buffer afterwards.
+    if let Err(err) = run(
+        &["paste-buffer", "-p", "-d", "-b", &buffer, "-t", target],
+        None,
+    ) {
+        let _ = run(&["delete-buffer", "-b", &buffer], None);
+        return Err(err);
+    }
     Ok(())
 }
 
-/// Create a temporary tmux session with side-by-side panes, each nested-
-/// attaching to one of the given target sessions.  The caller should
-/// `attach_session(name)` immediately after to enter the split view.
-pub fn create_split_session(name: &str, targets: &[String]) -> Result<()> {
+/// Replay one keypress in `target`: either literal text (`send-keys -l`) or
+/// a tmux key name like `C-c` or `Up`.
+pub fn send_key(server: Server, target: &str, key: &str, literal: bool) -> Result<()> {
+    let mut args = vec!["send-keys", "-t", target];
+    if literal {
+        args.push("-l");
+    }
+    // tmux reads a trailing `;` as a command separator unless escaped
+    let escaped;
+    let key = match key.strip_suffix(';') {
+        Some(head) => {
+            escaped = format!("{head}\\;");
+            escaped.as_str()
+        }
+        None => key,
+    };
+    // `--` so keys like `-` are not taken for flags
+    args.extend(["--", key]);
+    run_tmux_on(server, &args)?;
+    Ok(())
+}
+
+/// Capture a pane with its colors and attributes as escape sequences
+/// (`capture-pane -e`): the visible screen plus up to `history` lines of
+/// scrollback above it.
+pub fn capture_styled(server: Server, pane_id: &str, history: u16) -> Result<Vec<String>> {
+    let start = format!("-{history}");
+    let raw = run_tmux_on(
+        server,
+        &["capture-pane", "-p", "-e", "-t", pane_id, "-S", &start],
+    )?;
+    Ok(raw
+        .lines()
+        .map(str::trim_end)
+        .map(ToOwned::to_owned)
+        .collect())
+}
+
+/// Capture the visible contents of a pane.
+pub fn capture_pane(pane_id: &str) -> Result<Vec<String>> {
+    let raw = run_tmux(&["capture-pane", "-p", "-t", pane_id])?;
+    Ok(raw
+        .lines()
+        .map(str::trim_end)
+        .map(ToOwned::to_owned)
+        .collect())
+}
+
+/// Arrangements offered for a tmux split; the names are tmux's own layout
+/// names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SplitLayout {
+    #[default]
+    EvenHorizontal,
+    EvenVertical,
+    Tiled,
+    MainVertical,
+}
+
+impl SplitLayout {
+    pub fn name(self) -> &'static str {
+        match self {
+            SplitLayout::EvenHorizontal => "even-horizontal",
+            SplitLayout::EvenVertical => "even-vertical",
+            SplitLayout::Tiled => "tiled",
+            SplitLayout::MainVertical => "main-vertical",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        [
+            SplitLayout::EvenHorizontal,
+            SplitLayout::EvenVertical,
+            SplitLayout::Tiled,
+            SplitLayout::MainVertical,
+        ]
+        .into_iter()
+        .find(|l| l.name() == value.trim())
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            SplitLayout::EvenHorizontal => SplitLayout::EvenVertical,
+            SplitLayout::EvenVertical => SplitLayout::Tiled,
+            SplitLayout::Tiled => SplitLayout::MainVertical,
+            SplitLayout::MainVertical => SplitLayout::EvenHorizontal,
+        }
+    }
+}
+
+/// An agent pane shown in a tmux split, and the placeholder pane holding
+/// its place in the agent's own session meanwhile.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LentPane {
+    pub session: String,
+    pub pane_id: String,
+    pub placeholder_id: String,
+}
+
+/// A temporary session assembled from real agent panes with `swap-pane`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TmuxSplit {
+    pub name: String,
+    pub server: Server,
+    pub panes: Vec<LentPane>,
+}
+
+/// What a placeholder pane shows while its agent is away.
+const PLACEHOLDER_COMMAND: &str = "echo 'This agent is shown in a lattice split; it comes back when the split is closed.'; exec tail -f /dev/null";
+
+/// Create session `name` with one placeholder pane per target, arranged in
+/// `layout`. Nothing is moved yet: persist the returned split, then call
+/// `lend_panes` to swap the agents in. Panes can only be swapped within a
+/// server, so every target must live on `server`.
+pub fn create_split_session(
+    server: Server,
+    name: &str,
+    targets: &[(String, String)],
+    layout: SplitLayout,
+) -> Result<TmuxSplit> {
     if targets.is_empty() {
         return Err(anyhow!("no target sessions for split"));
     }
 
-    // Create the session; the first pane will attach to the first target.
-    let attach_cmd = format!("TMUX='' tmux attach-session -t '{}'", targets[0]);
-    let status = Command::new("tmux")
-        .arg("new-session")
-        .arg("-d")
-        .arg("-s")
-        .arg(name)
-        .status()
-        .with_context(|| format!("failed to create split session {name}"))?;
-    if !status.success() {
-        return Err(anyhow!("tmux new-session for split exited with {status}"));
+    let mut split = TmuxSplit {
+        name: name.to_owned(),
+        server,
+        panes: Vec::with_capacity(targets.len()),
+    };
+    let window = format!("{name}:");
+    for (i, (session, pane_id)) in targets.iter().enumerate() {
+        let created = if i == 0 {
+            run_tmux_on(
+                server,
+                &[
+                    "new-session",
+                    "-d",
+                    "-P",
+                    "-F",
+                    "#{pane_id}",
+                    "-s",
+                    name,
+                    PLACEHOLDER_COMMAND,
+                ],
+            )
+        } else {
+            // Re-applying the layout after each split keeps room for the
+            // next one.
+            run_tmux_on(
+                server,
+                &[
+                    "split-window",
+                    "-d",
+                    "-P",
+                    "-F",
+                    "#{pane_id}",
+                    "-t",
+                    &window,
+                    PLACEHOLDER_COMMAND,
+                ],
+            )
+            .and_then(|id| {
+                run_tmux_on(server, &["select-layout", "-t", &window, layout.name()]).map(|_| id)
+            })
+        };
+        let placeholder_id = match created {
+            Ok(id) => id.trim().to_owned(),
+            Err(err) => {
+                let _ = kill_session(server, name);
+                return Err(err.context(format!("failed to create split session {name}")));
+            }
+        };
+        split.panes.push(LentPane {
+            session: session.clone(),
+            pane_id: pane_id.clone(),
+            placeholder_id,
+        });
     }
+    let _ = run_tmux_on(server, &["select-layout", "-t", &window, layout.name()]);
+    Ok(split)
+}
 
-    // Send the nested-attach command into the first pane.
-    let target0 = format!("{name}:");
-    let _ = Command::new("tmux")
-        .arg("send-keys")
-        .arg("-t")
-        .arg(&target0)
-        .arg(&attach_cmd)
-        .arg("Enter")
-        .status();
-
-    // For each additional target, split and nested-attach.
-    for t in &targets[1..] {
-        let _ = Command::new("tmux")
-            .arg("split-window")
-            .arg("-h")
-            .arg("-t")
-            .arg(name)
-            .status();
-
-        let attach = format!("TMUX='' tmux attach-session -t '{}'", t);
-        let _ = Command::new("tmux")
-            .arg("send-keys")
-            .arg("-t")
-            .arg(format!("{name}:"))
-            .arg(&attach)
-            .arg("Enter")
-            .status();
-    }
-
-    // Even-horizontal layout for a clean split.
-    let _ = Command::new("tmux")
-        .arg("select-layout")
-        .arg("-t")
-        .arg(name)
-        .arg("even-horizontal")
-        .status();
+/// Swap each agent pane with its placeholder, moving the agents into the
+/// split. On failure the panes already moved are put back.
+pub fn lend_panes(split: &TmuxSplit) -> Result<()> {
+    for pane in &split.panes {
+        // -d keeps each window's active pane where it was.
+        let swapped = run_tmux_on(
+            split.server,
+            &[
+                "swap-pane",
+                "-d",
+                "-s",
+                &pane.pane_id,
+                "-t",
+                &pane.placeholder_id,
+            ],
+        );
+        if let Err(err) = swapped {
+            let _ = restore_split(split);
+            return Err(err.context(format!("failed to move {} into the split", pane.session)));
+        }
+    }
+    let first = split.panes[0].pane_id.as_str();
+    let _ = run_tmux_on(split.server, &["select-pane", "-t", first]);
+    Ok(())
+}
 
-    // Focus the first pane.
-    let _ = Command::new("tmux")
-        .arg("select-pane")
-        .arg("-t")
-        .arg(format!("{name}:.0"))
-        .status();
+/// Put every lent pane back in its own session and kill the split session.
+/// Safe to call at any point, including on a split that was never (or only
+/// partly) lent: panes are looked up by id wherever they are now.
+pub fn restore_split(split: &TmuxSplit) -> Result<()> {
+    let mut failed = Vec::new();
+    for pane in &split.panes {
+        let agent_at = pane_session(split.server, &pane.pane_id);
+        let placeholder_at = pane_session(split.server, &pane.placeholder_id);
+        let result = match (agent_at.as_deref(), placeholder_at.as_deref()) {
+            (Some(at), Some(_)) if at == split.name => run_tmux_on(
+                split.server,
+                &[
+                    "swap-pane",
+                    "-d",
+                    "-s",
+                    &pane.pane_id,
+                    "-t",
+                    &pane.placeholder_id,
+                ],
+            ),
+            // The agent exited while in the split; its session goes with it,
+            // as it would have without the split.
+            (None, Some(at)) if at != split.name => {
+                run_tmux_on(split.server, &["kill-pane", "-t", &pane.placeholder_id])
+            }
+            _ => Ok(String::new()),
+        };
+        if result.is_err() {
+            failed.push(pane.session.as_str());
+        }
+    }
+    // `=` so only this exact session is matched; it may already be gone.
+    let _ = run_tmux_on(
+        split.server,
+        &["kill-session", "-t", &format!("={}", split.name)],
+    );
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "could not return {} from the split",
+            failed.join(", ")
+        ))
+    }
+}
+
+/// Session a pane currently belongs to, or `None` when it no longer exists.
+fn pane_session(server: Server, pane_id: &str) -> Option<String> {
+    let raw = run_tmux_on(
+        server,
+        &["display-message", "-p", "-t", pane_id, "#{session_name}"],
+    )
+    .ok()?;
+    Some(raw.trim().to_owned()).filter(|s| !s.is_empty())
+}
+
+/// A key lattice binds in tmux while it runs: `F12` binds it in the root
+/// table, `prefix+L` after the prefix key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HotKey {
+    pub prefix: bool,
+    pub key: String,
+}
+
+impl HotKey {
+    /// Returns from an agent session to lattice.
+    pub const RETURN_DEFAULT: &'static str = "F12";
+    /// Opens the agent picker popup.
+    pub const PICK_DEFAULT: &'static str = "prefix+a";
+
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        let (prefix, key) = match value
+            .strip_prefix("prefix+")
+            .or_else(|| value.strip_prefix("prefix "))
+        {
+            Some(key) => (true, key.trim()),
+            None => (false, value),
+        };
+        if key.is_empty() || key.contains(char::is_whitespace) {
+            return None;
+        }
+        Some(Self {
+            prefix,
+            key: key.to_owned(),
+        })
+    }
+
+    fn table(&self) -> &'static str {
+        if self.prefix { "prefix" } else { "root" }
+    }
+
+    /// `bind-key` running `then` in sessions lattice sent a client to
+    /// (`if` is a format that is true there) and running the key's
+    /// `original` binding everywhere else. An unbound root-table key is
+    /// passed through to the pane.
+    fn bind_args(
+        &self,
+        condition: &str,
+        then: String,
+        otherwise: Option<String>,
+        original: Option<&Binding>,
+    ) -> Vec<String> {
+        let mut args = vec!["bind-key".to_owned()];
+        if original.is_some_and(|original| original.repeat) {
+            args.push("-r".to_owned());
+        }
+        args.extend([
+            "-T".to_owned(),
+            self.table().to_owned(),
+            self.key.clone(),
+            "if-shell".to_owned(),
+            "-F".to_owned(),
+            condition.to_owned(),
+            then,
+        ]);
+        let pass = match original {
+            Some(original) => Some(original.command.clone()),
+            None => (!self.prefix).then(|| format!("send-keys {}", self.key)),
+        };
+        match (otherwise, pass) {
+            (Some(otherwise), Some(pass)) => args.push(format!("{otherwise} {}", quote(&pass))),
+            (Some(otherwise), None) => args.push(otherwise),
+            (None, Some(pass)) => args.push(pass),
+            (None, None) => {}
+        }
+        args
+    }
+
+    /// Back to lattice: switch to the session named by `@lattice_return`
+    /// (tmux does not expand formats in a plain `switch-client -t`, hence
+    /// `run-shell -C`), or detach a client lattice attached. `q:` escapes
+    /// the name so quotes or `;` in it can't break the command it goes in.
+    fn return_args(&self, original: Option<&Binding>) -> Vec<String> {
+        self.bind_args(
+            "#{@lattice_return}",
+            "run-shell -C \"switch-client -t =#{q:@lattice_return}\"".to_owned(),
+            Some("if-shell -F '#{@lattice_detach}' detach-client".to_owned()),
+            original,
+        )
+    }
+
+    /// Open `command` (a shell command line) in a popup.
+    fn pick_args(&self, command: &str, original: Option<&Binding>) -> Vec<String> {
+        self.bind_args(
+            "#{||:#{@lattice_return},#{@lattice_detach}}",
+            format!("display-popup -E -w 70% -h 60% {}", quote(command)),
+            None,
+            original,
+        )
+    }
+}
 
+/// A key's binding as `list-keys` prints it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Binding {
+    /// Bound with `-r`, so it can repeat without the prefix.
+    repeat: bool,
+    /// Command line, in tmux's own syntax.
+    command: String,
+}
+
+impl Binding {
+    /// Parse `bind-key [-r] -T <table> <key> <command>`.
+    fn parse(line: &str) -> Option<Self> {
+        let rest = line.trim().strip_prefix("bind-key")?.trim_start();
+        let (repeat, rest) = match rest.strip_prefix("-r ") {
+            Some(rest) => (true, rest.trim_start()),
+            None => (false, rest),
+        };
+        let rest = rest.strip_prefix("-T ")?.trim_start();
+        // table, then key: neither contains unescaped whitespace
+        let (_table, rest) = rest.split_once(char::is_whitespace)?;
+        let (_key, command) = rest.trim_start().split_once(char::is_whitespace)?;
+        let command = command.trim();
+        (!command.is_empty()).then(|| Self {
+            repeat,
+            command: command.to_owned(),
+        })
+    }
+
+    /// One of lattice's own hotkey bindings.
+    fn is_lattice(&self) -> bool {
+        self.command.contains("@lattice_")
+    }
+}
+
+/// Double-quote `s` for a tmux command line.
+fn quote(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        if matches!(c, '"' | '\\' | '$') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// A key lattice bound over, with what it was bound to before.
+#[derive(Debug, Clone)]
+pub struct SavedKey {
+    server: Server,
+    key: HotKey,
+    original: Option<Binding>,
+}
+
+impl std::fmt::Display for HotKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.prefix {
+            write!(f, "prefix+")?;
+        }
+        write!(f, "{}", self.key)
+    }
+}
+
+/// Bind `back` to return to lattice and `pick` to open `pick_command` in a
+/// popup. Both only act in sessions carrying a `Mark` and run the key's
+/// existing binding elsewhere; `restore_hotkey` puts that binding back.
+/// A key another lattice already holds is left to it.
+pub fn bind_hotkeys(
+    server: Server,
+    back: &HotKey,
+    pick: &HotKey,
+    pick_command: &str,
+) -> Result<Vec<SavedKey>> {
+    let mut saved = Vec::new();
+    for (key, is_back) in [(back, true), (pick, false)] {
+        let original = key_binding(server, key);
+        if original.as_ref().is_some_and(Binding::is_lattice) {
+            continue;
+        }
+        let args = if is_back {
+            key.return_args(original.as_ref())
+        } else {
+            key.pick_args(pick_command, original.as_ref())
+        };
+        run_tmux_on(server, &args.iter().map(String::as_str).collect::<Vec<_>>())?;
+        saved.push(SavedKey {
+            server,
+            key: key.clone(),
+            original,
+        });
+    }
+    Ok(saved)
+}
+
+/// Current binding of `key`, `None` when it is unbound.
+fn key_binding(server: Server, key: &HotKey) -> Option<Binding> {
+    // fails with "unknown key" when there is none
+    let raw = run_tmux_on(server, &["list-keys", "-T", key.table(), &key.key]).ok()?;
+    raw.lines().next().and_then(Binding::parse)
+}
+
+/// Put back what `saved` was bound to before lattice bound it.
+pub fn restore_hotkey(saved: &SavedKey) {
+    let key = &saved.key;
+    let _ = match &saved.original {
+        Some(original) => {
+            let mut args = vec!["bind-key"];
+            if original.repeat {
+                args.push("-r");
+            }
+            args.extend(["-T", key.table(), &key.key, &original.command]);
+            run_tmux_on(saved.server, &args)
+        }
+        None => run_tmux_on(saved.server, &["unbind-key", "-T", key.table(), &key.key]),
+    };
+}
+
+/// How a client got from lattice to a session, recorded on the session so
+/// the hotkeys know how to get back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mark<'a> {
+    /// lattice's own client was switched over from session `home`.
+    Return(&'a str),
+    /// lattice attached a new client, which should detach.
+    Detach,
+}
+
+pub fn mark_session(server: Server, session: &str, mark: Mark<'_>) -> Result<()> {
+    let pid = std::process::id().to_string();
+    let (option, value) = match mark {
+        Mark::Return(home) => ("@lattice_return", home),
+        Mark::Detach => ("@lattice_detach", pid.as_str()),
+    };
+    // set-option takes a pane target, hence the trailing `:`
+    run_tmux_on(
+        server,
+        &["set-option", "-t", &format!("={session}:"), option, value],
+    )?;
     Ok(())
 }
 
-pub fn attach_session(name: &str) -> Result<()> {
-    let status = Command::new("tmux")
+/// Remove the marks this lattice process left anywhere on `server`:
+/// detach marks with its pid, and return marks pointing at `home`.
+pub fn clear_marks(server: Server, home: Option<&str>) {
+    let format = "#{session_name}\t#{@lattice_return}\t#{@lattice_detach}";
+    let Ok(raw) = run_tmux_on(server, &["list-sessions", "-F", format]) else {
+        return;
+    };
+    let pid = std::process::id().to_string();
+    for line in raw.lines() {
+        let mut parts = line.split('\t');
+        let (Some(session), Some(back), Some(detach)) = (parts.next(), parts.next(), parts.next())
+        else {
+            continue;
+        };
+        let target = format!("={session}:");
+        if home.is_some_and(|home| home == back) {
+            let _ = run_tmux_on(
+                server,
+                &["set-option", "-u", "-t", &target, "@lattice_return"],
+            );
+        }
+        if detach == pid {
+            let _ = run_tmux_on(
+                server,
+                &["set-option", "-u", "-t", &target, "@lattice_detach"],
+            );
+        }
+    }
+}
+
+/// Copy `from`'s marks to `to`, so the hotkeys keep working after the
+/// picker moves a client between agents.
+pub fn carry_marks(server: Server, from: &str, to: &str) -> Result<()> {
+    let raw = run_tmux_on(
+        server,
+        &[
+            "display-message",
+            "-p",
+            "-t",
+            &format!("={from}:"),
+            "#{@lattice_return}\t#{@lattice_detach}",
+        ],
+    )?;
+    let (back, detach) = raw
+        .trim_end_matches('\n')
+        .split_once('\t')
+        .unwrap_or_default();
+    let target = format!("={to}:");
+    for (option, value) in [("@lattice_return", back), ("@lattice_detach", detach)] {
+        if !value.is_empty() {
+            run_tmux_on(server, &["set-option", "-t", &target, option, value])?;
+        }
+    }
+    Ok(())
+}
+
+/// Flag (or unflag) a session as waiting for the user. Stored on the session
+/// so other processes, like the picker popup, can see it.
+pub fn set_attention(server: Server, session: &str, on: bool) {
+    let target = format!("={session}:");
+    let args: &[&str] = if on {
+        &["set-option", "-t", &target, "@lattice_attention", "1"]
+    } else {
+        &["set-option", "-u", "-t", &target, "@lattice_attention"]
+    };
+    let _ = run_tmux_on(server, args);
+}
+
+/// Store a session's notification rule; an empty `rule` removes it.
+pub fn set_notify_rule(server: Server, session: &str, rule: &str) -> Result<()> {
+    let target = format!("={session}:");
+    let args: &[&str] = if rule.is_empty() {
+        &["set-option", "-u", "-t", &target, "@lattice_notify"]
+    } else {
+        &["set-option", "-t", &target, "@lattice_notify", rule]
+    };
+    run_tmux_on(server, args).map(|_| ())
+}
+
+/// Whether a terminal (not a `client-N` control client) is attached.
+const TERMINAL_ATTACHED: &str = "#{m:*/dev/*,#{session_attached_list}}";
+const ALERTS_OFF: &str = "set-option -u activity-action ; set-option -u silence-action";
+const ALERTS_ON: &str = "set-option activity-action any ; set-option silence-action any";
+
+/// Array index of lattice's hooks, so hooks the session already has at
+/// other indices keep running and only lattice's are removed again.
+const HOOK_INDEX: u32 = 100;
+
+/// Have tmux report `session`'s alerts by running `hook_command <event>
+/// <session> <pane>`: silence once the agent's window has had no output for
+/// `silence_seconds`, activity after that, bells, and a dead pane.
+pub fn watch_session(
+    server: Server,
+    session: &str,
+    pane_id: &str,
+    silence_seconds: u64,
+    hook_command: &str,
+) -> Result<()> {
+    run_tmux_sequence(
+        server,
+        &watch_commands(session, pane_id, silence_seconds, hook_command),
+    )
+}
+
+fn watch_commands(
+    session: &str,
+    pane_id: &str,
+    silence_seconds: u64,
+    hook_command: &str,
+) -> Vec<Vec<String>> {
+    let target = format!("={session}:");
+    let mut commands = vec![
+        vec![
+            "set-option".to_owned(),
+            "-w".to_owned(),
+            "-t".to_owned(),
+            pane_id.to_owned(),
+            "monitor-activity".to_owned(),
+            "on".to_owned(),
+        ],
+        vec![
+            "set-option".to_owned(),
+            "-w".to_owned(),
+            "-t".to_owned(),
+            pane_id.to_owned(),
+            "monitor-silence".to_owned(),
+            silence_seconds.to_string(),
+        ],
+    ];
+    for kind in AlertKind::ALL {
+        let run = format!(
+            "run-shell -b {}",
+            quote(&format!(
+                "{hook_command} {} #{{q:session_name}} #{{pane_id}}",
+                kind.name()
+            ))
+        );
+        commands.push(vec![
+            "set-hook".to_owned(),
+            "-t".to_owned(),
+            target.clone(),
+            format!("{}[{HOOK_INDEX}]", kind.hook()),
+            run,
+        ]);
+    }
+    // Silence and activity alerts only fire for the window someone looks
+    // at when their action is `any`, which also makes tmux beep at that
+    // someone. Turn them off the moment a terminal attaches.
+    let sync = format!("if-shell -F '{TERMINAL_ATTACHED}' '{ALERTS_OFF}' '{ALERTS_ON}'");
+    for hook in SYNC_HOOKS {
+        commands.push(vec![
+            "set-hook".to_owned(),
+            "-t".to_owned(),
+            target.clone(),
+            format!("{hook}[{HOOK_INDEX}]"),
+            sync.clone(),
+        ]);
+    }
+    commands.push(vec![
+        "set-option".to_owned(),
+        "-t".to_owned(),
+        target,
+        "@lattice_watch".to_owned(),
+        silence_seconds.to_string(),
+    ]);
+    commands
+}
+
+/// Hooks that turn alerts off while a terminal is attached.
+const SYNC_HOOKS: [&str; 2] = ["client-attached", "client-session-changed"];
+
+/// Undo `watch_session`: remove lattice's hooks and the alert options it
+/// set, leaving the session's other hooks alone.
+pub fn unwatch_session(server: Server, session: &str, pane_id: &str) -> Result<()> {
+    run_tmux_sequence(server, &unwatch_commands(session, pane_id))
+}
+
+fn unwatch_commands(session: &str, pane_id: &str) -> Vec<Vec<String>> {
+    let target = format!("={session}:");
+    let hooks = AlertKind::ALL
+        .into_iter()
+        .map(AlertKind::hook)
+        .chain(SYNC_HOOKS)
+        .map(|hook| {
+            [
+                "set-hook",
+                "-u",
+                "-t",
+                &target,
+                &format!("{hook}[{HOOK_INDEX}]"),
+            ]
+            .map(ToOwned::to_owned)
+            .to_vec()
+        });
+    let window_options = ["monitor-activity", "monitor-silence"].map(|option| {
+        ["set-option", "-w", "-u", "-t", pane_id, option]
+            .map(ToOwned::to_owned)
+            .to_vec()
+    });
+    let session_options = ["activity-action", "silence-action", "@lattice_watch"].map(|option| {
+        ["set-option", "-u", "-t", &target, option]
+            .map(ToOwned::to_owned)
+            .to_vec()
+    });
+    hooks.chain(window_options).chain(session_options).collect()
+}
+
+/// Turn silence and activity alerts back on in those of `sessions` no
+/// terminal is attached to; attaching turns them off.
+pub fn sync_alerts(server: Server, sessions: &[&str]) -> Result<()> {
+    let commands: Vec<Vec<String>> = sessions
+        .iter()
+        .map(|session| {
+            [
+                "if-shell",
+                "-F",
+                "-t",
+                &format!("={session}:"),
+                TERMINAL_ATTACHED,
+                ALERTS_OFF,
+                ALERTS_ON,
+            ]
+            .map(ToOwned::to_owned)
+            .to_vec()
+        })
+        .collect();
+    run_tmux_sequence(server, &commands)
+}
+
+/// Clear `session`'s raised alerts. tmux reports silence and activity once,
+/// then waits for the alert to be cleared before reporting it again.
+pub fn rearm_alerts(server: Server, session: &str) -> Result<()> {
+    // -C only clears the alert flags of the session's windows.
+    let target = format!("={session}");
+    run_tmux_on(server, &["kill-session", "-C", "-t", &target]).map(|_| ())
+}
+
+/// Sessions flagged with `set_attention`.
+pub fn attention_sessions(server: Server) -> Vec<String> {
+    let format = "#{session_name}\t#{@lattice_attention}";
+    run_tmux_on(server, &["list-sessions", "-F", format])
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| line.strip_suffix("\t1"))
+        .map(ToOwned::to_owned)
+        .collect()
+}
+
+/// Whether this process runs inside a tmux client on `server` (in one of
+/// its panes or popups), so that client can be switched to another session
+/// instead of nesting a new one.
+pub fn is_current_server(server: Server) -> bool {
+    let ours = match std::env::var("TMUX") {
+        Ok(tmux) if !tmux.is_empty() => tmux.split(',').next().unwrap_or_default().to_owned(),
+        _ => return false,
+    };
+    run_tmux_on(server, &["display-message", "-p", "#{socket_path}"])
+        .is_ok_and(|path| path.trim() == ours)
+}
+
+/// Session this process runs in when inside tmux: the one holding its pane,
+/// or for a popup the one it was opened over.
+pub fn current_session() -> Option<String> {
+    let pane = std::env::var("TMUX_PANE").ok();
+    let mut args = vec!["display-message", "-p"];
+    if let Some(pane) = pane.as_deref() {
+        args.extend(["-t", pane]);
+    }
+    args.push("#{session_name}");
+    // No socket flags: plain tmux talks to the server in $TMUX.
+    let raw = run_tmux_on(Server::Default, &args).ok()?;
+    Some(raw.trim().to_owned()).filter(|s| !s.is_empty())
+}
+
+/// Switch the client this process runs in over to `session`.
+pub fn switch_client(server: Server, session: &str) -> Result<()> {
+    run_tmux_on(server, &["switch-client", "-t", &format!("={session}")])?;
+    Ok(())
+}
+
+/// Whether any client is attached to `session`; false once it is gone.
+pub fn has_clients(server: Server, session: &str) -> bool {
+    run_tmux_on(
+        server,
+        &[
+            "list-clients",
+            "-t",
+            &format!("={session}"),
+            "-F",
+            "#{client_name}",
+        ],
+    )
+    .is_ok_and(|raw| !raw.trim().is_empty())
+}
+
+/// Show `message` in the status line of every client attached to `server`.
+pub fn display_message(server: Server, message: &str) -> Result<()> {
+    // The message is a format; keep `#` literal.
+    let message = message.replace('#', "##");
+    let clients = run_tmux_on(server, &["list-clients", "-F", "#{client_name}"])?;
+    for client in clients.lines().filter(|c| !c.is_empty()) {
+        run_tmux_on(server, &["display-message", "-c", client, &message])?;
+    }
+    Ok(())
+}
+
+pub fn attach_session(server: Server, name: &str) -> Result<()> {
+    let status = command(server)
         .arg("attach-session")
         .arg("-t")
         .arg(name)
@@ -273,8 +1371,8 @@ pub fn attach_session(name: &str) -> Result<()> {
     }
 }
 
-pub fn kill_session(name: &str) -> Result<()> {
-    let status = Command::new("tmux")
+pub fn kill_session(server: Server, name: &str) -> Result<()> {
+    let status = command(server)
         .arg("kill-session")
         .arg("-t")
         .arg(name)
@@ -288,73 +1386,129 @@ pub fn kill_session(name: &str) -> Result<()> {
     }
 }
 
-fn parse_session_list(raw: &str) -> Result<Vec<Session>> {
-    if raw.trim().is_empty() {
-        return Ok(Vec::new());
-    }
+/// Whether a `#{session_attached_list}` value has a real terminal attached.
+/// Clients without a tty (such as lattice's own control-mode client) are
+/// named `client-<pid>` and do not count.
+fn has_terminal_client(clients: &str) -> bool {
+    clients
+        .split(',')
+        .any(|c| !c.is_empty() && !c.starts_with("client-"))
+}
 
-    let mut sessions = Vec::new();
-    for line in raw.lines() {
-        let parts: Vec<&str> = line.split('\t').collect();
-        if parts.len() != 4 {
+fn parse_pane_list(raw: &str) -> Result<Vec<PaneRow>> {
+    let mut rows = Vec::new();
+    for line in raw.lines().filter(|l| !l.trim().is_empty()) {
+        let parts: Vec<&str> = line.splitn(PANE_FIELDS, '\t').collect();
+        if parts.len() != PANE_FIELDS {
             return Err(anyhow!("unexpected tmux output line: {line}"));
         }
 
         let windows = parts[2]
             .parse::<u32>()
             .with_context(|| format!("invalid window count in line: {line}"))?;
+        let non_empty = |s: &str, default: &str| {
+            let s = s.trim();
+            if s.is_empty() {
+                default.to_owned()
+            } else {
+                s.to_owned()
+            }
+        };
+        let pane = Pane {
+            id: parts[7].to_owned(),
+            window: parts[15].parse().unwrap_or(0),
+            index: parts[16].parse().unwrap_or(0),
+            pid: parts[14].parse().unwrap_or(0),
+            current_command: non_empty(parts[8], "unknown"),
+            current_path: non_empty(parts[9], ""),
+            active: parts[5] == "1" && parts[6] == "1",
+            agent: parts[17] == "1",
+        };
+        let (active, agent) = (pane.active, pane.agent);
 
-        sessions.push(Session {
-            name: parts[0].to_owned(),
-            attached: parts[1] == "1",
-            windows,
-            created: parts[3].to_owned(),
-            current_command: "unknown".to_owned(),
-            pane_current_path: String::new(),
-            pane_title: String::new(),
-            preview: Vec::new(),
-            last_line: "(no output yet)".to_owned(),
+        rows.push(PaneRow {
+            session: Session {
+                name: parts[0].to_owned(),
+                server: Server::Lattice,
+                attached: has_terminal_client(parts[1]),
+                windows,
+                created: parts[3].to_owned(),
+                created_unix: parts[4].parse().unwrap_or(0),
+                current_command: pane.current_command.clone(),
+                pane_current_path: pane.current_path.clone(),
+                pane_title: non_empty(parts[20], ""),
+                notify_rule: parts[18].to_owned(),
+                alert_watch: parts[19].parse().ok(),
+                pane_id: pane.id.clone(),
+                pane_pid: pane.pid,
+                activity_unix: parts[10].parse().unwrap_or(0),
+                preview: Vec::new(),
+                last_line: "(no output yet)".to_owned(),
+                panes: vec![pane],
+            },
+            active,
+            agent,
+            key: ActivityKey {
+                activity: parts[10].parse().unwrap_or(0),
+                history_size: parts[11].parse().unwrap_or(0),
+                cursor: (
+                    parts[12].parse().unwrap_or(0),
+                    parts[13].parse().unwrap_or(0),
+                ),
+            },
         });
     }
-
-    Ok(sessions)
+    Ok(rows)
 }
 
-/// Lightweight polling for background activity detection.
-/// Returns `(session_name, preview_lines)` for all tmux sessions whose names
-/// start with "lattice_".
-pub fn poll_session_previews() -> Vec<(String, Vec<String>)> {
-    let Ok(raw) = run_tmux(&["list-sessions", "-F", "#{session_name}"]) else {
-        return Vec::new();
-    };
-
-    let mut out = Vec::new();
-    for name in raw.lines() {
-        let name = name.trim();
-        if name.is_empty() || !name.starts_with("lattice_") {
-            continue;
-        }
-        if let Ok(preview) = run_tmux(&[
-            "capture-pane",
-            "-p",
-            "-t",
-            &format!("{name}:0.0"),
-            "-S",
-            "-30",
-        ]) {
-            let lines: Vec<String> = preview
-                .lines()
-                .map(str::trim_end)
-                .map(ToOwned::to_owned)
-                .collect();
-            out.push((name.to_owned(), lines));
+/// Run several commands in one tmux invocation.
+fn run_tmux_sequence(server: Server, commands: &[Vec<String>]) -> Result<()> {
+    if commands.is_empty() {
+        return Ok(());
+    }
+    let mut args: Vec<&str> = Vec::new();
+    for command in commands {
+        if !args.is_empty() {
+            args.push(";");
         }
+        args.extend(command.iter().map(String::as_str));
     }
-    out
+    run_tmux_on(server, &args).map(|_| ())
 }
 
 fn run_tmux(args: &[&str]) -> Result<String> {
-    let output = Command::new("tmux")
+    run_tmux_on(Server::Lattice, args)
+}
+
+/// `run_tmux_on`, writing `input` (if any) to the command's stdin.
+fn run_tmux_input(server: Server, args: &[&str], input: Option<&str>) -> Result<String> {
+    let Some(input) = input else {
+        return run_tmux_on(server, args);
+    };
+    let mut child = command(server)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("failed to execute tmux {}", args.join(" ")))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(input.as_bytes())
+            .with_context(|| format!("failed to write to tmux {}", args[0]))?;
+    }
+    let output = child
+        .wait_with_output()
+        .with_context(|| format!("failed to execute tmux {}", args.join(" ")))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow!("tmux {} failed: {}", args[0], stderr.trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+fn run_tmux_on(server: Server, args: &[&str]) -> Result<String> {
+    let output = command(server)
         .args(args)
         .output()
         .with_context(|| format!("failed to execute tmux {}", args.join(" ")))?;
@@ -375,6 +1529,13 @@ fn run_tmux(args: &[&str]) -> Result<String> {
     }
 }
 
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
 fn is_no_server_error(msg: &str) -> bool {
     let lower = msg.to_ascii_lowercase();
     lower.contains("failed to connect to server")
@@ -382,7 +1543,7 @@ fn is_no_server_error(msg: &str) -> bool {
         || lower.contains("error connecting to")
 }
 
-fn last_non_empty_line(lines: &[String]) -> Option<&str> {
+pub fn last_non_empty_line(lines: &[String]) -> Option<&str> {
     for line in lines.iter().rev() {
         let trimmed = line.trim();
         if !trimmed.is_empty() {
@@ -398,24 +1559,338 @@ mod tests {
     use pretty_assertions::assert_eq;
 
     #[test]
-    fn parse_session_list_handles_valid_rows() {
-        let raw = "codex\t0\t1\tTue Feb 18 12:00:00 2026\nclaude\t1\t2\tTue Feb 18 13:00:00 2026\n";
-        let parsed = parse_session_list(raw).expect("should parse");
+    fn socket_flag_distinguishes_names_from_paths() {
+        assert_eq!(socket_flag("lattice"), "-L");
+        assert_eq!(socket_flag("/tmp/lattice.sock"), "-S");
+        assert_eq!(socket_flag("./lattice.sock"), "-S");
+    }
+
+    #[test]
+    fn split_layouts_round_trip_through_names() {
+        let mut layout = SplitLayout::default();
+        for _ in 0..4 {
+            assert_eq!(SplitLayout::parse(layout.name()), Some(layout));
+            layout = layout.next();
+        }
+        assert_eq!(layout, SplitLayout::EvenHorizontal);
+        assert_eq!(SplitLayout::parse(" tiled "), Some(SplitLayout::Tiled));
+        assert_eq!(SplitLayout::parse("main-horizontal"), None);
+    }
+
+    #[test]
+    fn hotkeys_parse_into_tables() {
+        let key = HotKey::parse(HotKey::RETURN_DEFAULT).expect("parse");
+        assert_eq!((key.prefix, key.key.as_str()), (false, "F12"));
+        let args = key.return_args(None);
+        assert_eq!(args[..4], ["bind-key", "-T", "root", "F12"]);
+        assert_eq!(
+            args[7],
+            "run-shell -C \"switch-client -t =#{q:@lattice_return}\""
+        );
+        assert_eq!(
+            args.last().map(String::as_str),
+            Some("if-shell -F '#{@lattice_detach}' detach-client \"send-keys F12\"")
+        );
+
+        let key = HotKey::parse("prefix+L").expect("parse");
+        assert_eq!((key.prefix, key.key.as_str()), (true, "L"));
+        assert_eq!(key.to_string(), "prefix+L");
+        assert_eq!(key.return_args(None)[2], "prefix");
+        assert_eq!(
+            key.pick_args("'/opt/$dir \"x\"/lattice' pick", None)
+                .last()
+                .map(String::as_str),
+            Some("display-popup -E -w 70% -h 60% \"'/opt/\\$dir \\\"x\\\"/lattice' pick\"")
+        );
+        assert_eq!(HotKey::parse("prefix M-r"), HotKey::parse("prefix+M-r"));
+
+        assert_eq!(HotKey::parse(""), None);
+        assert_eq!(HotKey::parse("prefix+"), None);
+        assert_eq!(HotKey::parse("C-a b"), None);
+    }
+
+    #[test]
+    fn hotkeys_fall_back_to_the_original_binding() {
+        let original =
+            Binding::parse("bind-key -r -T prefix a    display-message \"it's \\$HOME\"")
+                .expect("parse");
+        assert_eq!(
+            original,
+            Binding {
+                repeat: true,
+                command: "display-message \"it's \\$HOME\"".to_owned(),
+            }
+        );
+        let key = HotKey::parse(HotKey::PICK_DEFAULT).expect("parse");
+        let args = key.pick_args("lattice pick", Some(&original));
+        assert_eq!(args[..5], ["bind-key", "-r", "-T", "prefix", "a"]);
+        assert_eq!(
+            args.last().map(String::as_str),
+            Some("display-message \"it's \\$HOME\"")
+        );
+
+        let key = HotKey::parse(HotKey::RETURN_DEFAULT).expect("parse");
+        let original = Binding::parse("bind-key    -T root   F12   send-prefix").expect("parse");
+        assert_eq!(
+            key.return_args(Some(&original)).last().map(String::as_str),
+            Some("if-shell -F '#{@lattice_detach}' detach-client \"send-prefix\"")
+        );
+        // an unbound prefix key does nothing outside marked sessions
+        let key = HotKey::parse("prefix+L").expect("parse");
+        assert_eq!(key.pick_args("lattice pick", None).len(), 8);
+
+        assert_eq!(quote(r#"say "a\b" $X"#), r#""say \"a\\b\" \$X""#);
+        assert_eq!(Binding::parse("unknown key: a"), None);
+    }
+
+    #[test]
+    fn parse_pane_list_handles_valid_rows() {
+        let raw = "codex\tclient-4242\t1\tTue Feb 18 12:00:00 2026\t1771416000\t1\t1\t%1\tcodex\t/src/app\t1771416005\t120\t2\t10\t4242\t0\t0\t\tapproval,settle=20\t20\tFixing\ttests\n\
+                   claude\t/dev/pts/3,client-4242\t2\tTue Feb 18 13:00:00 2026\t1771419600\t1\t0\t%2\t\t\t0\t0\t0\t0\t\t1\t2\t1\t\t\t\n";
+        let parsed = parse_pane_list(raw).expect("should parse");
 
         assert_eq!(parsed.len(), 2);
-        assert_eq!(parsed[0].name, "codex");
-        assert!(!parsed[0].attached);
-        assert_eq!(parsed[0].windows, 1);
-        assert!(parsed[1].attached);
+        let codex = &parsed[0];
+        assert_eq!(codex.session.name, "codex");
+        assert!(!codex.session.attached);
+        assert_eq!(codex.session.windows, 1);
+        assert_eq!(codex.session.created_unix, 1771416000);
+        assert_eq!(codex.session.pane_current_path, "/src/app");
+        // Tabs inside the title survive
+        assert_eq!(codex.session.pane_title, "Fixing\ttests");
+        assert_eq!(codex.session.notify_rule, "approval,settle=20");
+        assert_eq!(codex.session.alert_watch, Some(20));
+        assert!(codex.active);
+        assert_eq!(codex.session.pane_id, "%1");
+        assert_eq!(codex.session.pane_pid, 4242);
+        assert_eq!(codex.session.activity_unix, 1771416005);
+        assert_eq!(codex.key.history_size, 120);
+
+        let claude = &parsed[1];
+        assert!(claude.session.attached);
+        assert!(!claude.active);
+        assert_eq!(claude.session.current_command, "unknown");
+        assert_eq!(claude.session.alert_watch, None);
+        assert!(claude.agent);
+        assert_eq!(claude.session.panes[0].label(), "1.2");
     }
 
     #[test]
-    fn parse_session_list_rejects_invalid_rows() {
+    fn parse_activity_reads_keys_by_pane() {
+        let raw = "%1\t1771416005\t120\t2\t10\n%2\t1771416000\t0\t0\t0\nbroken\n";
+        let keys = parse_activity(raw);
+        assert_eq!(keys.len(), 2);
+        assert_eq!(
+            keys["%1"],
+            ActivityKey {
+                activity: 1771416005,
+                history_size: 120,
+                cursor: (2, 10),
+            }
+        );
+
+        let stamp = CaptureStamp {
+            key: keys["%1"],
+            captured_at: 1771416006,
+        };
+        assert!(stamp.is_current(keys["%1"]));
+        assert!(!stamp.is_current(keys["%2"]));
+        // Output in the second of the capture may have come after it.
+        let same_second = CaptureStamp {
+            captured_at: 1771416005,
+            ..stamp
+        };
+        assert!(!same_second.is_current(keys["%1"]));
+    }
+
+    #[test]
+    fn parse_pane_list_rejects_invalid_rows() {
         let raw = "codex\t0\n";
-        let err = parse_session_list(raw).expect_err("invalid row should fail");
+        let err = parse_pane_list(raw).err().expect("invalid row should fail");
         assert!(err.to_string().contains("unexpected tmux output line"));
     }
 
+    /// Stand-in tmux server with single-pane sessions that counts how many
+    /// tmux processes lattice would have forked.
+    struct FakeServer {
+        activity: Vec<u64>,
+        forks: usize,
+    }
+
+    impl FakeServer {
+        fn new(sessions: usize) -> Self {
+            Self {
+                activity: vec![1000; sessions],
+                forks: 0,
+            }
+        }
+
+        fn run(&mut self, args: &[&str]) -> Result<String> {
+            self.forks += 1;
+            match args[0] {
+                "list-panes" => Ok(self
+                    .activity
+                    .iter()
+                    .enumerate()
+                    .map(|(i, activity)| {
+                        format!(
+                            "lattice_codex_{i}\t\t1\tThu Jan  1 00:00:00 1970\t0\t1\t1\t%{i}\tcodex\t/tmp\t{activity}\t0\t0\t0\t0\t0\t0\t\t\t\t\n"
+                        )
+                    })
+                    .collect()),
+                "capture-pane" => Ok(format!("output of {}\n", args[3])),
+                other => Err(anyhow!("unexpected tmux {other}")),
+            }
+        }
+
+        fn refresh(&mut self, cache: &mut CaptureCache, visible: &[String]) -> usize {
+            let before = self.forks;
+            let sessions = snapshot(&mut |args| self.run(args), Server::Lattice, cache, visible)
+                .expect("snapshot");
+            assert_eq!(sessions.len(), self.activity.len());
+            self.forks - before
+        }
+    }
+
+    #[test]
+    fn fork_count_scales_with_changed_sessions_not_total() {
+        for n in [1, 5, 20, 50] {
+            let mut server = FakeServer::new(n);
+            let mut cache = CaptureCache::default();
+
+            // Cold cache: one listing plus one capture per session (the old
+            // per-session display-message + capture-pane was 1 + 2n).
+            assert_eq!(server.refresh(&mut cache, &[]), 1 + n);
+
+            // Nothing changed: a single listing regardless of session count.
+            assert_eq!(server.refresh(&mut cache, &[]), 1);
+
+            // The visible session is always recaptured.
+            let visible = vec!["lattice_codex_0".to_owned()];
+            assert_eq!(server.refresh(&mut cache, &visible), 2);
+
+            // Only sessions with new activity are recaptured.
+            server.activity[n - 1] = 2000;
+            assert_eq!(server.refresh(&mut cache, &[]), 2);
+        }
+    }
+
+    #[test]
+    fn snapshot_keeps_cached_preview_for_idle_sessions() {
+        let mut server = FakeServer::new(2);
+        let mut cache = CaptureCache::default();
+        server.refresh(&mut cache, &[]);
+
+        let sessions = snapshot(
+            &mut |args| server.run(args),
+            Server::Lattice,
+            &mut cache,
+            &[],
+        )
+        .expect("snapshot");
+        assert_eq!(sessions[1].last_line, "output of %1");
+    }
+
+    #[test]
+    fn snapshot_reports_the_agent_pane_and_lists_all_panes() {
+        // The user's shell pane is active, the agent's pane is marked.
+        let raw = "work\t\t2\tThu Jan  1 00:00:00 1970\t0\t1\t0\t%1\tclaude\t/src\t0\t0\t0\t0\t11\t0\t0\t1\t\t\t\n\
+                   work\t\t2\tThu Jan  1 00:00:00 1970\t0\t1\t1\t%2\tbash\t/src\t0\t0\t0\t0\t12\t0\t1\t\t\t\t\n\
+                   work\t\t2\tThu Jan  1 00:00:00 1970\t0\t0\t1\t%3\tvim\t/src\t0\t0\t0\t0\t13\t1\t0\t\t\t\t\n";
+        let mut run = |args: &[&str]| match args[0] {
+            "list-panes" => Ok(raw.to_owned()),
+            _ => Ok(format!("output of {}\n", args[3])),
+        };
+        let sessions = snapshot(&mut run, Server::Lattice, &mut CaptureCache::default(), &[])
+            .expect("snapshot");
+
+        assert_eq!(sessions.len(), 1);
+        let work = &sessions[0];
+        assert_eq!(work.pane_id, "%1");
+        assert_eq!(work.current_command, "claude");
+        assert_eq!(work.last_line, "output of %1");
+        let labels: Vec<String> = work.panes.iter().map(Pane::label).collect();
+        assert_eq!(labels, ["0.0", "0.1", "1.0"]);
+        assert_eq!(work.panes[1].pid, 12);
+        assert!(work.panes[1].active);
+    }
+
+    #[test]
+    fn snapshot_reports_a_remembered_agent_pane() {
+        // Nothing is marked; the agent was found in the inactive pane.
+        let raw = "work\t\t1\tThu Jan  1 00:00:00 1970\t0\t1\t0\t%1\tclaude\t/src\t0\t0\t0\t0\t11\t0\t0\t\t\t\t\n\
+                   work\t\t1\tThu Jan  1 00:00:00 1970\t0\t1\t1\t%2\tbash\t/src\t0\t0\t0\t0\t12\t0\t1\t\t\t\t\n";
+        let mut run = |args: &[&str]| match args[0] {
+            "list-panes" => Ok(raw.to_owned()),
+            _ => Ok(format!("output of {}\n", args[3])),
+        };
+        let mut cache = CaptureCache::default();
+        let listed = |run: &mut dyn FnMut(&[&str]) -> Result<String>, cache: &mut CaptureCache| {
+            snapshot(run, Server::Lattice, cache, &[]).expect("snapshot")[0].clone()
+        };
+        assert_eq!(listed(&mut run, &mut cache).pane_id, "%2");
+
+        let panes = HashMap::from([((Server::Lattice, "work".to_owned()), "%1".to_owned())]);
+        assert!(cache.remember_agent_panes(panes.clone()));
+        assert!(!cache.remember_agent_panes(panes));
+        let work = listed(&mut run, &mut cache);
+        assert_eq!(work.pane_id, "%1");
+        assert_eq!(work.last_line, "output of %1");
+        assert!(!work.panes[0].agent);
+    }
+
+    #[test]
+    fn send_text_pastes_through_a_buffer_of_its_own() {
+        let mut calls: Vec<(Vec<String>, Option<String>)> = Vec::new();
+        let mut run = |args: &[&str], input: Option<&str>| {
+            calls.push((
+                args.iter().map(|a| a.to_string()).collect(),
+                input.map(ToOwned::to_owned),
+            ));
+            Ok(String::new())
+        };
+        for text in ["first\nline", "second"] {
+            send_text_with(&mut run, "%3", text, Duration::ZERO).expect("send");
+        }
+
+        let buffer = |call: &(Vec<String>, Option<String>)| call.0[2].clone();
+        assert_eq!(calls.len(), 6);
+        assert_eq!(calls[0].0[..2], ["load-buffer", "-b"]);
+        assert_eq!(calls[0].1.as_deref(), Some("first\nline"));
+        assert_eq!(
+            calls[1].0,
+            [
+                "paste-buffer",
+                "-p",
+                "-d",
+                "-b",
+                &buffer(&calls[0]),
+                "-t",
+                "%3"
+            ]
+        );
+        assert_eq!(calls[2].0, ["send-keys", "-t", "%3", "Enter"]);
+        assert_ne!(buffer(&calls[0]), buffer(&calls[3]));
+    }
+
+    #[test]
+    fn failed_paste_deletes_its_buffer() {
+        let mut calls: Vec<String> = Vec::new();
+        let mut run = |args: &[&str], _: Option<&str>| {
+            calls.push(args.join(" "));
+            match args[0] {
+                "paste-buffer" => Err(anyhow!("can't find pane: %9")),
+                _ => Ok(String::new()),
+            }
+        };
+        let err = send_text_with(&mut run, "%9", "hi", Duration::ZERO).expect_err("no pane");
+        assert!(err.to_string().contains("failed to paste into %9"));
+
+        let buffer = calls[0].split(' ').nth(2).expect("buffer").to_owned();
+        assert_eq!(calls.len(), 3);
+        assert_eq!(calls[2], format!("delete-buffer -b {buffer}"));
+    }
+
     #[test]
     fn last_non_empty_line_skips_blank_lines() {
         let lines = vec![
@@ -426,4 +1901,35 @@ mod tests {
         ];
         assert_eq!(last_non_empty_line(&lines), Some("hello world"));
     }
+
+    #[test]
+    fn unwatching_removes_only_what_watching_set() {
+        let hook = |command: &[String]| {
+            (command[0] == "set-hook").then(|| command[command.len() - 2].clone())
+        };
+        let watch = watch_commands("work", "%3", 30, "'/tmp/$x \"y\"/lattice' hook");
+        // tmux reads the hook command back exactly as the shell quoted it.
+        assert_eq!(
+            watch[2].last().map(String::as_str),
+            Some(
+                "run-shell -b \"'/tmp/\\$x \\\"y\\\"/lattice' hook silence #{q:session_name} #{pane_id}\""
+            )
+        );
+        let mut set: Vec<String> = watch.iter().filter_map(|c| hook(c)).collect();
+        let unwatch = unwatch_commands("work", "%3");
+        let mut unset: Vec<String> = unwatch
+            .iter()
+            .filter(|c| c[0] == "set-hook")
+            .map(|c| c[c.len() - 1].clone())
+            .collect();
+        set.sort();
+        unset.sort();
+        assert_eq!(set, unset);
+        assert!(set.iter().all(|hook| hook.ends_with("[100]")));
+        assert!(
+            unwatch
+                .iter()
+                .any(|c| c.join(" ") == "set-option -w -u -t %3 monitor-silence")
+        );
+    }
 }